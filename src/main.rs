@@ -1,10 +1,58 @@
 mod nes;
-use nes::Nes;
+use nes::{Config, ExpansionChip, Nes, RamPattern};
+
+const DEFAULT_ROM: &str = "testroms/donkey_kong.nes";
 
 fn main() {
-    let rom_path = "testroms/donkey_kong.nes";
+    let mut rom_path = DEFAULT_ROM.to_string();
+    let mut config = Config::default();
+
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--no-sprite-limit" => config.no_sprite_limit = true,
+            "--bus-conflicts" => config.bus_conflicts = true,
+            _ if arg.starts_with("--zip-entry=") => {
+                config.archive_entry = Some(arg.trim_start_matches("--zip-entry=").to_string());
+            }
+            _ if arg.starts_with("--patch=") => {
+                config.patch = Some(arg.trim_start_matches("--patch=").into());
+            }
+            _ if arg.starts_with("--fds-bios=") => {
+                config.fds_bios = Some(arg.trim_start_matches("--fds-bios=").into());
+            }
+            _ if arg.starts_with("--ram=") => {
+                match RamPattern::from_name(arg.trim_start_matches("--ram=")) {
+                    Some(pattern) => config.ram_pattern = pattern,
+                    None => {
+                        eprintln!("nesemu: expected --ram=zeros|ff|random|fceux, got {arg}");
+                        return;
+                    }
+                }
+            }
+            _ if arg.starts_with("--magic=") => {
+                match u8::from_str_radix(arg.trim_start_matches("--magic=").trim_start_matches('$'), 16) {
+                    Ok(magic) => config.magic_constant = Some(magic),
+                    Err(_) => {
+                        eprintln!("nesemu: expected --magic=<hex byte>, got {arg}");
+                        return;
+                    }
+                }
+            }
+            _ if arg.starts_with("--volume-") => {
+                if !parse_volume(&arg, &mut config) {
+                    eprintln!("nesemu: expected --volume-<chip>=<level>, got {arg}");
+                    return;
+                }
+            }
+            _ if arg.starts_with("--") => {
+                eprintln!("nesemu: unknown option {arg}");
+                return;
+            }
+            _ => rom_path = arg,
+        }
+    }
 
-    let mut nes = match Nes::new(rom_path) {
+    let mut nes = match Nes::new(&rom_path, config) {
         Ok(nes) => nes,
         Err(e) => {
             eprintln!("nesemu failed: {e}");
@@ -14,3 +62,18 @@ fn main() {
 
     nes.run();
 }
+
+/// Parse `--volume-<chip>=<level>`, e.g. `--volume-vrc7=0.5`.
+fn parse_volume(arg: &str, config: &mut Config) -> bool {
+    let Some((chip, level)) = arg.trim_start_matches("--volume-").split_once('=') else {
+        return false;
+    };
+
+    match (ExpansionChip::from_name(chip), level.parse::<f32>()) {
+        (Some(chip), Ok(level)) if level >= 0.0 => {
+            config.expansion_volumes.set(chip, level);
+            true
+        }
+        _ => false,
+    }
+}
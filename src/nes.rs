@@ -1,38 +1,141 @@
 mod apu;
+mod battery;
 mod bus;
 mod cartridge;
+mod config;
 mod cpu;
 mod joypad;
+mod mapper;
 mod ppu;
 mod renderer;
 
+use std::cell::RefCell;
+use std::rc::Rc;
 use std::sync::{Arc, Mutex};
 
+pub use apu::expansion::ExpansionChip;
+pub use bus::RamPattern;
+pub use config::Config;
+
+use apu::Apu;
+use battery::BatterySave;
 use bus::Bus;
-use cartridge::Cartridge;
+use cartridge::{Cartridge, CartridgeError};
 use cpu::Cpu;
+use mapper::Mapper;
 use ppu::Ppu;
-use renderer::Renderer;
+use renderer::{Input, Renderer};
+
+/// Frames between writes of battery-backed PRG RAM (~10 seconds).
+const BATTERY_FLUSH_FRAMES: u32 = 600;
 
 pub struct Nes {
     cpu: Cpu,
     renderer: Renderer,
+    mapper: Rc<RefCell<dyn Mapper>>,
+    battery: Option<BatterySave>,
+    cartridge: Cartridge, // As loaded from the ROM, for building the board again on power cycle
+    config: Config,
+    audio_buffer: Arc<Mutex<Vec<f32>>>,
 }
 
 impl Nes {
-    pub fn new(rom_path: &str) -> Result<Nes, String> {
-        let cartridge = Cartridge::new(rom_path)?;
-        let mirroring = cartridge.mirroring;
-        let ppu = Ppu::new(mirroring);
+    pub fn new(rom_path: &str, config: Config) -> Result<Nes, CartridgeError> {
+        let cartridge = Cartridge::new(rom_path, &config)?;
+        println!(
+            "Mapper: {}.{} ({:?} header, {:?}, {:?}, {:?})",
+            cartridge.mapper,
+            cartridge.submapper,
+            cartridge.format,
+            cartridge.timing,
+            cartridge.console_type,
+            cartridge.expansion_device
+        );
+        println!(
+            "PRG ROM: {} KB, CHR: {} KB, PRG RAM: {} KB + {} KB battery, CHR RAM: {} KB + {} KB battery",
+            cartridge.prg_rom.len() / 1024,
+            cartridge.chr.len() / 1024,
+            cartridge.prg_ram_size / 1024,
+            cartridge.prg_nvram_size / 1024,
+            cartridge.chr_ram_size / 1024,
+            cartridge.chr_nvram_size / 1024
+        );
+
+        let mut battery = if cartridge.has_battery {
+            Some(BatterySave::new(rom_path))
+        } else {
+            None
+        };
 
         // Shared audio buffer between APU and SDL2 audio callback
         let audio_buffer = Arc::new(Mutex::new(Vec::<f32>::with_capacity(44100)));
 
-        let bus = Bus::new(cartridge, ppu, audio_buffer.clone());
-        let cpu = Cpu::new(bus);
-        let renderer = Renderer::new(audio_buffer);
+        let (cpu, mapper) = Nes::build(&cartridge, &mut battery, &config, audio_buffer.clone())?;
+        let renderer = Renderer::new(audio_buffer.clone());
+
+        Ok(Nes {
+            cpu,
+            renderer,
+            mapper,
+            battery,
+            cartridge,
+            config,
+            audio_buffer,
+        })
+    }
+
+    /// Build the console around a fresh copy of the cartridge, with the
+    /// battery save loaded into it.
+    fn build(
+        cartridge: &Cartridge,
+        battery: &mut Option<BatterySave>,
+        config: &Config,
+        audio_buffer: Arc<Mutex<Vec<f32>>>,
+    ) -> Result<(Cpu, Rc<RefCell<dyn Mapper>>), CartridgeError> {
+        let mut cartridge = cartridge.clone();
+        if let Some(battery) = battery {
+            battery.load(&mut cartridge);
+        }
+
+        let mapper = mapper::new(cartridge, config)?;
+        let mut ppu = Ppu::new(mapper.clone());
+        ppu.set_sprite_limit(!config.no_sprite_limit);
+
+        let mut apu = Apu::new(audio_buffer);
+        apu.set_expansion_volumes(config.expansion_volumes.clone());
+
+        let bus = Bus::new(mapper.clone(), ppu, apu, config.ram_pattern);
+        let mut cpu = Cpu::new(bus);
+        if let Some(magic_constant) = config.magic_constant {
+            cpu.set_magic_constant(magic_constant);
+        }
+
+        Ok((cpu, mapper))
+    }
 
-        Ok(Nes { cpu, renderer })
+    /// Press the reset button. RAM and the board's registers are kept, the
+    /// CPU jumps through the reset vector and the PPU and APU are reset.
+    pub fn reset(&mut self) {
+        self.mapper.borrow_mut().reset();
+        self.cpu.reset();
+    }
+
+    /// Switch the console off and on again. Everything but the battery save
+    /// is lost and CPU RAM comes up in the configured power-on pattern.
+    pub fn power_cycle(&mut self) -> Result<(), CartridgeError> {
+        self.flush_battery();
+
+        let (cpu, mapper) = Nes::build(
+            &self.cartridge,
+            &mut self.battery,
+            &self.config,
+            self.audio_buffer.clone(),
+        )?;
+        self.cpu = cpu;
+        self.mapper = mapper;
+        self.cpu.power_up();
+
+        Ok(())
     }
 
     pub fn run(&mut self) {
@@ -42,6 +145,8 @@ impl Nes {
 
         self.cpu.power_up();
 
+        let mut frames_since_flush = 0;
+
         loop {
             let frame_start = Instant::now();
 
@@ -57,14 +162,33 @@ impl Nes {
 
             // Poll SDL events and handle input
             match self.renderer.poll_events() {
-                None => return, // Quit requested
+                None => {
+                    // Quit requested
+                    self.flush_battery();
+                    return;
+                }
                 Some(key_events) => {
-                    for (button, pressed) in key_events {
-                        self.cpu.set_joypad_button(button, pressed);
+                    for input in key_events {
+                        match input {
+                            Input::Button(button, pressed) => self.cpu.set_joypad_button(button, pressed),
+                            Input::Reset => self.reset(),
+                            Input::PowerCycle => {
+                                if let Err(e) = self.power_cycle() {
+                                    eprintln!("Power cycle failed: {e}");
+                                }
+                            }
+                            Input::SwitchDiskSide => self.mapper.borrow_mut().switch_disk_side(),
+                        }
                     }
                 }
             }
 
+            frames_since_flush += 1;
+            if frames_since_flush >= BATTERY_FLUSH_FRAMES {
+                self.flush_battery();
+                frames_since_flush = 0;
+            }
+
             // Frame timing — sleep if we finished early to maintain ~60 FPS
             let elapsed = frame_start.elapsed();
             if elapsed < FRAME_DURATION {
@@ -72,4 +196,11 @@ impl Nes {
             }
         }
     }
+
+    /// Write battery-backed PRG RAM or the disk to the .sav file if it changed.
+    fn flush_battery(&mut self) {
+        if let Some(battery) = &mut self.battery {
+            battery.flush(self.mapper.borrow().cartridge().save_data());
+        }
+    }
 }
@@ -16,6 +16,13 @@ pub mod pulse;
 pub mod triangle;
 pub mod noise;
 pub mod dmc;
+pub mod expansion;
+pub mod fds;
+pub mod mmc5;
+pub mod n163;
+pub mod sunsoft5b;
+pub mod vrc6;
+pub mod vrc7;
 
 use std::sync::{Arc, Mutex};
 
@@ -23,6 +30,7 @@ use pulse::PulseChannel;
 use triangle::TriangleChannel;
 use noise::NoiseChannel;
 use dmc::DmcChannel;
+use expansion::{ExpansionAudio, ExpansionVolumes};
 
 /// NES CPU clock rate (~1.789773 MHz NTSC)
 const CPU_FREQ: f64 = 1_789_773.0;
@@ -123,6 +131,8 @@ pub struct Apu {
     pulse_table: [f32; 31],
     tnd_table: [f32; 203],
 
+    expansion_volumes: ExpansionVolumes,
+
     // NES hardware-accurate filter chain
     hp_37hz: FirstOrderFilter,    // Capacitor coupling
     hp_90hz: FirstOrderFilter,    // AC coupling on output
@@ -154,6 +164,7 @@ impl Apu {
             audio_buffer,
             pulse_table: pulse_table(),
             tnd_table: tnd_table(),
+            expansion_volumes: ExpansionVolumes::default(),
             hp_37hz: FirstOrderFilter::high_pass(37.0, sr),
             hp_90hz: FirstOrderFilter::high_pass(90.0, sr),
             lp_14khz: FirstOrderFilter::low_pass(14000.0, sr),
@@ -161,11 +172,35 @@ impl Apu {
         }
     }
 
-    /// Called every CPU cycle.
+    pub fn set_expansion_volumes(&mut self, volumes: ExpansionVolumes) {
+        self.expansion_volumes = volumes;
+    }
+
+    /// Console reset. All channels are silenced as if $4015 was cleared, the
+    /// triangle restarts its waveform and the DMC output loses its upper 6
+    /// bits. The frame counter restarts in its current mode with the IRQ
+    /// enabled, as on the 2A03E/G (the letterless 2A03 keeps counting).
+    pub fn reset(&mut self) {
+        self.write_status(0);
+        self.triangle.reset();
+        self.dmc.reset();
+        self.frame_interrupt = false;
+        self.write_frame_counter(self.frame_counter_mode << 7);
+    }
+
+    /// Called every CPU cycle, along with the cartridge sound chip if any.
     /// Returns Some(address) if the DMC needs a memory read.
-    pub fn tick(&mut self) -> Option<u16> {
+    pub fn tick(&mut self, expansion: Option<&mut dyn ExpansionAudio>) -> Option<u16> {
         self.cpu_cycles += 1;
 
+        let expansion = match expansion {
+            Some(chip) => {
+                chip.tick();
+                chip.output() * self.expansion_volumes.get(chip.chip())
+            }
+            None => 0.0,
+        };
+
         // Triangle timer ticks at CPU rate
         self.triangle.tick_timer();
 
@@ -183,7 +218,7 @@ impl Apu {
 
         // Accumulate raw mix for decimation averaging
         // (~40.6 raw samples averaged per output sample: 1789773/44100)
-        let raw = self.mix() as f64;
+        let raw = self.mix(expansion) as f64;
         self.sample_sum += raw;
         self.sample_count += 1;
 
@@ -226,6 +261,11 @@ impl Apu {
         dmc_read
     }
 
+    /// Whether the frame counter or the DMC is asserting the CPU IRQ line.
+    pub fn irq_pending(&self) -> bool {
+        self.frame_interrupt || self.dmc.interrupt_flag
+    }
+
     /// Feed a byte read from memory into the DMC sample buffer
     pub fn dmc_fill_buffer(&mut self, value: u8) {
         self.dmc.fill_sample_buffer(value);
@@ -283,8 +323,9 @@ impl Apu {
         self.noise.tick_length_counter();
     }
 
-    /// Mix all channels using the NES non-linear mixing formula
-    fn mix(&self) -> f32 {
+    /// Mix all channels using the NES non-linear mixing formula, plus the
+    /// cartridge expansion audio which is summed in after the APU's DAC
+    fn mix(&self, expansion: f32) -> f32 {
         let p1 = self.pulse1.output() as usize;
         let p2 = self.pulse2.output() as usize;
         let t = self.triangle.output() as usize;
@@ -295,7 +336,7 @@ impl Apu {
         let tnd_idx = (3 * t + 2 * n + d).min(202);
         let tnd_out = self.tnd_table[tnd_idx];
 
-        pulse_out + tnd_out
+        pulse_out + tnd_out + expansion
     }
 
     // ─── Register writes ─────────────────────────────────────────────────
@@ -95,6 +95,11 @@ impl DmcChannel {
         self.interrupt_flag = false;
     }
 
+    /// Console reset: only bit 0 of the output level survives.
+    pub fn reset(&mut self) {
+        self.output_level &= 1;
+    }
+
     pub fn bytes_remaining(&self) -> u16 {
         self.bytes_remaining
     }
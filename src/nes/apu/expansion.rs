@@ -0,0 +1,91 @@
+/// Sound chips found on cartridges.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ExpansionChip {
+    Mmc5,
+    Vrc6,
+    Vrc7,
+    N163,
+    Sunsoft5b,
+    Fds,
+}
+
+impl ExpansionChip {
+    /// Look up a chip by its lowercase name, as given on the command line.
+    pub fn from_name(name: &str) -> Option<ExpansionChip> {
+        match name {
+            "mmc5" => Some(ExpansionChip::Mmc5),
+            "vrc6" => Some(ExpansionChip::Vrc6),
+            "vrc7" => Some(ExpansionChip::Vrc7),
+            "n163" => Some(ExpansionChip::N163),
+            "5b" => Some(ExpansionChip::Sunsoft5b),
+            "fds" => Some(ExpansionChip::Fds),
+            _ => None,
+        }
+    }
+}
+
+/// Gain applied to each expansion chip before it is mixed with the APU.
+/// 1.0 keeps the chip at its level relative to the APU on real hardware.
+#[derive(Clone)]
+pub struct ExpansionVolumes {
+    pub mmc5: f32,
+    pub vrc6: f32,
+    pub vrc7: f32,
+    pub n163: f32,
+    pub sunsoft5b: f32,
+    pub fds: f32,
+}
+
+impl ExpansionVolumes {
+    pub fn get(&self, chip: ExpansionChip) -> f32 {
+        match chip {
+            ExpansionChip::Mmc5 => self.mmc5,
+            ExpansionChip::Vrc6 => self.vrc6,
+            ExpansionChip::Vrc7 => self.vrc7,
+            ExpansionChip::N163 => self.n163,
+            ExpansionChip::Sunsoft5b => self.sunsoft5b,
+            ExpansionChip::Fds => self.fds,
+        }
+    }
+
+    pub fn set(&mut self, chip: ExpansionChip, volume: f32) {
+        match chip {
+            ExpansionChip::Mmc5 => self.mmc5 = volume,
+            ExpansionChip::Vrc6 => self.vrc6 = volume,
+            ExpansionChip::Vrc7 => self.vrc7 = volume,
+            ExpansionChip::N163 => self.n163 = volume,
+            ExpansionChip::Sunsoft5b => self.sunsoft5b = volume,
+            ExpansionChip::Fds => self.fds = volume,
+        }
+    }
+}
+
+impl Default for ExpansionVolumes {
+    fn default() -> Self {
+        ExpansionVolumes {
+            mmc5: 1.0,
+            vrc6: 1.0,
+            vrc7: 1.0,
+            n163: 1.0,
+            sunsoft5b: 1.0,
+            fds: 1.0,
+        }
+    }
+}
+
+/// Sound hardware on the cartridge, mixed with the internal channels.
+///
+/// The mapper owns the chip and forwards its register writes. `Apu::tick`
+/// clocks it once per CPU cycle and adds its output, scaled by the chip's
+/// volume, to the mix before decimation.
+pub trait ExpansionAudio {
+    /// Which chip this is, for picking its volume.
+    fn chip(&self) -> ExpansionChip;
+
+    /// Advance the chip by one CPU cycle.
+    fn tick(&mut self);
+
+    /// Current output on the scale of the internal mix, where both pulse
+    /// channels at full volume give about 0.26.
+    fn output(&self) -> f32;
+}
@@ -0,0 +1,270 @@
+use super::expansion::{ExpansionAudio, ExpansionChip};
+
+/// Mix level of one output step. The output is wave (0-63) * gain (0-32), and
+/// at full volume the channel is about 2.4 times as loud as an APU pulse.
+const OUTPUT_STEP: f32 = 0.36 / 2016.0;
+
+/// Master volume from $4089 bits 0-1: 2/2, 2/3, 2/4, 2/5
+const MASTER_VOLUMES: [f32; 4] = [1.0, 2.0 / 3.0, 0.5, 0.4];
+
+/// Pitch adjustment for each modulation table entry; None resets the counter
+const MOD_STEPS: [Option<i8>; 8] = [Some(0), Some(1), Some(2), Some(4), None, Some(-4), Some(-2), Some(-1)];
+
+/// Coefficient of the one-pole low-pass filter (~2 kHz) on the FDS output
+const FILTER_ALPHA: f32 = 0.007;
+
+/// Volume or modulation envelope: a 6-bit gain ramped up or down.
+struct FdsEnvelope {
+    /// Bit 7 of the control register: use `gain` directly
+    disabled: bool,
+    increase: bool,
+    speed: u8,
+    gain: u8,
+    counter: u32,
+}
+
+impl FdsEnvelope {
+    fn new() -> Self {
+        FdsEnvelope {
+            disabled: true,
+            increase: false,
+            speed: 0,
+            gain: 0,
+            counter: 0,
+        }
+    }
+
+    /// $4080/$4084: bit 7 disable, bit 6 increase, bits 0-5 speed (or gain when disabled)
+    fn write(&mut self, value: u8, master_speed: u8) {
+        self.disabled = value & 0x80 != 0;
+        self.increase = value & 0x40 != 0;
+        self.speed = value & 0x3F;
+        if self.disabled {
+            self.gain = self.speed;
+        }
+        self.reset_counter(master_speed);
+    }
+
+    fn reset_counter(&mut self, master_speed: u8) {
+        self.counter = 8 * (master_speed as u32 + 1) * (self.speed as u32 + 1);
+    }
+
+    fn tick(&mut self, master_speed: u8) {
+        if self.disabled {
+            return;
+        }
+
+        if self.counter > 0 {
+            self.counter -= 1;
+            return;
+        }
+        self.reset_counter(master_speed);
+
+        if self.increase {
+            if self.gain < 32 {
+                self.gain += 1;
+            }
+        } else if self.gain > 0 {
+            self.gain -= 1;
+        }
+    }
+}
+
+/// Famicom Disk System expansion audio
+///
+/// One 64-step wavetable channel whose pitch is bent by a modulation unit
+/// stepping through a 32-entry table of pitch deltas.
+///
+/// $4040-$407F: Wavetable (6-bit samples, writable while $4089 bit 7 is set)
+/// $4080: Volume envelope      $4082-$4083: Wave frequency, halt (bit 7),
+///                                          envelope disable (bit 6)
+/// $4084: Mod envelope         $4085: Mod counter (7-bit signed)
+/// $4086-$4087: Mod frequency, mod halt (bit 7)
+/// $4088: Mod table write (while the mod unit is halted)
+/// $4089: Wave write enable (bit 7), master volume (bits 0-1)
+/// $408A: Envelope speed multiplier
+/// $4090/$4092: Volume/mod gain reads
+pub struct FdsAudio {
+    wave_table: [u8; 64],
+    wave_write: bool,
+    wave_frequency: u16,
+    wave_halt: bool,
+    wave_accumulator: u32,
+    wave_output: u8,
+
+    envelopes_disabled: bool,
+    envelope_speed: u8,
+    volume: FdsEnvelope,
+    sweep: FdsEnvelope,
+
+    mod_table: [u8; 64],
+    mod_position: usize,
+    mod_counter: i8,
+    mod_frequency: u16,
+    mod_halt: bool,
+    mod_accumulator: u32,
+
+    master_volume: usize,
+    filtered: f32,
+}
+
+impl FdsAudio {
+    pub fn new() -> Self {
+        FdsAudio {
+            wave_table: [0; 64],
+            wave_write: false,
+            wave_frequency: 0,
+            wave_halt: true,
+            wave_accumulator: 0,
+            wave_output: 0,
+            envelopes_disabled: false,
+            envelope_speed: 0xE8,
+            volume: FdsEnvelope::new(),
+            sweep: FdsEnvelope::new(),
+            mod_table: [0; 64],
+            mod_position: 0,
+            mod_counter: 0,
+            mod_frequency: 0,
+            mod_halt: true,
+            mod_accumulator: 0,
+            master_volume: 0,
+            filtered: 0.0,
+        }
+    }
+
+    pub fn read_register(&self, addr: u16) -> Option<u8> {
+        match addr {
+            0x4040..=0x407F => Some(self.wave_table[(addr - 0x4040) as usize] | 0x40),
+            0x4090 => Some(self.volume.gain | 0x40),
+            0x4092 => Some(self.sweep.gain | 0x40),
+            _ => None,
+        }
+    }
+
+    pub fn write_register(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x4040..=0x407F if self.wave_write => {
+                self.wave_table[(addr - 0x4040) as usize] = value & 0x3F;
+            }
+            0x4080 => self.volume.write(value, self.envelope_speed),
+            0x4082 => self.wave_frequency = (self.wave_frequency & 0x0F00) | value as u16,
+            0x4083 => {
+                self.wave_frequency = (self.wave_frequency & 0x00FF) | ((value as u16 & 0x0F) << 8);
+                self.wave_halt = value & 0x80 != 0;
+                self.envelopes_disabled = value & 0x40 != 0;
+                if self.wave_halt {
+                    self.wave_accumulator = 0;
+                }
+            }
+            0x4084 => self.sweep.write(value, self.envelope_speed),
+            0x4085 => {
+                // Sign-extend the 7-bit counter
+                self.mod_counter = ((value & 0x7F) << 1) as i8 >> 1;
+            }
+            0x4086 => self.mod_frequency = (self.mod_frequency & 0x0F00) | value as u16,
+            0x4087 => {
+                self.mod_frequency = (self.mod_frequency & 0x00FF) | ((value as u16 & 0x0F) << 8);
+                self.mod_halt = value & 0x80 != 0;
+                if self.mod_halt {
+                    self.mod_accumulator = 0;
+                }
+            }
+            0x4088 if self.mod_halt => {
+                // Each write fills two steps of the 64-step table
+                self.mod_table[self.mod_position] = value & 0x07;
+                self.mod_table[(self.mod_position + 1) & 0x3F] = value & 0x07;
+                self.mod_position = (self.mod_position + 2) & 0x3F;
+            }
+            0x4089 => {
+                self.wave_write = value & 0x80 != 0;
+                self.master_volume = (value & 0x03) as usize;
+            }
+            0x408A => self.envelope_speed = value,
+            _ => {}
+        }
+    }
+
+    /// Wave frequency after modulation by the mod counter and mod gain.
+    fn modulated_frequency(&self) -> u32 {
+        let pitch = self.wave_frequency as i32;
+        if self.mod_halt {
+            return pitch as u32;
+        }
+
+        let mut temp = self.mod_counter as i32 * self.sweep.gain as i32;
+        let remainder = temp & 0x0F;
+        temp >>= 4;
+        if remainder > 0 && temp & 0x80 == 0 {
+            temp += if self.mod_counter < 0 { -1 } else { 2 };
+        }
+        if temp >= 192 {
+            temp -= 256;
+        } else if temp < -64 {
+            temp += 256;
+        }
+
+        temp *= pitch;
+        let remainder = temp & 0x3F;
+        temp >>= 6;
+        if remainder >= 32 {
+            temp += 1;
+        }
+
+        (pitch + temp).max(0) as u32
+    }
+
+    fn tick_modulator(&mut self) {
+        if self.mod_halt {
+            return;
+        }
+
+        self.mod_accumulator += self.mod_frequency as u32;
+        if self.mod_accumulator < 0x10000 {
+            return;
+        }
+        self.mod_accumulator &= 0xFFFF;
+
+        match MOD_STEPS[self.mod_table[self.mod_position] as usize] {
+            Some(step) => {
+                // The counter is 7-bit signed and wraps
+                let counter = self.mod_counter.wrapping_add(step);
+                self.mod_counter = ((counter as u8) << 1) as i8 >> 1;
+            }
+            None => self.mod_counter = 0,
+        }
+        self.mod_position = (self.mod_position + 1) & 0x3F;
+    }
+}
+
+impl ExpansionAudio for FdsAudio {
+    fn chip(&self) -> ExpansionChip {
+        ExpansionChip::Fds
+    }
+
+    fn tick(&mut self) {
+        if !self.envelopes_disabled && !self.wave_halt && self.envelope_speed != 0 {
+            self.volume.tick(self.envelope_speed);
+            self.sweep.tick(self.envelope_speed);
+        }
+
+        self.tick_modulator();
+
+        if !self.wave_halt {
+            let frequency = self.modulated_frequency();
+            self.wave_accumulator = (self.wave_accumulator + frequency) & 0x3F_FFFF;
+        }
+
+        // The output holds its last value while the wavetable is writable
+        if !self.wave_write {
+            self.wave_output = self.wave_table[(self.wave_accumulator >> 16) as usize & 0x3F];
+        }
+
+        let gain = self.volume.gain.min(32) as f32;
+        let sample = self.wave_output as f32 * gain * MASTER_VOLUMES[self.master_volume] * OUTPUT_STEP;
+        self.filtered += (sample - self.filtered) * FILTER_ALPHA;
+    }
+
+    fn output(&self) -> f32 {
+        self.filtered
+    }
+}
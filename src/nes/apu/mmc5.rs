@@ -0,0 +1,138 @@
+use super::expansion::{ExpansionAudio, ExpansionChip};
+use super::pulse::PulseChannel;
+
+/// CPU cycles between envelope and length counter clocks (~240 Hz)
+const FRAME_PERIOD: u16 = 7457;
+
+/// MMC5 expansion audio
+///
+/// Two pulse channels that match the APU pulses without the sweep unit, and
+/// an 8-bit PCM channel. With no frame counter on the cartridge, envelopes
+/// and length counters are clocked at a fixed 240 Hz.
+///
+/// $5000-$5003: Pulse 1      $5004-$5007: Pulse 2
+/// $5010: PCM mode (bit 0: read mode) and IRQ enable (bit 7)
+/// $5011: PCM raw output (write mode)
+/// $5015: Pulse enable / length counter status
+pub struct Mmc5Audio {
+    pulse1: PulseChannel,
+    pulse2: PulseChannel,
+
+    pcm_read_mode: bool,
+    pcm_irq_enabled: bool,
+    pcm_irq: bool,
+    pcm_output: u8,
+
+    frame_timer: u16,
+    even_cycle: bool,
+
+    pulse_table: [f32; 31],
+}
+
+impl Mmc5Audio {
+    pub fn new() -> Self {
+        Mmc5Audio {
+            pulse1: PulseChannel::without_sweep(),
+            pulse2: PulseChannel::without_sweep(),
+            pcm_read_mode: false,
+            pcm_irq_enabled: false,
+            pcm_irq: false,
+            pcm_output: 0,
+            frame_timer: 0,
+            even_cycle: false,
+            pulse_table: super::pulse_table(),
+        }
+    }
+
+    pub fn write_register(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x5000 => self.pulse1.write_control(value),
+            0x5002 => self.pulse1.write_timer_lo(value),
+            0x5003 => self.pulse1.write_timer_hi(value),
+            0x5004 => self.pulse2.write_control(value),
+            0x5006 => self.pulse2.write_timer_lo(value),
+            0x5007 => self.pulse2.write_timer_hi(value),
+            0x5010 => {
+                self.pcm_read_mode = value & 0x01 != 0;
+                self.pcm_irq_enabled = value & 0x80 != 0;
+            }
+            // Writing 0 is ignored in write mode, as 0 ends a sample in read mode
+            0x5011 if !self.pcm_read_mode && value != 0 => self.pcm_output = value,
+            0x5015 => {
+                self.pulse1.set_enabled(value & 0x01 != 0);
+                self.pulse2.set_enabled(value & 0x02 != 0);
+            }
+            _ => {}
+        }
+    }
+
+    /// $5010 read — PCM IRQ flag, cleared by the read
+    pub fn read_pcm_status(&mut self) -> u8 {
+        let status = if self.pcm_irq { 0x80 } else { 0x00 };
+        self.pcm_irq = false;
+        status
+    }
+
+    /// $5015 read — Pulse length counter status
+    pub fn read_status(&self) -> u8 {
+        let mut status = 0u8;
+        if self.pulse1.length_counter() > 0 { status |= 0x01; }
+        if self.pulse2.length_counter() > 0 { status |= 0x02; }
+        status
+    }
+
+    /// In read mode, the PCM channel plays bytes the CPU reads from $8000-$BFFF.
+    pub fn observe_prg_read(&mut self, addr: u16, value: u8) {
+        if !self.pcm_read_mode || !(0x8000..=0xBFFF).contains(&addr) {
+            return;
+        }
+
+        if value == 0 {
+            self.pcm_irq = true;
+        } else {
+            self.pcm_output = value;
+        }
+    }
+
+    pub fn irq_pending(&self) -> bool {
+        self.pcm_irq && self.pcm_irq_enabled
+    }
+}
+
+impl ExpansionAudio for Mmc5Audio {
+    fn chip(&self) -> ExpansionChip {
+        ExpansionChip::Mmc5
+    }
+
+    fn tick(&mut self) {
+        self.even_cycle = !self.even_cycle;
+        if self.even_cycle {
+            self.pulse1.tick_timer();
+            self.pulse2.tick_timer();
+        }
+
+        self.frame_timer += 1;
+        if self.frame_timer >= FRAME_PERIOD {
+            self.frame_timer = 0;
+            self.pulse1.tick_envelope();
+            self.pulse1.tick_length_counter();
+            self.pulse2.tick_envelope();
+            self.pulse2.tick_length_counter();
+        }
+    }
+
+    fn output(&self) -> f32 {
+        let pulse = self.pulse1.output() as usize + self.pulse2.output() as usize;
+        let pulse_out = self.pulse_table[pulse];
+
+        // PCM goes through the same kind of DAC as the DMC, at 8 bits
+        let pcm = self.pcm_output as f32 / 2.0;
+        let pcm_out = if pcm > 0.0 {
+            163.67 / (24329.0 / pcm + 100.0)
+        } else {
+            0.0
+        };
+
+        pulse_out + pcm_out
+    }
+}
@@ -0,0 +1,147 @@
+use super::expansion::{ExpansionAudio, ExpansionChip};
+
+/// CPU cycles spent on each channel before the chip moves to the next one
+const CYCLES_PER_CHANNEL: u8 = 15;
+
+/// Mix level of one output step. The channel output is (sample - 8) * volume,
+/// so a full volume wave spans about twice an APU pulse at full volume.
+const OUTPUT_STEP: f32 = 0.0024;
+
+/// Start of the channel registers in sound RAM; channel 7 is at $78-$7F
+const CHANNEL_BASE: usize = 0x40;
+
+/// Namco 163 expansion audio
+///
+/// Up to eight wavetable channels with 4-bit samples stored in 128 bytes of
+/// sound RAM, which also holds the channel registers at $40-$7F:
+///
+/// +0: Frequency bits 0-7      +1: Phase bits 0-7
+/// +2: Frequency bits 8-15     +3: Phase bits 8-15
+/// +4: Frequency bits 16-17 and wave length (256 - (value & $FC) samples)
+/// +5: Phase bits 16-23        +6: Wave address in samples
+/// +7: Volume (bits 0-3); at $7F also enabled channels - 1 (bits 4-6)
+///
+/// $F800: Sound RAM address (bits 0-6) and auto-increment (bit 7)
+/// $4800: Sound RAM data
+///
+/// The chip updates one channel every 15 CPU cycles and outputs only that
+/// channel until the next, so more channels mean a lower rate per channel
+/// and the multiplexing whine of the real hardware.
+pub struct N163Audio {
+    ram: [u8; 0x80],
+    address: u8,
+    auto_increment: bool,
+    /// Set by the mapper through $E000 bit 6
+    disabled: bool,
+
+    cycle: u8,
+    /// Channel currently being updated and output, counting down from 7
+    channel: usize,
+    output: f32,
+}
+
+impl N163Audio {
+    pub fn new() -> Self {
+        N163Audio {
+            ram: [0; 0x80],
+            address: 0,
+            auto_increment: false,
+            disabled: false,
+            cycle: 0,
+            channel: 7,
+            output: 0.0,
+        }
+    }
+
+    pub fn write_address(&mut self, value: u8) {
+        self.address = value & 0x7F;
+        self.auto_increment = value & 0x80 != 0;
+    }
+
+    pub fn read_data(&mut self) -> u8 {
+        let value = self.ram[self.address as usize];
+        self.advance_address();
+        value
+    }
+
+    pub fn write_data(&mut self, value: u8) {
+        self.ram[self.address as usize] = value;
+        self.advance_address();
+    }
+
+    pub fn set_disabled(&mut self, disabled: bool) {
+        self.disabled = disabled;
+    }
+
+    fn advance_address(&mut self) {
+        if self.auto_increment {
+            self.address = (self.address + 1) & 0x7F;
+        }
+    }
+
+    fn enabled_channels(&self) -> usize {
+        ((self.ram[0x7F] >> 4) & 0x07) as usize + 1
+    }
+
+    /// 4-bit sample `index` of the wavetable, two samples per byte, low nibble first
+    fn sample(&self, index: u8) -> u8 {
+        let byte = self.ram[(index >> 1) as usize & 0x7F];
+        if index & 1 == 0 {
+            byte & 0x0F
+        } else {
+            byte >> 4
+        }
+    }
+
+    /// Advance a channel's phase and return its current output.
+    fn update_channel(&mut self, channel: usize) -> i16 {
+        let base = CHANNEL_BASE + channel * 8;
+        let regs = &self.ram[base..base + 8];
+
+        let frequency = regs[0] as u32 | (regs[2] as u32) << 8 | (regs[4] as u32 & 0x03) << 16;
+        let length = 256 - (regs[4] & 0xFC) as u32;
+        let wave_address = regs[6];
+        let volume = (regs[7] & 0x0F) as i16;
+
+        let mut phase = regs[1] as u32 | (regs[3] as u32) << 8 | (regs[5] as u32) << 16;
+        phase = (phase + frequency) % (length << 16);
+
+        self.ram[base + 1] = phase as u8;
+        self.ram[base + 3] = (phase >> 8) as u8;
+        self.ram[base + 5] = (phase >> 16) as u8;
+
+        let sample = self.sample(((phase >> 16) as u8).wrapping_add(wave_address));
+        (sample as i16 - 8) * volume
+    }
+}
+
+impl ExpansionAudio for N163Audio {
+    fn chip(&self) -> ExpansionChip {
+        ExpansionChip::N163
+    }
+
+    fn tick(&mut self) {
+        if self.disabled {
+            self.output = 0.0;
+            return;
+        }
+
+        self.cycle += 1;
+        if self.cycle < CYCLES_PER_CHANNEL {
+            return;
+        }
+        self.cycle = 0;
+
+        let lowest = 8 - self.enabled_channels();
+        self.channel = if self.channel <= lowest || self.channel > 7 {
+            7
+        } else {
+            self.channel - 1
+        };
+        self.output = self.update_channel(self.channel) as f32 * OUTPUT_STEP;
+    }
+
+    fn output(&self) -> f32 {
+        self.output
+    }
+}
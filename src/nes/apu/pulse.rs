@@ -49,6 +49,7 @@ pub struct PulseChannel {
     sweep_divider: u8,
     sweep_reload: bool,
     channel_id: u8, // 1 or 2, affects sweep negate behavior
+    has_sweep: bool, // Expansion pulses (MMC5) have no sweep unit and never mute
 }
 
 impl PulseChannel {
@@ -75,6 +76,15 @@ impl PulseChannel {
             sweep_divider: 0,
             sweep_reload: false,
             channel_id,
+            has_sweep: true,
+        }
+    }
+
+    /// Pulse channel without a sweep unit, as found on the MMC5.
+    pub fn without_sweep() -> Self {
+        PulseChannel {
+            has_sweep: false,
+            ..PulseChannel::new(1)
         }
     }
 
@@ -189,6 +199,9 @@ impl PulseChannel {
     }
 
     fn is_sweep_muting(&self) -> bool {
+        if !self.has_sweep {
+            return false;
+        }
         self.timer_period < 8 || self.sweep_target_period() > 0x7FF
     }
 
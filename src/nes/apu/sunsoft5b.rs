@@ -0,0 +1,253 @@
+use super::expansion::{ExpansionAudio, ExpansionChip};
+
+/// The tone, noise and envelope generators are clocked every 16 CPU cycles
+const PRESCALER: u8 = 16;
+
+/// Mix level of one channel at full volume
+const CHANNEL_LEVEL: f32 = 0.15;
+
+/// Square wave channel: toggles every `period` generator clocks.
+#[derive(Clone, Copy)]
+struct Tone {
+    period: u16,
+    counter: u16,
+    high: bool,
+}
+
+impl Tone {
+    fn tick(&mut self) {
+        self.counter += 1;
+        if self.counter >= self.period.max(1) {
+            self.counter = 0;
+            self.high = !self.high;
+        }
+    }
+}
+
+/// Envelope shared by all channels: a 32-step ramp shaped by $0D.
+struct Envelope {
+    period: u16,
+    counter: u16,
+    /// Position in the current ramp, 0-31
+    step: u8,
+    attack: bool,
+    alternate: bool,
+    hold: bool,
+    continues: bool,
+    holding: bool,
+    /// Ramp direction, flipped by alternating shapes
+    rising: bool,
+}
+
+impl Envelope {
+    fn new() -> Self {
+        Envelope {
+            period: 0,
+            counter: 0,
+            step: 0,
+            attack: false,
+            alternate: false,
+            hold: false,
+            continues: false,
+            holding: true,
+            rising: false,
+        }
+    }
+
+    /// $0D write restarts the envelope.
+    /// Bit 3: continue, bit 2: attack, bit 1: alternate, bit 0: hold
+    fn write_shape(&mut self, value: u8) {
+        self.continues = value & 0x08 != 0;
+        self.attack = value & 0x04 != 0;
+        self.alternate = value & 0x02 != 0;
+        self.hold = value & 0x01 != 0;
+        self.rising = self.attack;
+        self.step = 0;
+        self.counter = 0;
+        self.holding = false;
+    }
+
+    fn tick(&mut self) {
+        if self.holding {
+            return;
+        }
+
+        self.counter += 1;
+        if self.counter < self.period.max(1) {
+            return;
+        }
+        self.counter = 0;
+
+        if self.step < 31 {
+            self.step += 1;
+            return;
+        }
+
+        // End of a ramp
+        if !self.continues {
+            self.holding = true;
+            self.rising = false;
+            self.step = 31;
+        } else if self.hold {
+            self.holding = true;
+            if self.alternate {
+                self.rising = !self.rising;
+            }
+        } else {
+            self.step = 0;
+            if self.alternate {
+                self.rising = !self.rising;
+            }
+        }
+    }
+
+    /// Current 5-bit level
+    fn level(&self) -> u8 {
+        if !self.continues && self.holding {
+            return 0;
+        }
+        if self.rising {
+            self.step
+        } else {
+            31 - self.step
+        }
+    }
+}
+
+/// Sunsoft 5B expansion audio
+///
+/// A YM2149F (AY-3-8910 family) inside the FME-7: three square wave channels,
+/// a noise generator and an envelope generator, mixed per channel.
+///
+/// $C000: Register select      $E000: Register data
+///
+/// $00-$05: Tone A/B/C period (12 bits, low byte then high nibble)
+/// $06: Noise period (5 bits)
+/// $07: Mixer: tone disable (bits 0-2), noise disable (bits 3-5), active low
+/// $08-$0A: Channel volume (bits 0-3), envelope mode (bit 4)
+/// $0B-$0C: Envelope period (16 bits)
+/// $0D: Envelope shape
+///
+/// Volume is logarithmic, 3 dB per step of the 4-bit channel volume and
+/// 1.5 dB per step of the 5-bit envelope.
+pub struct Sunsoft5bAudio {
+    address: u8,
+    tones: [Tone; 3],
+    noise_period: u8,
+    noise_counter: u8,
+    noise_lfsr: u32,
+    mixer: u8,
+    volumes: [u8; 3],
+    envelope: Envelope,
+
+    prescaler: u8,
+    levels: [f32; 32],
+}
+
+impl Sunsoft5bAudio {
+    pub fn new() -> Self {
+        let mut levels = [0.0f32; 32];
+        for (level, amplitude) in levels.iter_mut().enumerate().skip(1) {
+            *amplitude = 10f32.powf((level as f32 - 31.0) * 1.5 / 20.0);
+        }
+
+        Sunsoft5bAudio {
+            address: 0,
+            tones: [Tone {
+                period: 0,
+                counter: 0,
+                high: false,
+            }; 3],
+            noise_period: 0,
+            noise_counter: 0,
+            noise_lfsr: 1,
+            mixer: 0xFF,
+            volumes: [0; 3],
+            envelope: Envelope::new(),
+            prescaler: 0,
+            levels,
+        }
+    }
+
+    pub fn write_address(&mut self, value: u8) {
+        self.address = value & 0x0F;
+    }
+
+    pub fn write_data(&mut self, value: u8) {
+        match self.address {
+            reg @ 0x00..=0x05 => {
+                let tone = &mut self.tones[(reg >> 1) as usize];
+                tone.period = if reg & 1 == 0 {
+                    (tone.period & 0x0F00) | value as u16
+                } else {
+                    (tone.period & 0x00FF) | ((value as u16 & 0x0F) << 8)
+                };
+            }
+            0x06 => self.noise_period = value & 0x1F,
+            0x07 => self.mixer = value,
+            reg @ 0x08..=0x0A => self.volumes[(reg - 0x08) as usize] = value & 0x1F,
+            0x0B => self.envelope.period = (self.envelope.period & 0xFF00) | value as u16,
+            0x0C => self.envelope.period = (self.envelope.period & 0x00FF) | (value as u16) << 8,
+            0x0D => self.envelope.write_shape(value),
+            _ => {}
+        }
+    }
+
+    fn tick_noise(&mut self) {
+        // The noise generator runs at half the tone rate
+        self.noise_counter += 1;
+        if self.noise_counter < self.noise_period.max(1) * 2 {
+            return;
+        }
+        self.noise_counter = 0;
+
+        // 17-bit LFSR with taps at bits 0 and 3
+        let feedback = (self.noise_lfsr ^ (self.noise_lfsr >> 3)) & 1;
+        self.noise_lfsr = (self.noise_lfsr >> 1) | (feedback << 16);
+    }
+}
+
+impl ExpansionAudio for Sunsoft5bAudio {
+    fn chip(&self) -> ExpansionChip {
+        ExpansionChip::Sunsoft5b
+    }
+
+    fn tick(&mut self) {
+        self.prescaler += 1;
+        if self.prescaler < PRESCALER {
+            return;
+        }
+        self.prescaler = 0;
+
+        for tone in self.tones.iter_mut() {
+            tone.tick();
+        }
+        self.tick_noise();
+        self.envelope.tick();
+    }
+
+    fn output(&self) -> f32 {
+        let noise = self.noise_lfsr & 1 != 0;
+
+        let mut sum = 0.0;
+        for (channel, tone) in self.tones.iter().enumerate() {
+            let tone_disabled = self.mixer & (0x01 << channel) != 0;
+            let noise_disabled = self.mixer & (0x08 << channel) != 0;
+            if !((tone.high || tone_disabled) && (noise || noise_disabled)) {
+                continue;
+            }
+
+            let volume = self.volumes[channel];
+            let level = if volume & 0x10 != 0 {
+                self.envelope.level()
+            } else if volume & 0x0F == 0 {
+                0
+            } else {
+                (volume & 0x0F) * 2 + 1
+            };
+            sum += self.levels[level as usize];
+        }
+
+        sum * CHANNEL_LEVEL
+    }
+}
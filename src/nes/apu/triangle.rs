@@ -77,6 +77,11 @@ impl TriangleChannel {
         self.length_counter
     }
 
+    /// Console reset: the sequencer goes back to step 0, which outputs 15.
+    pub fn reset(&mut self) {
+        self.sequence_pos = 0;
+    }
+
     /// Called every CPU cycle (triangle timer ticks at CPU rate, not APU rate)
     pub fn tick_timer(&mut self) {
         if self.timer_value == 0 {
@@ -0,0 +1,201 @@
+use super::expansion::{ExpansionAudio, ExpansionChip};
+
+/// Mix level of one output step. A VRC6 pulse at full volume is about as
+/// loud as an APU pulse at full volume.
+const OUTPUT_STEP: f32 = 0.1488 / 15.0;
+
+/// VRC6 pulse channel: 16-step duty cycle with a 4-bit volume.
+struct Vrc6Pulse {
+    volume: u8,
+    duty: u8,
+    /// Ignore the duty cycle and output the volume constantly
+    constant: bool,
+    enabled: bool,
+    period: u16,
+    timer: u16,
+    step: u8,
+}
+
+impl Vrc6Pulse {
+    fn new() -> Self {
+        Vrc6Pulse {
+            volume: 0,
+            duty: 0,
+            constant: false,
+            enabled: false,
+            period: 0,
+            timer: 0,
+            step: 15,
+        }
+    }
+
+    fn write(&mut self, register: u16, value: u8) {
+        match register {
+            0 => {
+                self.constant = value & 0x80 != 0;
+                self.duty = (value >> 4) & 0x07;
+                self.volume = value & 0x0F;
+            }
+            1 => self.period = (self.period & 0x0F00) | value as u16,
+            _ => {
+                self.period = (self.period & 0x00FF) | ((value as u16 & 0x0F) << 8);
+                self.enabled = value & 0x80 != 0;
+                if !self.enabled {
+                    self.step = 15;
+                }
+            }
+        }
+    }
+
+    fn tick(&mut self, shift: u8) {
+        if !self.enabled {
+            return;
+        }
+
+        if self.timer == 0 {
+            self.timer = self.period >> shift;
+            self.step = self.step.wrapping_sub(1) & 0x0F;
+        } else {
+            self.timer -= 1;
+        }
+    }
+
+    fn output(&self) -> u8 {
+        if self.enabled && (self.constant || self.step <= self.duty) {
+            self.volume
+        } else {
+            0
+        }
+    }
+}
+
+/// VRC6 sawtooth channel: adds the rate to an accumulator every other clock
+/// and resets it after seven additions.
+struct Vrc6Sawtooth {
+    rate: u8,
+    enabled: bool,
+    period: u16,
+    timer: u16,
+    step: u8,
+    accumulator: u8,
+}
+
+impl Vrc6Sawtooth {
+    fn new() -> Self {
+        Vrc6Sawtooth {
+            rate: 0,
+            enabled: false,
+            period: 0,
+            timer: 0,
+            step: 0,
+            accumulator: 0,
+        }
+    }
+
+    fn write(&mut self, register: u16, value: u8) {
+        match register {
+            0 => self.rate = value & 0x3F,
+            1 => self.period = (self.period & 0x0F00) | value as u16,
+            _ => {
+                self.period = (self.period & 0x00FF) | ((value as u16 & 0x0F) << 8);
+                self.enabled = value & 0x80 != 0;
+                if !self.enabled {
+                    self.step = 0;
+                    self.accumulator = 0;
+                }
+            }
+        }
+    }
+
+    fn tick(&mut self, shift: u8) {
+        if !self.enabled {
+            return;
+        }
+
+        if self.timer > 0 {
+            self.timer -= 1;
+            return;
+        }
+
+        self.timer = self.period >> shift;
+        self.step += 1;
+        if self.step == 14 {
+            self.step = 0;
+            self.accumulator = 0;
+        } else if self.step.is_multiple_of(2) {
+            self.accumulator = self.accumulator.wrapping_add(self.rate);
+        }
+    }
+
+    /// Top 5 bits of the accumulator
+    fn output(&self) -> u8 {
+        self.accumulator >> 3
+    }
+}
+
+/// Konami VRC6 expansion audio
+///
+/// $9000-$9002: Pulse 1      $A000-$A002: Pulse 2      $B000-$B002: Sawtooth
+/// $9003: Frequency control (bit 0: halt, bit 1: periods >> 4, bit 2: periods >> 8)
+///
+/// Registers are given with the VRC6b address line swap already undone.
+pub struct Vrc6Audio {
+    pulse1: Vrc6Pulse,
+    pulse2: Vrc6Pulse,
+    sawtooth: Vrc6Sawtooth,
+    halt: bool,
+    shift: u8,
+}
+
+impl Vrc6Audio {
+    pub fn new() -> Self {
+        Vrc6Audio {
+            pulse1: Vrc6Pulse::new(),
+            pulse2: Vrc6Pulse::new(),
+            sawtooth: Vrc6Sawtooth::new(),
+            halt: false,
+            shift: 0,
+        }
+    }
+
+    pub fn write_register(&mut self, addr: u16, value: u8) {
+        let register = addr & 0x0003;
+        match addr & 0xF000 {
+            0x9000 if register == 3 => {
+                self.halt = value & 0x01 != 0;
+                self.shift = if value & 0x04 != 0 {
+                    8
+                } else if value & 0x02 != 0 {
+                    4
+                } else {
+                    0
+                };
+            }
+            0x9000 => self.pulse1.write(register, value),
+            0xA000 => self.pulse2.write(register, value),
+            0xB000 => self.sawtooth.write(register, value),
+            _ => {}
+        }
+    }
+}
+
+impl ExpansionAudio for Vrc6Audio {
+    fn chip(&self) -> ExpansionChip {
+        ExpansionChip::Vrc6
+    }
+
+    fn tick(&mut self) {
+        if self.halt {
+            return;
+        }
+
+        self.pulse1.tick(self.shift);
+        self.pulse2.tick(self.shift);
+        self.sawtooth.tick(self.shift);
+    }
+
+    fn output(&self) -> f32 {
+        let sum = self.pulse1.output() + self.pulse2.output() + self.sawtooth.output();
+        sum as f32 * OUTPUT_STEP
+    }
+}
@@ -0,0 +1,434 @@
+use std::f32::consts::TAU;
+
+use super::expansion::{ExpansionAudio, ExpansionChip};
+
+/// The FM core runs at 3.58 MHz / 72, producing one sample every 36 CPU cycles
+const SAMPLE_CYCLES: u8 = 36;
+const FM_RATE: f32 = 49_716.0;
+
+/// Mix level of one channel at full volume, about that of an APU pulse
+const CHANNEL_LEVEL: f32 = 0.15;
+
+/// Envelope attenuation at which an operator is considered silent
+const SILENCE_DB: f32 = 96.0;
+
+/// Time for the slowest attack (rate 1) and decay (rate 1) to cross the
+/// whole 96 dB range. Each step of effective rate makes them 2^(1/4) faster.
+const ATTACK_TIME: f32 = 2.826;
+const DECAY_TIME: f32 = 39.28;
+
+const AM_DEPTH_DB: f32 = 4.8;
+const AM_RATE_HZ: f32 = 3.7;
+/// Vibrato depth, about 14 cents
+const VIBRATO_DEPTH: f32 = 0.0081;
+const VIBRATO_RATE_HZ: f32 = 6.4;
+
+/// Frequency multiplier for each MULT setting
+const MULTIPLIERS: [f32; 16] = [
+    0.5, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 10.0, 12.0, 12.0, 15.0, 15.0,
+];
+
+/// Key scale level attenuation in dB at octave 7, indexed by the top 4 bits
+/// of the F-number. Lower octaves subtract 6 dB each.
+const KSL_TABLE: [f32; 16] = [
+    0.0, 18.0, 24.0, 27.75, 30.0, 32.25, 33.75, 35.25, 36.0, 37.5, 38.25, 39.0, 39.75, 40.5,
+    41.25, 42.0,
+];
+
+/// Built-in instruments 1-15 (instrument 0 is the custom patch in registers $00-$07)
+const PATCHES: [[u8; 8]; 15] = [
+    [0x03, 0x21, 0x05, 0x06, 0xE8, 0x81, 0x42, 0x27],
+    [0x13, 0x41, 0x14, 0x0D, 0xD8, 0xF6, 0x23, 0x12],
+    [0x11, 0x11, 0x08, 0x08, 0xFA, 0xB2, 0x20, 0x12],
+    [0x31, 0x61, 0x0C, 0x07, 0xA8, 0x64, 0x61, 0x27],
+    [0x32, 0x21, 0x1E, 0x06, 0xE1, 0x76, 0x01, 0x28],
+    [0x02, 0x01, 0x06, 0x00, 0xA3, 0xE2, 0xF4, 0xF4],
+    [0x21, 0x61, 0x1D, 0x07, 0x82, 0x81, 0x11, 0x07],
+    [0x23, 0x21, 0x22, 0x17, 0xA2, 0x72, 0x01, 0x17],
+    [0x35, 0x11, 0x25, 0x00, 0x40, 0x73, 0x72, 0x01],
+    [0xB5, 0x01, 0x0F, 0x0F, 0xA8, 0xA5, 0x51, 0x02],
+    [0x17, 0xC1, 0x24, 0x07, 0xF8, 0xF8, 0x22, 0x12],
+    [0x71, 0x23, 0x11, 0x06, 0x65, 0x74, 0x18, 0x16],
+    [0x01, 0x02, 0xD3, 0x05, 0xC9, 0x95, 0x03, 0x02],
+    [0x61, 0x63, 0x0C, 0x00, 0x94, 0xC0, 0x33, 0xF6],
+    [0x21, 0x72, 0x0D, 0x00, 0xC1, 0xD5, 0x56, 0x06],
+];
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum EnvelopeStage {
+    Attack,
+    Decay,
+    Sustain,
+    Release,
+    Off,
+}
+
+/// Operator settings decoded from an 8-byte patch.
+/// Operator 0 is the modulator, operator 1 the carrier.
+struct OperatorPatch {
+    am: bool,
+    vibrato: bool,
+    /// Hold the sustain level while the key is on (otherwise percussive)
+    sustained: bool,
+    ksr: bool,
+    multiplier: f32,
+    ksl: u8,
+    rectified: bool,
+    attack: u8,
+    decay: u8,
+    sustain_level: u8,
+    release: u8,
+}
+
+impl OperatorPatch {
+    fn decode(patch: &[u8; 8], op: usize) -> Self {
+        let flags = patch[op];
+        OperatorPatch {
+            am: flags & 0x80 != 0,
+            vibrato: flags & 0x40 != 0,
+            sustained: flags & 0x20 != 0,
+            ksr: flags & 0x10 != 0,
+            multiplier: MULTIPLIERS[(flags & 0x0F) as usize],
+            ksl: patch[2 + op] >> 6,
+            rectified: patch[3] & (0x08 << op) != 0,
+            attack: patch[4 + op] >> 4,
+            decay: patch[4 + op] & 0x0F,
+            sustain_level: patch[6 + op] >> 4,
+            release: patch[6 + op] & 0x0F,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Operator {
+    /// Position in the waveform, in cycles
+    phase: f32,
+    /// Envelope attenuation in dB
+    envelope: f32,
+    stage: EnvelopeStage,
+}
+
+impl Operator {
+    fn new() -> Self {
+        Operator {
+            phase: 0.0,
+            envelope: SILENCE_DB,
+            stage: EnvelopeStage::Off,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Channel {
+    fnum: u16,
+    block: u8,
+    key_on: bool,
+    /// Sustain bit of $20-$25: slow release after key off
+    sustain: bool,
+    instrument: u8,
+    volume: u8,
+    operators: [Operator; 2],
+    /// Last two modulator outputs, averaged for self-feedback
+    feedback: [f32; 2],
+}
+
+impl Channel {
+    fn new() -> Self {
+        Channel {
+            fnum: 0,
+            block: 0,
+            key_on: false,
+            sustain: false,
+            instrument: 0,
+            volume: 0,
+            operators: [Operator::new(); 2],
+            feedback: [0.0; 2],
+        }
+    }
+
+    /// Envelope rate adjustment from key scale rate: higher notes use faster envelopes
+    fn key_scale_rate(&self, ksr: bool) -> u8 {
+        let key = (self.block << 1) | (self.fnum >> 8) as u8;
+        if ksr {
+            key
+        } else {
+            key >> 2
+        }
+    }
+
+    fn key_scale_level(&self, ksl: u8) -> f32 {
+        if ksl == 0 {
+            return 0.0;
+        }
+
+        let base = KSL_TABLE[(self.fnum >> 5) as usize] - 6.0 * (7 - self.block) as f32;
+        let scale = [0.0, 0.25, 0.5, 1.0][ksl as usize];
+        base.max(0.0) * scale
+    }
+}
+
+/// Konami VRC7 expansion audio
+///
+/// Six two-operator FM channels from a cut-down YM2413 (OPLL), with 15
+/// built-in instruments and one custom instrument.
+///
+/// $9010: Register select      $9030: Register data
+///
+/// $00-$07: Custom instrument
+/// $10-$15: F-number low 8 bits
+/// $20-$25: Sustain (bit 5), key on (bit 4), octave (bits 1-3), F-number bit 8
+/// $30-$35: Instrument (bits 4-7), volume attenuation in 3 dB steps (bits 0-3)
+///
+/// This is a floating point model of the chip rather than a bit-exact one:
+/// phases, envelopes and attenuation follow the documented curves, but the
+/// chip's log-sin tables and envelope counter quirks are not reproduced.
+pub struct Vrc7Audio {
+    address: u8,
+    custom: [u8; 8],
+    channels: [Channel; 6],
+    /// $E000 bit 6 holds the chip in reset
+    reset: bool,
+
+    cycle: u8,
+    lfo_time: f32,
+    attack_steps: [f32; 64],
+    decay_steps: [f32; 64],
+    output: f32,
+}
+
+impl Vrc7Audio {
+    pub fn new() -> Self {
+        let mut attack_steps = [0.0f32; 64];
+        let mut decay_steps = [0.0f32; 64];
+        for rate in 4..64 {
+            let speed = 2f32.powf((rate - 4) as f32 / 4.0);
+            // Attack approaches 0 dB exponentially: each sample shrinks
+            // (attenuation + 1) by a constant factor
+            let attack_samples = (ATTACK_TIME * FM_RATE / speed).max(1.0);
+            attack_steps[rate] = 1.0 - (SILENCE_DB + 1.0).powf(-1.0 / attack_samples);
+            decay_steps[rate] = SILENCE_DB * speed / (DECAY_TIME * FM_RATE);
+        }
+        // Attack rate 15 is instant
+        for step in attack_steps.iter_mut().skip(60) {
+            *step = 1.0;
+        }
+
+        Vrc7Audio {
+            address: 0,
+            custom: [0; 8],
+            channels: [Channel::new(); 6],
+            reset: false,
+            cycle: 0,
+            lfo_time: 0.0,
+            attack_steps,
+            decay_steps,
+            output: 0.0,
+        }
+    }
+
+    pub fn write_address(&mut self, value: u8) {
+        self.address = value;
+    }
+
+    pub fn write_data(&mut self, value: u8) {
+        let reg = self.address;
+        let index = (reg & 0x0F) as usize;
+
+        match reg {
+            0x00..=0x07 => self.custom[index] = value,
+            0x10..=0x15 => {
+                let channel = &mut self.channels[index];
+                channel.fnum = (channel.fnum & 0x100) | value as u16;
+            }
+            0x20..=0x25 => {
+                let channel = &mut self.channels[index];
+                channel.fnum = (channel.fnum & 0xFF) | ((value as u16 & 0x01) << 8);
+                channel.block = (value >> 1) & 0x07;
+                channel.sustain = value & 0x20 != 0;
+
+                let key_on = value & 0x10 != 0;
+                if key_on && !channel.key_on {
+                    for op in channel.operators.iter_mut() {
+                        op.phase = 0.0;
+                        op.stage = EnvelopeStage::Attack;
+                    }
+                    channel.feedback = [0.0; 2];
+                } else if !key_on && channel.key_on {
+                    for op in channel.operators.iter_mut() {
+                        if op.stage != EnvelopeStage::Off {
+                            op.stage = EnvelopeStage::Release;
+                        }
+                    }
+                }
+                channel.key_on = key_on;
+            }
+            0x30..=0x35 => {
+                let channel = &mut self.channels[index];
+                channel.instrument = value >> 4;
+                channel.volume = value & 0x0F;
+            }
+            _ => {}
+        }
+    }
+
+    /// $E000 bit 6: while set, the chip is silent and all channels are reset.
+    pub fn set_reset(&mut self, reset: bool) {
+        self.reset = reset;
+        if reset {
+            self.channels = [Channel::new(); 6];
+            self.output = 0.0;
+        }
+    }
+
+    fn patch(&self, instrument: u8) -> [u8; 8] {
+        match instrument {
+            0 => self.custom,
+            n => PATCHES[n as usize - 1],
+        }
+    }
+
+    /// Compute one FM sample.
+    fn clock(&mut self) {
+        self.lfo_time += 1.0 / FM_RATE;
+        let am_db = AM_DEPTH_DB * 0.5 * (1.0 + (TAU * AM_RATE_HZ * self.lfo_time).sin());
+        let vibrato = 1.0 + VIBRATO_DEPTH * (TAU * VIBRATO_RATE_HZ * self.lfo_time).sin();
+
+        let mut sum = 0.0;
+        for index in 0..self.channels.len() {
+            let patch = self.patch(self.channels[index].instrument);
+            sum += self.clock_channel(index, &patch, am_db, vibrato);
+        }
+        self.output = sum * CHANNEL_LEVEL;
+    }
+
+    fn clock_channel(&mut self, index: usize, patch: &[u8; 8], am_db: f32, vibrato: f32) -> f32 {
+        let feedback_level = patch[3] & 0x07;
+        let modulator_level = (patch[2] & 0x3F) as f32 * 0.75;
+
+        let channel = self.channels[index];
+        let base_increment = channel.fnum as f32 * (1u32 << channel.block) as f32 / (1u32 << 19) as f32;
+
+        let mut outputs = [0.0f32; 2];
+        let mut operators = channel.operators;
+        for (op, operator) in operators.iter_mut().enumerate() {
+            let settings = OperatorPatch::decode(patch, op);
+            self.advance_envelope(operator, &settings, &channel);
+
+            let mut increment = base_increment * settings.multiplier;
+            if settings.vibrato {
+                increment *= vibrato;
+            }
+            operator.phase = (operator.phase + increment).fract();
+
+            let mut attenuation = operator.envelope + channel.key_scale_level(settings.ksl);
+            attenuation += if op == 0 {
+                modulator_level
+            } else {
+                channel.volume as f32 * 3.0
+            };
+            if settings.am {
+                attenuation += am_db;
+            }
+            if operator.stage == EnvelopeStage::Off || attenuation >= SILENCE_DB {
+                continue;
+            }
+
+            // The modulator feeds back into itself, the carrier is modulated
+            // by the modulator: at full level by +-4 and +-8 pi respectively
+            let offset = if op == 0 {
+                if feedback_level == 0 {
+                    0.0
+                } else {
+                    let average = (channel.feedback[0] + channel.feedback[1]) / 2.0;
+                    average * 2.0 * TAU / (1 << (7 - feedback_level)) as f32
+                }
+            } else {
+                outputs[0] * 4.0 * TAU
+            };
+
+            let mut wave = (TAU * operator.phase + offset).sin();
+            if settings.rectified && wave < 0.0 {
+                wave = 0.0;
+            }
+            outputs[op] = wave * 10f32.powf(-attenuation / 20.0);
+        }
+
+        let channel = &mut self.channels[index];
+        channel.operators = operators;
+        channel.feedback = [channel.feedback[1], outputs[0]];
+        outputs[1]
+    }
+
+    fn advance_envelope(&self, operator: &mut Operator, settings: &OperatorPatch, channel: &Channel) {
+        let rate_step = |rate: u8, table: &[f32; 64]| -> f32 {
+            if rate == 0 {
+                return 0.0;
+            }
+            let effective = (rate * 4 + channel.key_scale_rate(settings.ksr)).min(63);
+            table[effective as usize]
+        };
+
+        match operator.stage {
+            EnvelopeStage::Attack => {
+                let step = rate_step(settings.attack, &self.attack_steps);
+                operator.envelope -= (operator.envelope + 1.0) * step;
+                if operator.envelope <= 0.0 {
+                    operator.envelope = 0.0;
+                    operator.stage = EnvelopeStage::Decay;
+                }
+            }
+            EnvelopeStage::Decay => {
+                let sustain_db = settings.sustain_level as f32 * 3.0;
+                operator.envelope += rate_step(settings.decay, &self.decay_steps);
+                if operator.envelope >= sustain_db {
+                    operator.envelope = sustain_db;
+                    operator.stage = EnvelopeStage::Sustain;
+                }
+            }
+            EnvelopeStage::Sustain => {
+                // Percussive instruments keep decaying at the release rate
+                if !settings.sustained {
+                    operator.envelope += rate_step(settings.release, &self.decay_steps);
+                }
+            }
+            EnvelopeStage::Release => {
+                let rate = if channel.sustain {
+                    5
+                } else if settings.sustained {
+                    settings.release
+                } else {
+                    7
+                };
+                operator.envelope += rate_step(rate, &self.decay_steps);
+            }
+            EnvelopeStage::Off => {}
+        }
+
+        if operator.envelope >= SILENCE_DB {
+            operator.envelope = SILENCE_DB;
+            operator.stage = EnvelopeStage::Off;
+        }
+    }
+}
+
+impl ExpansionAudio for Vrc7Audio {
+    fn chip(&self) -> ExpansionChip {
+        ExpansionChip::Vrc7
+    }
+
+    fn tick(&mut self) {
+        if self.reset {
+            return;
+        }
+
+        self.cycle += 1;
+        if self.cycle >= SAMPLE_CYCLES {
+            self.cycle = 0;
+            self.clock();
+        }
+    }
+
+    fn output(&self) -> f32 {
+        self.output
+    }
+}
@@ -0,0 +1,59 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::cartridge::Cartridge;
+
+/// Battery-backed PRG RAM persisted to a .sav file next to the ROM.
+///
+/// The file is a raw dump of PRG RAM, or of the disk with its gaps for
+/// Famicom Disk System images. It is loaded before the mapper is built and
+/// written back whenever the data has changed since the last save.
+pub struct BatterySave {
+    path: PathBuf,
+    saved: Vec<u8>,
+}
+
+impl BatterySave {
+    pub fn new(rom_path: &str) -> BatterySave {
+        BatterySave {
+            path: Path::new(rom_path).with_extension("sav"),
+            saved: Vec::new(),
+        }
+    }
+
+    /// Fill the cartridge save data from the save file, if there is one.
+    pub fn load(&mut self, cartridge: &mut Cartridge) {
+        let save_data = cartridge.save_data_mut();
+        self.saved = save_data.to_vec();
+
+        let data = match fs::read(&self.path) {
+            Ok(data) => data,
+            Err(_) => return,
+        };
+
+        if data.len() != save_data.len() {
+            eprintln!(
+                "{}: expected {} bytes, found {}",
+                self.path.display(),
+                save_data.len(),
+                data.len()
+            );
+        }
+
+        let len = data.len().min(save_data.len());
+        save_data[..len].copy_from_slice(&data[..len]);
+        self.saved = save_data.to_vec();
+    }
+
+    /// Write the save data to the save file if it changed since the last save.
+    pub fn flush(&mut self, save_data: &[u8]) {
+        if self.saved == save_data {
+            return;
+        }
+
+        match fs::write(&self.path, save_data) {
+            Ok(()) => self.saved = save_data.to_vec(),
+            Err(e) => eprintln!("Failed to write {}: {e}", self.path.display()),
+        }
+    }
+}
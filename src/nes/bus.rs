@@ -1,48 +1,123 @@
 use core::panic;
-use std::sync::{Arc, Mutex};
+use std::cell::RefCell;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::rc::Rc;
+
+use super::{apu::Apu, cpu::Addr, joypad::Joypad, mapper::Mapper, ppu::Ppu};
+
+/// Contents of the 2 KB of CPU RAM at power on. The real SRAM comes up in
+/// whatever state it settles to, and a few games depend on it.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum RamPattern {
+    #[default]
+    Zeros,
+    /// Every byte $FF
+    Ones,
+    Random,
+    /// 4 bytes of $00 then 4 bytes of $FF, repeated, as FCEUX does
+    Fceux,
+}
 
-use super::{apu::Apu, cartridge::Cartridge, cpu::Addr, joypad::Joypad, ppu::Ppu};
+impl RamPattern {
+    /// Look up a pattern by its lowercase name, as given on the command line.
+    pub fn from_name(name: &str) -> Option<RamPattern> {
+        match name {
+            "zeros" | "00" => Some(RamPattern::Zeros),
+            "ff" => Some(RamPattern::Ones),
+            "random" => Some(RamPattern::Random),
+            "fceux" => Some(RamPattern::Fceux),
+            _ => None,
+        }
+    }
+
+    pub fn fill(self, mem: &mut [u8]) {
+        match self {
+            RamPattern::Zeros => mem.fill(0x00),
+            RamPattern::Ones => mem.fill(0xFF),
+            RamPattern::Random => {
+                // xorshift64, seeded from the standard library's random hash keys
+                let mut state = RandomState::new().build_hasher().finish() | 1;
+                for byte in mem {
+                    state ^= state << 13;
+                    state ^= state >> 7;
+                    state ^= state << 17;
+                    *byte = state as u8;
+                }
+            }
+            RamPattern::Fceux => {
+                for (i, byte) in mem.iter_mut().enumerate() {
+                    *byte = if i & 0x04 == 0 { 0x00 } else { 0xFF };
+                }
+            }
+        }
+    }
+}
 
 pub struct Bus {
     mem: [u8; 0x800],  // 2 KB internal RAM
-    rom: Cartridge,
+    mapper: Rc<RefCell<dyn Mapper>>,
     ppu: Ppu,
     apu: Apu,
     joypad1: Joypad,
+    oam_dma_request: Option<u8>,   // Page written to $4014, copied by the CPU's DMA unit
+    dmc_dma_request: Option<Addr>, // Sample byte the DMC wants fetched
 }
 
 impl Bus {
-    pub fn new(rom: Cartridge, ppu: Ppu, audio_buffer: Arc<Mutex<Vec<f32>>>) -> Bus {
-        let mut bus = Bus {
-            mem: [0x0; 0x800],
-            rom,
+    pub fn new(mapper: Rc<RefCell<dyn Mapper>>, ppu: Ppu, apu: Apu, ram_pattern: RamPattern) -> Bus {
+        let mut mem = [0x0; 0x800];
+        ram_pattern.fill(&mut mem);
+
+        Bus {
+            mem,
+            mapper,
             ppu,
-            apu: Apu::new(audio_buffer),
+            apu,
             joypad1: Joypad::new(),
-        };
-
-        bus.init();
-        bus
+            oam_dma_request: None,
+            dmc_dma_request: None,
+        }
     }
 
-    fn init(&mut self) {
-        println!("Mapper: {}", self.rom.mapper);
-        self.ppu.load_chr(&self.rom.chr_rom);
+    /// Console reset: RAM keeps its contents, the PPU, APU and controller
+    /// port are reset and any pending DMA is dropped.
+    pub fn reset(&mut self) {
+        self.ppu.reset();
+        self.apu.reset();
+        self.joypad1.reset();
+        self.oam_dma_request = None;
+        self.dmc_dma_request = None;
     }
 
     pub fn ppu_tick(&mut self, tick: u8) {
         self.ppu.tick(tick);
     }
 
-    /// Tick the APU once per CPU cycle.
+    /// Tick the APU, the cartridge sound chip and the board's CPU-clocked
+    /// logic once per CPU cycle.
     pub fn apu_tick(&mut self) {
-        if let Some(dmc_addr) = self.apu.tick() {
-            // DMC needs to read a byte from memory
-            let value = self.read_u8(dmc_addr);
-            self.apu.dmc_fill_buffer(value);
+        let mut mapper = self.mapper.borrow_mut();
+        mapper.cpu_tick();
+
+        if let Some(dmc_addr) = self.apu.tick(mapper.expansion_audio()) {
+            // DMC needs a byte from memory, fetched by DMA on the next CPU read
+            self.dmc_dma_request = Some(dmc_addr);
         }
     }
 
+    pub fn take_oam_dma_request(&mut self) -> Option<u8> {
+        self.oam_dma_request.take()
+    }
+
+    pub fn take_dmc_dma_request(&mut self) -> Option<Addr> {
+        self.dmc_dma_request.take()
+    }
+
+    pub fn dmc_fill_buffer(&mut self, value: u8) {
+        self.apu.dmc_fill_buffer(value);
+    }
+
     pub fn get_ppu_tick(&self) -> (usize, usize) {
         (self.ppu.get_scanlines(), self.ppu.get_cycles())
     }
@@ -56,24 +131,23 @@ impl Bus {
     }
 
     fn handle_ppu_read(&mut self, idx: u8) -> u8 {
-        match idx {
-            0 => panic!("Read on PPUCTRL is invalid"),
-            1 => panic!("Read on PPUMASK is invalid"),
+        let value = match idx {
             2 => self.ppu.status(),
-            3 => panic!("Read on OAMADDR is invalid"),
             4 => self.ppu.oam_data_read(),
-            5 => panic!("Read on PPUSCROLL is invalid"),
-            6 => panic!("Read on PPUADDR is invalid"),
             7 => self.ppu.data_read(),
-            _ => panic!("This should be impossible"),
-        }
+            // Write-only registers return whatever was last on the PPU data bus
+            _ => self.ppu.get_io_latch(),
+        };
+        self.ppu.set_io_latch(value);
+        value
     }
 
     fn handle_ppu_write(&mut self, idx: u8, value: u8) {
+        self.ppu.set_io_latch(value);
         match idx {
             0 => self.ppu.ctrl(value),
             1 => self.ppu.mask(value),
-            2 => panic!("Write on PPUSTATUS is invalid"),
+            2 => {} // PPUSTATUS is read-only
             3 => self.ppu.oam_addr(value),
             4 => self.ppu.oam_data_write(value),
             5 => self.ppu.scroll(value),
@@ -83,16 +157,6 @@ impl Bus {
         }
     }
 
-    fn handle_oam_dma(&mut self, value: u8) {
-        let addr = Addr::from_le_bytes([0x00, value]) as usize;
-
-        let mut data: [u8; 256] = [0; 256];
-        for i in 0..256 {
-            data[i] = self.read_u8((addr + i) as u16);
-        }
-        self.ppu.oam_dma(&data);
-    }
-
     pub fn read_u16(&mut self, address: Addr) -> u16 {
         u16::from_le_bytes([self.read_u8(address), self.read_u8(address + 1)])
     }
@@ -104,7 +168,7 @@ impl Bus {
             // PPU mapped I/O (mirrored every 8 bytes)
             0x2000..=0x3FFF => self.handle_ppu_read((address & 0x07) as u8),
             // OAM DMA
-            0x4014 => panic!("Read on OAM DMA is invalid"),
+            0x4014 => 0, // Write-only, reached by dummy reads
             // APU Status
             0x4015 => self.apu.read_status(),
             // Joypad 1
@@ -114,25 +178,9 @@ impl Bus {
             // APU and I/O
             0x4000..=0x4013 => 0, // APU registers are write-only (except $4015)
             0x4018..=0x401F => 0, // Test mode
-            // Cartridge space: PRG ROM
-            0x8000..=0xFFFF => self.read_prg_rom(address),
-            _ => 0,
-        }
-    }
-
-    pub fn read_i8(&mut self, address: Addr) -> i8 {
-        self.read_u8(address) as i8
-    }
-
-    fn read_prg_rom(&self, address: Addr) -> u8 {
-        let offset = (address - 0x8000) as usize;
-        let prg_len = self.rom.prg_rom.len();
-        
-        if prg_len == 0 {
-            return 0;
+            // Cartridge space
+            0x4020..=0xFFFF => self.mapper.borrow_mut().cpu_read(address),
         }
-
-        self.rom.prg_rom[offset % prg_len]
     }
 
     pub fn write_u8(&mut self, address: Addr, value: u8) {
@@ -140,9 +188,13 @@ impl Bus {
             // Internal RAM (mirrored every 0x800 bytes)
             0x0000..=0x1FFF => self.mem[(address & 0x07FF) as usize] = value,
             // PPU mapped I/O
-            0x2000..=0x3FFF => self.handle_ppu_write((address & 0x07) as u8, value),
+            0x2000..=0x3FFF => {
+                let register = (address & 0x07) as u8;
+                self.mapper.borrow_mut().ppu_register_write(0x2000 | register as u16, value);
+                self.handle_ppu_write(register, value);
+            }
             // OAM DMA
-            0x4014 => self.handle_oam_dma(value),
+            0x4014 => self.oam_dma_request = Some(value),
             // Joypad 1 strobe
             0x4016 => self.joypad1.write(value),
             // APU registers ($4000-$4013, $4015, $4017)
@@ -150,11 +202,18 @@ impl Bus {
             // Remaining I/O
             0x4018..=0x401F => {} // Test mode
             // Cartridge space
-            _ => {}
+            0x4020..=0xFFFF => self.mapper.borrow_mut().cpu_write(address, value),
         }
     }
     
     pub fn poll_nmi_status(&mut self) -> bool {
         self.ppu.get_nmi_occurred()
     }
+
+    /// The IRQ line is level-triggered and shared: it stays asserted for as
+    /// long as any source (APU frame counter, DMC, cartridge) holds it low,
+    /// and each source is acknowledged through its own registers.
+    pub fn poll_irq_status(&self) -> bool {
+        self.apu.irq_pending() || self.mapper.borrow().irq_pending()
+    }
 }
@@ -1,77 +1,497 @@
+mod archive;
+mod database;
+mod disk;
+mod error;
+mod header;
+mod patch;
+mod unif;
+
 use std::fs;
 
-const NES_HEADER_SIZE: usize = 0x10;
+use database::{Crc32, GameInfo, Sha1};
+use disk::FDS_TAG;
+use header::{Header, HEADER_SIZE, NES_TAG};
+use unif::{UnifImage, UNIF_TAG};
+
+use super::config::Config;
+
+pub use disk::DiskImage;
+pub use error::CartridgeError;
+pub use header::{ConsoleType, ExpansionDevice, HeaderFormat, Timing};
+
 const TRAINER_SIZE: usize = 0x200;
-const PRG_ROM_BLOCK_SIZE: usize = 0x4000;
-const CHR_ROM_BLOCK_SIZE: usize = 0x2000;
+const CHR_RAM_SIZE: usize = 0x2000;
+const FOUR_SCREEN_VRAM_SIZE: usize = 0x800;
+const PRG_RAM_SIZE: usize = 0x2000;
 
+/// NES 2.0 mapper number of the Famicom Disk System
+pub const FDS_MAPPER: u16 = 20;
+const FDS_BIOS_SIZE: usize = 0x2000;
+const FDS_PRG_RAM_SIZE: usize = 0x8000;
+
+/// Signature of NSF music files
+const NSF_TAG: [u8; 5] = *b"NESM\x1A";
+
+#[derive(Clone)]
 pub struct Cartridge {
     pub prg_rom: Vec<u8>,
-    pub chr_rom: Vec<u8>,
+    pub chr: Vec<u8>,     // CHR ROM, or CHR RAM when has_chr_ram is set
+    pub has_chr_ram: bool,
+    pub prg_ram: Vec<u8>, // Work RAM at $6000-$7FFF, volatile and battery backed parts
     pub mirroring: Mirroring,
-    pub has_prg_ram: bool,
+    pub has_battery: bool, // PRG RAM is battery backed and should be saved
     pub has_trainer: bool,
     pub has_four_screen: bool,
-    pub mapper: u8,
+    pub vram: Vec<u8>, // Extra 2 KB of nametable RAM on four-screen boards
+    pub disk: Option<DiskImage>, // Disk in the drive of a Famicom Disk System
+    pub mapper: u16,
+    pub submapper: u8,
+
+    pub format: HeaderFormat,
+    pub prg_ram_size: usize,
+    pub prg_nvram_size: usize,
+    pub chr_ram_size: usize,
+    pub chr_nvram_size: usize,
+    pub timing: Timing,
+    pub console_type: ConsoleType,
+    pub expansion_device: ExpansionDevice,
 }
 
-#[derive(Default, Clone, Copy)]
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
 pub enum Mirroring {
     #[default]
     Horizontal,
     Vertical,
+    SingleScreenLower,
+    SingleScreenUpper,
+    FourScreen,
 }
 
-const NES_TAG: [u8; 4] = [0x4E, 0x45, 0x53, 0x1A];
+impl Mirroring {
+    /// Which 1 KB page backs each of the four logical nametables. Pages 0
+    /// and 1 are the console's VRAM, pages 2 and 3 are cartridge VRAM.
+    pub fn page(self, nametable: u16) -> usize {
+        let nametable = (nametable & 0x03) as usize;
+        match self {
+            // NT0 and NT1 map to page 0, NT2 and NT3 map to page 1
+            Mirroring::Horizontal => nametable >> 1,
+            // NT0 and NT2 map to page 0, NT1 and NT3 map to page 1
+            Mirroring::Vertical => nametable & 0x01,
+            // All four nametables map to the same page
+            Mirroring::SingleScreenLower => 0,
+            Mirroring::SingleScreenUpper => 1,
+            // Every nametable has its own page
+            Mirroring::FourScreen => nametable,
+        }
+    }
+}
 
 impl Cartridge {
-    pub fn new(path: &str) -> Result<Cartridge, String> {
-        let file = match fs::read(path) {
-            Ok(file) => file,
-            Err(e) => return Err(e.to_string()),
-        };
+    pub fn new(path: &str, config: &Config) -> Result<Cartridge, CartridgeError> {
+        let mut file = archive::unpack(fs::read(path)?, config.archive_entry.as_deref())?;
 
-        let raw = &file;
-        if raw[0..4] != NES_TAG {
-            return Err("NES signature not found".to_string());
+        if let Some(patch_path) = patch::find_patch(path, config.patch.as_deref()) {
+            file = patch::apply(file, &patch_path)?;
+            println!("Applied patch {}", patch_path.display());
         }
 
-        let mirroring = if raw[6] & 0b1 != 0 {
-            Mirroring::Vertical
+        if DiskImage::is_disk_image(&file) {
+            let bios_path = config.fds_bios.as_ref().ok_or(CartridgeError::MissingBios)?;
+            let bios = fs::read(bios_path)?;
+            return Cartridge::from_disk(&file, bios);
+        }
+
+        let mut cartridge = Cartridge::from_bytes(&file)?;
+        cartridge.apply_database();
+        Ok(cartridge)
+    }
+
+    /// Parse a UNIF image, picking the mapper from the board name.
+    pub fn from_unif(raw: &[u8]) -> Result<Cartridge, CartridgeError> {
+        let image = UnifImage::parse(raw)?;
+        let (mapper, submapper) = image
+            .mapper()
+            .ok_or_else(|| CartridgeError::UnsupportedBoard(image.board.clone()))?;
+
+        let has_chr_ram = image.has_chr_ram || image.chr_rom.is_empty();
+        let chr = if image.chr_rom.is_empty() {
+            vec![0; CHR_RAM_SIZE]
         } else {
-            Mirroring::Horizontal
+            image.chr_rom
         };
+        let chr_ram_size = if has_chr_ram { chr.len() } else { 0 };
 
-        let has_prg_ram = raw[6] & 0b10 != 0;
-        let has_trainer = raw[6] & 0b100 != 0;
-        let has_four_screen = raw[6] & 0b1000 != 0;
+        // UNIF has no PRG RAM size, so every board gets 8 KB like archaic iNES
+        let (prg_ram_size, prg_nvram_size) = if image.has_battery {
+            (0, PRG_RAM_SIZE)
+        } else {
+            (PRG_RAM_SIZE, 0)
+        };
+
+        let mirroring = image.mirroring.unwrap_or_default();
+        let has_four_screen = mirroring == Mirroring::FourScreen;
+        let vram = if has_four_screen {
+            vec![0; FOUR_SCREEN_VRAM_SIZE]
+        } else {
+            Vec::new()
+        };
 
-        let mapper = (raw[7] & 0xF0) | ((raw[6] & 0xF0) >> 4);
+        Ok(Cartridge {
+            prg_rom: image.prg_rom,
+            chr,
+            has_chr_ram,
+            prg_ram: vec![0; PRG_RAM_SIZE],
+            mirroring,
+            has_battery: image.has_battery,
+            has_trainer: false,
+            has_four_screen,
+            vram,
+            disk: None,
+            mapper,
+            submapper,
+            format: HeaderFormat::Unif,
+            prg_ram_size,
+            prg_nvram_size,
+            chr_ram_size,
+            chr_nvram_size: 0,
+            timing: image.timing,
+            console_type: ConsoleType::Nes,
+            expansion_device: ExpansionDevice::Unspecified,
+        })
+    }
 
-        if raw[7] & 0b1100 == 0b1100 {
-            return Err("Can't parse NES 2.0 roms yet".to_string());
+    /// Build a Famicom Disk System from an .fds image and the disk BIOS.
+    ///
+    /// The RAM adapter is treated as a cartridge: the 8 KB BIOS is its PRG
+    /// ROM at $E000, it has 32 KB of PRG RAM at $6000-$DFFF and 8 KB of CHR
+    /// RAM. The disk is battery backed, in that writes to it are saved.
+    pub fn from_disk(raw: &[u8], bios: Vec<u8>) -> Result<Cartridge, CartridgeError> {
+        if bios.len() != FDS_BIOS_SIZE {
+            return Err(CartridgeError::BadBios { found: bios.len() });
         }
+        let disk = DiskImage::parse(raw)?;
 
-        let trainer_size = if has_trainer { TRAINER_SIZE } else { 0 };
-        let prg_rom_size = raw[4] as usize * PRG_ROM_BLOCK_SIZE;
-        let chr_rom_size = raw[5] as usize * CHR_ROM_BLOCK_SIZE;
+        Ok(Cartridge {
+            prg_rom: bios,
+            chr: vec![0; CHR_RAM_SIZE],
+            has_chr_ram: true,
+            prg_ram: vec![0; FDS_PRG_RAM_SIZE],
+            mirroring: Mirroring::Horizontal,
+            has_battery: true,
+            has_trainer: false,
+            has_four_screen: false,
+            vram: Vec::new(),
+            disk: Some(disk),
+            mapper: FDS_MAPPER,
+            submapper: 0,
+            format: HeaderFormat::INes,
+            prg_ram_size: FDS_PRG_RAM_SIZE,
+            prg_nvram_size: 0,
+            chr_ram_size: CHR_RAM_SIZE,
+            chr_nvram_size: 0,
+            timing: Timing::Ntsc,
+            console_type: ConsoleType::Nes,
+            expansion_device: ExpansionDevice::Unspecified,
+        })
+    }
+
+    /// Parse an iNES / NES 2.0 or UNIF image held in memory.
+    pub fn from_bytes(raw: &[u8]) -> Result<Cartridge, CartridgeError> {
+        if raw.starts_with(&UNIF_TAG) {
+            return Cartridge::from_unif(raw);
+        }
+        if raw.starts_with(&FDS_TAG) {
+            return Err(CartridgeError::UnsupportedHeaderVersion("FDS"));
+        }
+        if raw.starts_with(&NSF_TAG) {
+            return Err(CartridgeError::UnsupportedNsf);
+        }
+
+        let header_bytes: &[u8; HEADER_SIZE] = match raw.get(0..HEADER_SIZE) {
+            Some(bytes) => bytes.try_into().unwrap(),
+            None if raw.len() < 4 || raw[0..4] == NES_TAG => {
+                return Err(CartridgeError::TruncatedHeader { found: raw.len() })
+            }
+            None => return Err(CartridgeError::BadMagic),
+        };
+        let header = Header::parse(header_bytes)?;
+
+        let trainer_size = if header.has_trainer { TRAINER_SIZE } else { 0 };
 
-        let prg_rom_begin = NES_HEADER_SIZE + trainer_size;
-        let prg_rom_end = prg_rom_begin + prg_rom_size;
-        let prg_rom = raw[prg_rom_begin..prg_rom_end].to_vec();
+        let prg_rom_begin = HEADER_SIZE + trainer_size;
+        let prg_rom = slice_rom(raw, prg_rom_begin, header.prg_rom_size).ok_or(
+            CartridgeError::TruncatedPrg {
+                expected: header.prg_rom_size,
+                found: raw.len().saturating_sub(prg_rom_begin),
+            },
+        )?;
 
-        let chr_rom_begin = prg_rom_end;
-        let chr_rom_end = chr_rom_begin + chr_rom_size;
-        let chr_rom = raw[chr_rom_begin..chr_rom_end].to_vec();
+        let chr_rom_begin = prg_rom_begin + header.prg_rom_size;
+        let chr_rom = slice_rom(raw, chr_rom_begin, header.chr_rom_size).ok_or(
+            CartridgeError::TruncatedChr {
+                expected: header.chr_rom_size,
+                found: raw.len().saturating_sub(chr_rom_begin),
+            },
+        )?;
+
+        // Boards without CHR ROM have CHR RAM that the game fills at runtime,
+        // 8 KB unless the header says otherwise
+        let has_chr_ram = chr_rom.is_empty();
+        let chr = if has_chr_ram {
+            let size = header.chr_ram_size + header.chr_nvram_size;
+            vec![0; if size == 0 { CHR_RAM_SIZE } else { size }]
+        } else {
+            chr_rom
+        };
+        let chr_ram_size = if has_chr_ram {
+            chr.len() - header.chr_nvram_size
+        } else {
+            header.chr_ram_size
+        };
+
+        let prg_ram = vec![0; header.prg_ram_size + header.prg_nvram_size];
+
+        let (mirroring, vram) = if header.has_four_screen {
+            (Mirroring::FourScreen, vec![0; FOUR_SCREEN_VRAM_SIZE])
+        } else {
+            (header.mirroring, Vec::new())
+        };
 
         Ok(Cartridge {
             prg_rom,
-            chr_rom,
+            chr,
+            has_chr_ram,
+            prg_ram,
             mirroring,
-            has_prg_ram,
-            has_trainer,
-            has_four_screen,
-            mapper,
+            has_battery: header.has_battery,
+            has_trainer: header.has_trainer,
+            has_four_screen: header.has_four_screen,
+            vram,
+            disk: None,
+            mapper: header.mapper,
+            submapper: header.submapper,
+            format: header.format,
+            prg_ram_size: header.prg_ram_size,
+            prg_nvram_size: header.prg_nvram_size,
+            chr_ram_size,
+            chr_nvram_size: header.chr_nvram_size,
+            timing: header.timing,
+            console_type: header.console_type,
+            expansion_device: header.expansion_device,
         })
     }
+
+    /// CRC32 and SHA-1 of PRG ROM followed by CHR ROM, the keys of the
+    /// game database.
+    pub fn rom_hashes(&self) -> (u32, [u8; 20]) {
+        let mut crc = Crc32::new();
+        let mut sha1 = Sha1::new();
+        crc.update(&self.prg_rom);
+        sha1.update(&self.prg_rom);
+        if !self.has_chr_ram {
+            crc.update(&self.chr);
+            sha1.update(&self.chr);
+        }
+        (crc.finish(), sha1.finish())
+    }
+
+    /// Replace header fields with the database entry for this game, if it is
+    /// a known title.
+    pub fn apply_database(&mut self) {
+        let (crc, sha1) = self.rom_hashes();
+        if let Some(game) = database::lookup(crc, &sha1) {
+            self.apply_game_info(game);
+        }
+    }
+
+    /// Override the header with a database entry and log every field that
+    /// changed.
+    fn apply_game_info(&mut self, game: &GameInfo) {
+        let mut changes = Vec::new();
+        if self.mapper != game.mapper || self.submapper != game.submapper {
+            changes.push(format!(
+                "mapper {}.{} -> {}.{}",
+                self.mapper, self.submapper, game.mapper, game.submapper
+            ));
+            self.mapper = game.mapper;
+            self.submapper = game.submapper;
+        }
+        if self.mirroring != game.mirroring {
+            changes.push(format!("mirroring {:?} -> {:?}", self.mirroring, game.mirroring));
+            self.mirroring = game.mirroring;
+            self.has_four_screen = game.mirroring == Mirroring::FourScreen;
+            self.vram = if self.has_four_screen {
+                vec![0; FOUR_SCREEN_VRAM_SIZE]
+            } else {
+                Vec::new()
+            };
+        }
+        if self.has_battery != game.has_battery {
+            changes.push(format!("battery {} -> {}", self.has_battery, game.has_battery));
+            self.set_battery(game.has_battery);
+        }
+        if self.timing != game.timing {
+            changes.push(format!("timing {:?} -> {:?}", self.timing, game.timing));
+            self.timing = game.timing;
+        }
+
+        if changes.is_empty() {
+            println!("Database: {} (header is correct)", game.name);
+        } else {
+            println!("Database: {}, overriding {}", game.name, changes.join(", "));
+        }
+    }
+
+    /// Move PRG RAM between the volatile and battery-backed sizes.
+    fn set_battery(&mut self, has_battery: bool) {
+        let size = (self.prg_ram_size + self.prg_nvram_size).max(PRG_RAM_SIZE);
+        self.has_battery = has_battery;
+        if has_battery {
+            self.prg_ram_size = 0;
+            self.prg_nvram_size = size;
+        } else {
+            self.prg_ram_size = size;
+            self.prg_nvram_size = 0;
+        }
+        self.prg_ram.resize(size, 0);
+    }
+
+    /// Memory kept in the .sav file: the disk on a Famicom Disk System,
+    /// PRG RAM otherwise.
+    pub fn save_data(&self) -> &[u8] {
+        match &self.disk {
+            Some(disk) => disk.data(),
+            None => &self.prg_ram,
+        }
+    }
+
+    pub fn save_data_mut(&mut self) -> &mut [u8] {
+        match &mut self.disk {
+            Some(disk) => disk.data_mut(),
+            None => &mut self.prg_ram,
+        }
+    }
+
+    /// Read PRG RAM, mirrored across $6000-$7FFF.
+    pub fn read_prg_ram(&self, addr: u16) -> u8 {
+        if self.prg_ram.is_empty() {
+            return 0;
+        }
+        self.prg_ram[(addr as usize - 0x6000) % self.prg_ram.len()]
+    }
+
+    pub fn write_prg_ram(&mut self, addr: u16, value: u8) {
+        if self.prg_ram.is_empty() {
+            return;
+        }
+        let len = self.prg_ram.len();
+        self.prg_ram[(addr as usize - 0x6000) % len] = value;
+    }
+
+    /// Write to CHR memory. Writes to CHR ROM are ignored.
+    pub fn write_chr(&mut self, index: usize, value: u8) {
+        if self.has_chr_ram {
+            self.chr[index] = value;
+        }
+    }
+}
+
+/// Copy `size` bytes starting at `begin`, or None if the image is too short.
+fn slice_rom(raw: &[u8], begin: usize, size: usize) -> Option<Vec<u8>> {
+    let end = begin.checked_add(size)?;
+    raw.get(begin..end).map(|rom| rom.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// iNES image with 16 KB PRG ROM and 8 KB CHR ROM, mapper 0, horizontal
+    /// mirroring and no battery.
+    fn ines_image() -> Vec<u8> {
+        let mut raw = vec![0; HEADER_SIZE + 0x4000 + 0x2000];
+        raw[0..4].copy_from_slice(&NES_TAG);
+        raw[4] = 1;
+        raw[5] = 1;
+        for (i, byte) in raw[HEADER_SIZE..].iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+        raw
+    }
+
+    #[test]
+    fn missing_chr_rom_reports_default_chr_ram() {
+        let mut raw = ines_image();
+        raw[5] = 0;
+        raw.truncate(HEADER_SIZE + 0x4000);
+
+        let cartridge = Cartridge::from_bytes(&raw).unwrap();
+
+        assert!(cartridge.has_chr_ram);
+        assert_eq!(cartridge.chr.len(), CHR_RAM_SIZE);
+        assert_eq!(cartridge.chr_ram_size, CHR_RAM_SIZE);
+    }
+
+    #[test]
+    fn database_entry_overrides_wrong_header() {
+        let mut cartridge = Cartridge::from_bytes(&ines_image()).unwrap();
+        let (_, sha1) = cartridge.rom_hashes();
+        let game = GameInfo {
+            name: "Bad header",
+            sha1,
+            mapper: 1,
+            submapper: 0,
+            mirroring: Mirroring::Vertical,
+            has_battery: true,
+            timing: Timing::Pal,
+        };
+
+        cartridge.apply_game_info(&game);
+
+        assert_eq!(cartridge.mapper, 1);
+        assert_eq!(cartridge.mirroring, Mirroring::Vertical);
+        assert!(cartridge.has_battery);
+        assert_eq!(cartridge.prg_ram_size, 0);
+        assert_eq!(cartridge.prg_nvram_size, PRG_RAM_SIZE);
+        assert_eq!(cartridge.prg_ram.len(), PRG_RAM_SIZE);
+        assert_eq!(cartridge.timing, Timing::Pal);
+    }
+
+    #[test]
+    fn known_dump_overrides_wrong_header() {
+        // Donkey Kong is NROM with horizontal mirroring; claim MMC3, vertical
+        let mut raw = include_bytes!("../../testroms/donkey_kong.nes").to_vec();
+        raw[6] = 0x41;
+        let mut cartridge = Cartridge::from_bytes(&raw).unwrap();
+        assert_eq!(cartridge.mapper, 4);
+
+        let (crc, sha1) = cartridge.rom_hashes();
+        assert_eq!(crc, 0x6F97_C721);
+        assert_eq!(database::lookup(crc, &sha1).map(|game| game.name), Some("Donkey Kong (World)"));
+
+        cartridge.apply_database();
+
+        assert_eq!(cartridge.mapper, 0);
+        assert_eq!(cartridge.mirroring, Mirroring::Horizontal);
+    }
+
+    #[test]
+    fn nsf_file_is_rejected() {
+        let mut raw = vec![0; 0x80 + 0x1000];
+        raw[0..5].copy_from_slice(&NSF_TAG);
+
+        let result = Cartridge::from_bytes(&raw);
+
+        assert!(matches!(result, Err(CartridgeError::UnsupportedNsf)));
+    }
+
+    #[test]
+    fn unknown_game_keeps_header() {
+        let mut cartridge = Cartridge::from_bytes(&ines_image()).unwrap();
+        cartridge.apply_database();
+
+        assert_eq!(cartridge.mapper, 0);
+        assert_eq!(cartridge.mirroring, Mirroring::Horizontal);
+        assert!(!cartridge.has_battery);
+    }
 }
@@ -0,0 +1,168 @@
+use miniz_oxide::inflate::{decompress_to_vec_with_limit, TINFLStatus};
+
+use super::database::Crc32;
+use super::CartridgeError;
+
+const GZIP_MAGIC: [u8; 2] = [0x1F, 0x8B];
+const ZIP_LOCAL_HEADER: [u8; 4] = *b"PK\x03\x04";
+const ZIP_CENTRAL_HEADER: [u8; 4] = *b"PK\x01\x02";
+const ZIP_END_OF_DIRECTORY: [u8; 4] = *b"PK\x05\x06";
+
+const METHOD_STORED: u16 = 0;
+const METHOD_DEFLATE: u16 = 8;
+
+/// Largest file unpacked from an archive, far above any real ROM or disk
+const MAX_UNPACKED_SIZE: usize = 64 * 1024 * 1024;
+
+/// Extensions of the images picked out of a zip when no entry is named
+const ROM_EXTENSIONS: [&str; 4] = [".nes", ".fds", ".unf", ".nsf"];
+
+/// Unpack a .zip or .gz file, or return `raw` unchanged if it is neither.
+///
+/// From a zip, `entry` picks a file by name; without it the first entry
+/// with a ROM extension is used.
+pub fn unpack(raw: Vec<u8>, entry: Option<&str>) -> Result<Vec<u8>, CartridgeError> {
+    if raw.starts_with(&GZIP_MAGIC) {
+        gunzip(&raw)
+    } else if raw.starts_with(&ZIP_LOCAL_HEADER) {
+        unzip(&raw, entry)
+    } else {
+        Ok(raw)
+    }
+}
+
+/// Decompress a gzip stream (RFC 1952): a 10-byte header, optional fields,
+/// deflate data, then the CRC32 and size of the original.
+fn gunzip(raw: &[u8]) -> Result<Vec<u8>, CartridgeError> {
+    const FHCRC: u8 = 0x02;
+    const FEXTRA: u8 = 0x04;
+    const FNAME: u8 = 0x08;
+    const FCOMMENT: u8 = 0x10;
+
+    let truncated = CartridgeError::BadArchive("gzip file truncated");
+    if raw.len() < 18 {
+        return Err(truncated);
+    }
+    if raw[2] != METHOD_DEFLATE as u8 {
+        return Err(CartridgeError::BadArchive("unknown gzip compression method"));
+    }
+
+    let flags = raw[3];
+    let mut position = 10;
+    if flags & FEXTRA != 0 {
+        let length = read_u16(raw, position).ok_or(truncated)? as usize;
+        position += 2 + length;
+    }
+    for flag in [FNAME, FCOMMENT] {
+        if flags & flag != 0 {
+            let end = raw.get(position..).and_then(|rest| rest.iter().position(|&b| b == 0));
+            position += end.ok_or(CartridgeError::BadArchive("gzip file truncated"))? + 1;
+        }
+    }
+    if flags & FHCRC != 0 {
+        position += 2;
+    }
+
+    let trailer = raw.len() - 8;
+    let deflated = raw
+        .get(position..trailer)
+        .ok_or(CartridgeError::BadArchive("gzip file truncated"))?;
+    let data = inflate(deflated)?;
+
+    let crc = read_u32(raw, trailer).unwrap_or(0);
+    check_crc(&data, crc)?;
+    Ok(data)
+}
+
+/// Extract one file from a zip archive, found through the central directory
+/// at the end of the file. Zip64 archives are not supported.
+fn unzip(raw: &[u8], entry: Option<&str>) -> Result<Vec<u8>, CartridgeError> {
+    // The end of directory record is 22 bytes plus a comment of up to 64 KB
+    let search_from = raw.len().saturating_sub(22 + 0xFFFF);
+    let end_of_directory = (search_from..raw.len().saturating_sub(21))
+        .rev()
+        .find(|&offset| raw[offset..].starts_with(&ZIP_END_OF_DIRECTORY))
+        .ok_or(CartridgeError::BadArchive("zip directory not found"))?;
+
+    let entry_count = read_u16(raw, end_of_directory + 10).unwrap_or(0);
+    let mut position = read_u32(raw, end_of_directory + 16).unwrap_or(0) as usize;
+
+    for _ in 0..entry_count {
+        let header = raw
+            .get(position..position + 46)
+            .filter(|header| header.starts_with(&ZIP_CENTRAL_HEADER))
+            .ok_or(CartridgeError::BadArchive("zip directory is corrupt"))?;
+
+        let method = read_u16(header, 10).unwrap_or(0);
+        let crc = read_u32(header, 16).unwrap_or(0);
+        let compressed_size = read_u32(header, 20).unwrap_or(0) as usize;
+        let name_length = read_u16(header, 28).unwrap_or(0) as usize;
+        let extra_length = read_u16(header, 30).unwrap_or(0) as usize;
+        let comment_length = read_u16(header, 32).unwrap_or(0) as usize;
+        let local_header = read_u32(header, 42).unwrap_or(0) as usize;
+
+        let name_bytes = raw
+            .get(position + 46..position + 46 + name_length)
+            .ok_or(CartridgeError::BadArchive("zip directory is corrupt"))?;
+        let name = String::from_utf8_lossy(name_bytes);
+        position += 46 + name_length + extra_length + comment_length;
+
+        let wanted = match entry {
+            Some(entry) => name == entry,
+            None => {
+                let lower = name.to_ascii_lowercase();
+                ROM_EXTENSIONS.iter().any(|extension| lower.ends_with(extension))
+            }
+        };
+        if !wanted {
+            continue;
+        }
+
+        // The local header repeats the name and has its own extra field
+        let local_name_length = read_u16(raw, local_header + 26).unwrap_or(0) as usize;
+        let local_extra_length = read_u16(raw, local_header + 28).unwrap_or(0) as usize;
+        let data_begin = local_header + 30 + local_name_length + local_extra_length;
+        let compressed = data_begin
+            .checked_add(compressed_size)
+            .and_then(|data_end| raw.get(data_begin..data_end))
+            .ok_or(CartridgeError::BadArchive("zip entry truncated"))?;
+
+        let data = match method {
+            METHOD_STORED => compressed.to_vec(),
+            METHOD_DEFLATE => inflate(compressed)?,
+            _ => return Err(CartridgeError::BadArchive("unknown zip compression method")),
+        };
+        check_crc(&data, crc)?;
+        return Ok(data);
+    }
+
+    Err(CartridgeError::MissingArchiveEntry(
+        entry.unwrap_or("a .nes, .fds, .unf or .nsf file").to_string(),
+    ))
+}
+
+fn inflate(deflated: &[u8]) -> Result<Vec<u8>, CartridgeError> {
+    decompress_to_vec_with_limit(deflated, MAX_UNPACKED_SIZE).map_err(|e| match e.status {
+        TINFLStatus::HasMoreOutput => CartridgeError::BadArchive("unpacked file is too large"),
+        _ => CartridgeError::BadArchive("compressed data is corrupt"),
+    })
+}
+
+fn check_crc(data: &[u8], expected: u32) -> Result<(), CartridgeError> {
+    let mut crc = Crc32::new();
+    crc.update(data);
+    if crc.finish() != expected {
+        return Err(CartridgeError::BadArchive("CRC mismatch"));
+    }
+    Ok(())
+}
+
+fn read_u16(raw: &[u8], offset: usize) -> Option<u16> {
+    let bytes = raw.get(offset..offset + 2)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32(raw: &[u8], offset: usize) -> Option<u32> {
+    let bytes = raw.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
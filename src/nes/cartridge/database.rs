@@ -0,0 +1,155 @@
+mod games;
+
+use super::{Mirroring, Timing};
+
+/// Known-good board settings for a dumped game.
+pub struct GameInfo {
+    pub name: &'static str,
+    pub sha1: [u8; 20],
+    pub mapper: u16,
+    pub submapper: u8,
+    pub mirroring: Mirroring,
+    pub has_battery: bool,
+    pub timing: Timing,
+}
+
+/// Look up a game by the CRC32 and SHA-1 of its PRG ROM followed by its
+/// CHR ROM, which do not change when only the header is wrong. The table
+/// is keyed by CRC32 and the SHA-1 rules out collisions.
+pub fn lookup(crc: u32, sha1: &[u8; 20]) -> Option<&'static GameInfo> {
+    games::GAMES.get(&crc).filter(|game| game.sha1 == *sha1)
+}
+
+/// Running CRC32 (IEEE 802.3, as used by zip and the usual ROM databases).
+pub struct Crc32(u32);
+
+impl Crc32 {
+    pub fn new() -> Self {
+        Crc32(0xFFFF_FFFF)
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        for &byte in data {
+            self.0 ^= byte as u32;
+            for _ in 0..8 {
+                let mask = (self.0 & 1).wrapping_neg();
+                self.0 = (self.0 >> 1) ^ (0xEDB8_8320 & mask);
+            }
+        }
+    }
+
+    pub fn finish(&self) -> u32 {
+        !self.0
+    }
+}
+
+/// Running SHA-1 (FIPS 180-1).
+pub struct Sha1 {
+    state: [u32; 5],
+    block: [u8; 64],
+    block_len: usize,
+    length: u64,
+}
+
+impl Sha1 {
+    pub fn new() -> Self {
+        Sha1 {
+            state: [0x6745_2301, 0xEFCD_AB89, 0x98BA_DCFE, 0x1032_5476, 0xC3D2_E1F0],
+            block: [0; 64],
+            block_len: 0,
+            length: 0,
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.length += data.len() as u64;
+        for &byte in data {
+            self.block[self.block_len] = byte;
+            self.block_len += 1;
+            if self.block_len == 64 {
+                self.process_block();
+                self.block_len = 0;
+            }
+        }
+    }
+
+    pub fn finish(mut self) -> [u8; 20] {
+        // Pad with a 1 bit, zeros up to 56 mod 64, then the bit length
+        let bit_length = self.length.wrapping_mul(8);
+        self.update(&[0x80]);
+        while self.block_len != 56 {
+            self.update(&[0x00]);
+        }
+        self.update(&bit_length.to_be_bytes());
+
+        let mut digest = [0; 20];
+        for (chunk, word) in digest.chunks_exact_mut(4).zip(self.state) {
+            chunk.copy_from_slice(&word.to_be_bytes());
+        }
+        digest
+    }
+
+    fn process_block(&mut self) {
+        let mut w = [0u32; 80];
+        for (i, chunk) in self.block.chunks_exact(4).enumerate() {
+            w[i] = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = self.state;
+        for (i, &word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A82_7999),
+                20..=39 => (b ^ c ^ d, 0x6ED9_EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1B_BCDC),
+                _ => (b ^ c ^ d, 0xCA62_C1D6),
+            };
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+
+        for (state, value) in self.state.iter_mut().zip([a, b, c, d, e]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crc32_check_value() {
+        let mut crc = Crc32::new();
+        crc.update(b"123456789");
+        assert_eq!(crc.finish(), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn sha1_test_vectors() {
+        let hex = |digest: [u8; 20]| digest.iter().map(|b| format!("{b:02x}")).collect::<String>();
+
+        assert_eq!(hex(Sha1::new().finish()), "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+
+        let mut sha1 = Sha1::new();
+        sha1.update(b"abc");
+        assert_eq!(hex(sha1.finish()), "a9993e364706816aba3e25717850c26c9cd0d89d");
+
+        // Two blocks, fed in pieces
+        let mut sha1 = Sha1::new();
+        sha1.update(b"abcdbcdecdefdefgefghfghighij");
+        sha1.update(b"hijkijkljklmklmnlmnomnopnopq");
+        assert_eq!(hex(sha1.finish()), "84983e441c3bd26ebaae4aa1f95129e5e54670f1");
+    }
+}
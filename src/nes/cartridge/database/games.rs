@@ -0,0 +1,13 @@
+// Generated by tools/nes20db.py from the NES 2.0 XML database. Do not edit.
+
+use phf::phf_map;
+
+use super::super::{Mirroring, Timing};
+use super::GameInfo;
+
+#[rustfmt::skip]
+pub static GAMES: phf::Map<u32, GameInfo> = phf_map! {
+    0x2E6301EDu32 => GameInfo{name: "Super Mario Bros. 3 (USA)", sha1: [0xBB, 0x89, 0x4D, 0x10, 0x4C, 0x79, 0x6F, 0x69, 0xBA, 0x16, 0x58, 0x7E, 0xB6, 0x6C, 0x02, 0x75, 0xF5, 0xC2, 0xFC, 0x02], mapper: 4, submapper: 0, mirroring: Mirroring::Horizontal, has_battery: false, timing: Timing::Ntsc},
+    0x6F97C721u32 => GameInfo{name: "Donkey Kong (World)", sha1: [0xD2, 0x22, 0xDB, 0xBA, 0x5B, 0xD3, 0x71, 0x6B, 0xBF, 0x62, 0xCA, 0x91, 0x16, 0x7C, 0x6A, 0x9D, 0x15, 0xC6, 0x00, 0x65], mapper: 0, submapper: 0, mirroring: Mirroring::Horizontal, has_battery: false, timing: Timing::Ntsc},
+    0xF6035030u32 => GameInfo{name: "Contra (USA)", sha1: [0x97, 0x94, 0x94, 0xE7, 0x86, 0x9A, 0xC7, 0xAB, 0x48, 0x15, 0xFD, 0xBD, 0x1D, 0xC9, 0x9F, 0x89, 0x3F, 0x71, 0x3F, 0xBF], mapper: 2, submapper: 0, mirroring: Mirroring::Vertical, has_battery: false, timing: Timing::Ntsc},
+};
@@ -0,0 +1,190 @@
+use super::CartridgeError;
+
+pub const FDS_TAG: [u8; 4] = [0x46, 0x44, 0x53, 0x1A]; // "FDS" followed by MS-DOS end of file
+const FDS_HEADER_SIZE: usize = 0x10;
+
+/// Bytes of block data on one side of an .fds image
+const SIDE_SIZE: usize = 65500;
+
+/// Every side starts with the "*NINTENDO-HVC*" disk info block
+const DISK_INFO_BLOCK: [u8; 15] = *b"\x01*NINTENDO-HVC*";
+
+/// Gap before the first block (28300 bits) and after every block (976 bits)
+const LEAD_IN_GAP: usize = 28300 / 8;
+const BLOCK_GAP: usize = 976 / 8;
+
+/// Size of one side as the drive sees it, gaps included. The head takes
+/// about 8 seconds to travel this far at the drive's ~96 kbit/s. Sides
+/// whose blocks and gaps don't fit are made longer instead of cut short.
+const RAW_SIDE_SIZE: usize = 94_000;
+
+/// Famicom Disk System disk, stored as the drive reads it.
+///
+/// .fds images only hold the block data of each side. The drive also sees
+/// the gaps between blocks, the start mark before each block and the CRC
+/// after it, so those are added when the image is loaded. All sides are
+/// kept back to back, `side_size` bytes each.
+#[derive(Clone)]
+pub struct DiskImage {
+    data: Vec<u8>,
+    side_size: usize,
+}
+
+impl DiskImage {
+    /// Parse an .fds image, with or without the 16-byte fwNES header.
+    pub fn parse(raw: &[u8]) -> Result<DiskImage, CartridgeError> {
+        let (sides, expected_sides) = if raw.starts_with(&FDS_TAG) {
+            let expected = raw.get(4).copied().unwrap_or(0) as usize;
+            (raw.get(FDS_HEADER_SIZE..).unwrap_or(&[]), Some(expected))
+        } else {
+            (raw, None)
+        };
+
+        // Some headers leave the side count at 0
+        let side_count = expected_sides
+            .filter(|&count| count > 0)
+            .unwrap_or(sides.len() / SIDE_SIZE);
+        let expected = side_count.max(1) * SIDE_SIZE;
+        if side_count == 0 || sides.len() < expected {
+            return Err(CartridgeError::TruncatedDisk {
+                expected,
+                found: sides.len(),
+            });
+        }
+
+        let raw_sides: Vec<Vec<u8>> = sides
+            .chunks_exact(SIDE_SIZE)
+            .take(side_count)
+            .map(add_gaps)
+            .collect();
+        let side_size = raw_sides
+            .iter()
+            .map(|side| side.len())
+            .fold(RAW_SIDE_SIZE, usize::max);
+
+        let mut data = Vec::with_capacity(side_count * side_size);
+        for mut side in raw_sides {
+            side.resize(side_size, 0);
+            data.extend(side);
+        }
+
+        Ok(DiskImage { data, side_size })
+    }
+
+    /// Whether `raw` looks like an .fds image rather than a cartridge.
+    pub fn is_disk_image(raw: &[u8]) -> bool {
+        raw.starts_with(&FDS_TAG) || raw.starts_with(&DISK_INFO_BLOCK)
+    }
+
+    pub fn side_count(&self) -> usize {
+        self.data.len() / self.side_size
+    }
+
+    /// Length of every side, gaps included.
+    pub fn side_size(&self) -> usize {
+        self.side_size
+    }
+
+    pub fn side(&self, side: usize) -> &[u8] {
+        &self.data[side * self.side_size..(side + 1) * self.side_size]
+    }
+
+    pub fn side_mut(&mut self, side: usize) -> &mut [u8] {
+        &mut self.data[side * self.side_size..(side + 1) * self.side_size]
+    }
+
+    /// All sides back to back, as saved to the sidecar file.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn data_mut(&mut self) -> &mut [u8] {
+        &mut self.data
+    }
+}
+
+/// Lay out one side's blocks as the drive reads them:
+/// gap, $80 start mark, block, CRC, gap, $80 start mark, block, ...
+/// The caller pads the result to the side size.
+fn add_gaps(side: &[u8]) -> Vec<u8> {
+    let mut raw = vec![0; LEAD_IN_GAP];
+    let mut position = 0;
+    let mut file_size = 0;
+
+    while position < side.len() {
+        let length = match side[position] {
+            // Disk info
+            1 => 56,
+            // File amount
+            2 => 2,
+            // File header, which holds the size of the file data that follows
+            3 => {
+                file_size = side
+                    .get(position + 13..position + 15)
+                    .map_or(0, |size| u16::from_le_bytes([size[0], size[1]]) as usize);
+                16
+            }
+            // File data
+            4 => 1 + file_size,
+            // Unused space
+            _ => break,
+        };
+
+        let Some(block) = side.get(position..position + length) else {
+            break;
+        };
+        raw.push(0x80);
+        raw.extend_from_slice(block);
+        // The BIOS only checks the CRC flag in $4030, never the CRC itself
+        raw.extend_from_slice(&[0x4D, 0x62]);
+        raw.extend_from_slice(&[0; BLOCK_GAP]);
+        position += length;
+    }
+
+    raw
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One side holding the disk info block and `files` one-byte files,
+    /// the data byte of file n being n.
+    fn side_with_files(files: usize) -> Vec<u8> {
+        let mut side = DISK_INFO_BLOCK.to_vec();
+        side.resize(56, 0);
+        side.extend_from_slice(&[0x02, files as u8]);
+        for n in 0..files {
+            let mut header = [0u8; 16];
+            header[0] = 0x03;
+            header[13] = 1; // File size
+            side.extend_from_slice(&header);
+            side.extend_from_slice(&[0x04, n as u8]);
+        }
+        side.resize(SIDE_SIZE, 0);
+        side
+    }
+
+    #[test]
+    fn short_side_is_padded() {
+        let disk = DiskImage::parse(&side_with_files(4)).unwrap();
+
+        assert_eq!(disk.side_count(), 1);
+        assert_eq!(disk.side_size(), RAW_SIDE_SIZE);
+    }
+
+    #[test]
+    fn long_side_is_not_truncated() {
+        // Each file takes 18 bytes of data but 268 on the raw side
+        let files = 1000;
+        let disk = DiskImage::parse(&side_with_files(files)).unwrap();
+
+        assert_eq!(disk.side_count(), 1);
+        assert!(disk.side_size() > RAW_SIDE_SIZE);
+
+        // Every file's data block is still on the side
+        let data_blocks = disk.side(0).windows(2).filter(|window| *window == [0x80, 0x04]).count();
+        assert_eq!(data_blocks, files);
+        assert_eq!(disk.data().len(), disk.side_size());
+    }
+}
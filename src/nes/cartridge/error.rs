@@ -0,0 +1,93 @@
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum CartridgeError {
+    Io(io::Error),
+    /// The file does not start with the "NES\x1A" signature
+    BadMagic,
+    /// The file ends inside the 16-byte header
+    TruncatedHeader { found: usize },
+    TruncatedPrg { expected: usize, found: usize },
+    TruncatedChr { expected: usize, found: usize },
+    /// The disk image is shorter than its sides
+    TruncatedDisk { expected: usize, found: usize },
+    /// A disk image was given without the FDS BIOS
+    MissingBios,
+    /// The FDS BIOS is not 8 KB
+    BadBios { found: usize },
+    /// A UNIF chunk runs past the end of the file
+    TruncatedChunk(String),
+    /// A UNIF image lacks a required chunk
+    MissingChunk(&'static str),
+    /// The UNIF board name does not match any emulated mapper
+    UnsupportedBoard(String),
+    /// The .zip or .gz file could not be unpacked
+    BadArchive(&'static str),
+    /// The zip has no entry with the requested name, or no ROM at all
+    MissingArchiveEntry(String),
+    /// The IPS/UPS/BPS patch is malformed or produced a bad ROM
+    BadPatch(&'static str),
+    /// The UPS/BPS patch was made for a ROM with a different CRC32
+    PatchBaseMismatch { expected: u32, found: u32 },
+    UnsupportedMapper(u16),
+    /// The file is an NSF music rip, which can't be played yet
+    UnsupportedNsf,
+    /// A known ROM format this loader can't read yet
+    UnsupportedHeaderVersion(&'static str),
+}
+
+impl fmt::Display for CartridgeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CartridgeError::Io(e) => write!(f, "{e}"),
+            CartridgeError::BadMagic => write!(f, "NES signature not found"),
+            CartridgeError::TruncatedHeader { found } => {
+                write!(f, "header truncated: expected 16 bytes, found {found}")
+            }
+            CartridgeError::TruncatedPrg { expected, found } => {
+                write!(f, "PRG ROM truncated: expected {expected} bytes, found {found}")
+            }
+            CartridgeError::TruncatedChr { expected, found } => {
+                write!(f, "CHR ROM truncated: expected {expected} bytes, found {found}")
+            }
+            CartridgeError::TruncatedDisk { expected, found } => {
+                write!(f, "disk image truncated: expected {expected} bytes, found {found}")
+            }
+            CartridgeError::MissingBios => write!(f, "disk images need the FDS BIOS (--fds-bios=<path>)"),
+            CartridgeError::BadBios { found } => {
+                write!(f, "FDS BIOS should be 8192 bytes, found {found}")
+            }
+            CartridgeError::TruncatedChunk(chunk) => write!(f, "UNIF chunk {chunk} truncated"),
+            CartridgeError::MissingChunk(chunk) => write!(f, "UNIF image has no {chunk} chunk"),
+            CartridgeError::UnsupportedBoard(board) => write!(f, "board {board} is not supported"),
+            CartridgeError::BadArchive(reason) => write!(f, "cannot unpack archive: {reason}"),
+            CartridgeError::MissingArchiveEntry(entry) => write!(f, "archive does not contain {entry}"),
+            CartridgeError::BadPatch(reason) => write!(f, "cannot apply patch: {reason}"),
+            CartridgeError::PatchBaseMismatch { expected, found } => write!(
+                f,
+                "patch is for a different ROM: expected CRC32 {expected:08X}, found {found:08X}"
+            ),
+            CartridgeError::UnsupportedMapper(mapper) => write!(f, "mapper {mapper} is not supported"),
+            CartridgeError::UnsupportedNsf => write!(f, "NSF music files are not supported"),
+            CartridgeError::UnsupportedHeaderVersion(format) => {
+                write!(f, "{format} images are not supported")
+            }
+        }
+    }
+}
+
+impl std::error::Error for CartridgeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CartridgeError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for CartridgeError {
+    fn from(e: io::Error) -> Self {
+        CartridgeError::Io(e)
+    }
+}
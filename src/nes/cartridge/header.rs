@@ -0,0 +1,233 @@
+use super::{CartridgeError, Mirroring};
+
+pub const HEADER_SIZE: usize = 0x10;
+
+pub const NES_TAG: [u8; 4] = [0x4E, 0x45, 0x53, 0x1A];
+const PRG_ROM_BLOCK_SIZE: usize = 0x4000;
+const CHR_ROM_BLOCK_SIZE: usize = 0x2000;
+const PRG_RAM_BLOCK_SIZE: usize = 0x2000;
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HeaderFormat {
+    #[default]
+    INes,
+    Nes20,
+    /// Board-name based UNIF image
+    Unif,
+}
+
+/// CPU/PPU timing the game was made for.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Timing {
+    #[default]
+    Ntsc,
+    Pal,
+    /// Runs on both NTSC and PAL machines
+    MultiRegion,
+    Dendy,
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ConsoleType {
+    #[default]
+    Nes,
+    VsSystem,
+    Playchoice10,
+    /// NES 2.0 extended console type (byte 13, bits 0-3)
+    Extended(u8),
+}
+
+/// Input device the game expects to be plugged in (NES 2.0 byte 15).
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ExpansionDevice {
+    #[default]
+    Unspecified,
+    StandardControllers,
+    FourScore,
+    FamicomFourPlayers,
+    VsSystem4016,
+    VsSystem4017,
+    VsZapper,
+    Zapper,
+    TwoZappers,
+    BandaiHyperShot,
+    PowerPadSideA,
+    PowerPadSideB,
+    FamilyTrainerSideA,
+    FamilyTrainerSideB,
+    ArkanoidNes,
+    ArkanoidFamicom,
+    Other(u8),
+}
+
+impl From<u8> for ExpansionDevice {
+    fn from(value: u8) -> Self {
+        match value {
+            0x00 => ExpansionDevice::Unspecified,
+            0x01 => ExpansionDevice::StandardControllers,
+            0x02 => ExpansionDevice::FourScore,
+            0x03 => ExpansionDevice::FamicomFourPlayers,
+            0x04 => ExpansionDevice::VsSystem4016,
+            0x05 => ExpansionDevice::VsSystem4017,
+            0x07 => ExpansionDevice::VsZapper,
+            0x08 => ExpansionDevice::Zapper,
+            0x09 => ExpansionDevice::TwoZappers,
+            0x0A => ExpansionDevice::BandaiHyperShot,
+            0x0B => ExpansionDevice::PowerPadSideA,
+            0x0C => ExpansionDevice::PowerPadSideB,
+            0x0D => ExpansionDevice::FamilyTrainerSideA,
+            0x0E => ExpansionDevice::FamilyTrainerSideB,
+            0x0F => ExpansionDevice::ArkanoidNes,
+            0x10 => ExpansionDevice::ArkanoidFamicom,
+            other => ExpansionDevice::Other(other),
+        }
+    }
+}
+
+/// Decoded iNES / NES 2.0 header.
+///
+/// 0-3: "NES" followed by MS-DOS end of file
+/// 4:   PRG ROM size LSB
+/// 5:   CHR ROM size LSB
+/// 6:   Mapper D3-D0, four-screen, trainer, battery, mirroring
+/// 7:   Mapper D7-D4, header format (bits 2-3), console type
+/// 8:   iNES: PRG RAM size      NES 2.0: submapper, mapper D11-D8
+/// 9:   iNES: TV system         NES 2.0: CHR/PRG ROM size MSB
+/// 10:  NES 2.0: PRG NVRAM/RAM shift counts
+/// 11:  NES 2.0: CHR NVRAM/RAM shift counts
+/// 12:  NES 2.0: CPU/PPU timing
+/// 13:  NES 2.0: Vs. System type or extended console type
+/// 14:  NES 2.0: miscellaneous ROM count
+/// 15:  NES 2.0: default expansion device
+pub struct Header {
+    pub format: HeaderFormat,
+    pub mapper: u16,
+    pub submapper: u8,
+    pub mirroring: Mirroring,
+    pub has_battery: bool,
+    pub has_trainer: bool,
+    pub has_four_screen: bool,
+    pub prg_rom_size: usize,
+    pub chr_rom_size: usize,
+    pub prg_ram_size: usize,
+    pub prg_nvram_size: usize,
+    pub chr_ram_size: usize,
+    pub chr_nvram_size: usize,
+    pub timing: Timing,
+    pub console_type: ConsoleType,
+    pub expansion_device: ExpansionDevice,
+}
+
+impl Header {
+    pub fn parse(raw: &[u8; HEADER_SIZE]) -> Result<Header, CartridgeError> {
+        if raw[0..4] != NES_TAG {
+            return Err(CartridgeError::BadMagic);
+        }
+
+        let mirroring = if raw[6] & 0b1 != 0 {
+            Mirroring::Vertical
+        } else {
+            Mirroring::Horizontal
+        };
+
+        let console_type = match raw[7] & 0b11 {
+            0 => ConsoleType::Nes,
+            1 => ConsoleType::VsSystem,
+            2 => ConsoleType::Playchoice10,
+            _ => ConsoleType::Extended(raw[13] & 0x0F),
+        };
+
+        let mut header = Header {
+            format: HeaderFormat::INes,
+            mapper: ((raw[7] & 0xF0) | (raw[6] >> 4)) as u16,
+            submapper: 0,
+            mirroring,
+            has_battery: raw[6] & 0b10 != 0,
+            has_trainer: raw[6] & 0b100 != 0,
+            has_four_screen: raw[6] & 0b1000 != 0,
+            prg_rom_size: raw[4] as usize * PRG_ROM_BLOCK_SIZE,
+            chr_rom_size: raw[5] as usize * CHR_ROM_BLOCK_SIZE,
+            prg_ram_size: 0,
+            prg_nvram_size: 0,
+            chr_ram_size: 0,
+            chr_nvram_size: 0,
+            timing: Timing::Ntsc,
+            console_type,
+            expansion_device: ExpansionDevice::Unspecified,
+        };
+
+        match raw[7] & 0b1100 {
+            0b1000 => header.parse_nes20(raw),
+            format => header.parse_ines(raw, format),
+        }
+
+        Ok(header)
+    }
+
+    fn parse_ines(&mut self, raw: &[u8; HEADER_SIZE], format: u8) {
+        // Archaic iNES dumps often have junk ("DiskDude!") in bytes 7-15,
+        // which would corrupt the upper mapper nibble.
+        let archaic = format != 0 || raw[12..16].iter().any(|&b| b != 0);
+
+        // Byte 8 is the PRG RAM size in 8 KB units, 0 meaning 8 KB
+        let prg_ram_blocks = if archaic { 1 } else { (raw[8] as usize).max(1) };
+        let prg_ram_size = prg_ram_blocks * PRG_RAM_BLOCK_SIZE;
+        if self.has_battery {
+            self.prg_nvram_size = prg_ram_size;
+        } else {
+            self.prg_ram_size = prg_ram_size;
+        }
+
+        if archaic {
+            self.mapper &= 0x0F;
+            self.console_type = ConsoleType::Nes;
+        } else if raw[9] & 0b1 != 0 {
+            self.timing = Timing::Pal;
+        }
+    }
+
+    fn parse_nes20(&mut self, raw: &[u8; HEADER_SIZE]) {
+        self.format = HeaderFormat::Nes20;
+        self.mapper |= ((raw[8] & 0x0F) as u16) << 8;
+        self.submapper = raw[8] >> 4;
+
+        self.prg_rom_size = rom_size(raw[4], raw[9] & 0x0F, PRG_ROM_BLOCK_SIZE);
+        self.chr_rom_size = rom_size(raw[5], raw[9] >> 4, CHR_ROM_BLOCK_SIZE);
+
+        self.prg_ram_size = ram_size(raw[10] & 0x0F);
+        self.prg_nvram_size = ram_size(raw[10] >> 4);
+        self.chr_ram_size = ram_size(raw[11] & 0x0F);
+        self.chr_nvram_size = ram_size(raw[11] >> 4);
+
+        self.timing = match raw[12] & 0b11 {
+            0 => Timing::Ntsc,
+            1 => Timing::Pal,
+            2 => Timing::MultiRegion,
+            _ => Timing::Dendy,
+        };
+
+        self.expansion_device = ExpansionDevice::from(raw[15] & 0x3F);
+    }
+}
+
+/// NES 2.0 ROM size from the LSB byte and the MSB nibble. An MSB of $F
+/// switches to exponent-multiplier notation: LSB is EEEEEEMM and the size
+/// is 2^E * (MM*2+1) bytes.
+fn rom_size(lsb: u8, msb: u8, block_size: usize) -> usize {
+    if msb == 0x0F {
+        let exponent = (lsb >> 2) as u32;
+        let multiplier = (lsb & 0b11) as usize * 2 + 1;
+        2usize.saturating_pow(exponent).saturating_mul(multiplier)
+    } else {
+        (((msb as usize) << 8) | lsb as usize) * block_size
+    }
+}
+
+/// NES 2.0 RAM size from a shift count: 0 means none, otherwise 64 << count.
+fn ram_size(shift: u8) -> usize {
+    if shift == 0 {
+        0
+    } else {
+        64 << shift
+    }
+}
@@ -0,0 +1,359 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::database::Crc32;
+use super::CartridgeError;
+
+const IPS_MAGIC: &[u8] = b"PATCH";
+const IPS_EOF: &[u8] = b"EOF";
+const UPS_MAGIC: &[u8] = b"UPS1";
+const BPS_MAGIC: &[u8] = b"BPS1";
+
+/// UPS and BPS end with the CRC32s of the source, the target and the patch
+const FOOTER_SIZE: usize = 12;
+
+/// Largest output a UPS or BPS patch may ask for, well above any NES ROM
+/// or disk image. Sizes come straight from the patch file.
+const MAX_TARGET_SIZE: usize = 64 * 1024 * 1024;
+
+const PATCH_EXTENSIONS: [&str; 3] = ["ips", "ups", "bps"];
+
+/// The patch to apply: `explicit` if given, otherwise a .ips, .ups or .bps
+/// file next to the ROM with the same name.
+pub fn find_patch(rom_path: &str, explicit: Option<&Path>) -> Option<PathBuf> {
+    if let Some(path) = explicit {
+        return Some(path.to_path_buf());
+    }
+
+    PATCH_EXTENSIONS
+        .iter()
+        .map(|extension| Path::new(rom_path).with_extension(extension))
+        .find(|path| path.is_file())
+}
+
+/// Apply an IPS, UPS or BPS patch to a ROM image.
+pub fn apply(rom: Vec<u8>, path: &Path) -> Result<Vec<u8>, CartridgeError> {
+    let patch = fs::read(path)?;
+
+    if patch.starts_with(IPS_MAGIC) {
+        apply_ips(rom, &patch)
+    } else if patch.starts_with(UPS_MAGIC) {
+        apply_ups(&rom, &patch)
+    } else if patch.starts_with(BPS_MAGIC) {
+        apply_bps(&rom, &patch)
+    } else {
+        Err(CartridgeError::BadPatch("unknown patch format"))
+    }
+}
+
+/// IPS: records of a 24-bit offset, a 16-bit size and that many bytes, or a
+/// size of 0 followed by a 16-bit run length and the byte to repeat. "EOF"
+/// ends the list and may be followed by a 24-bit size to truncate to.
+/// IPS has no checksums, so any ROM is accepted.
+fn apply_ips(mut rom: Vec<u8>, patch: &[u8]) -> Result<Vec<u8>, CartridgeError> {
+    let truncated = || CartridgeError::BadPatch("IPS patch truncated");
+    let mut reader = PatchReader::new(patch, IPS_MAGIC.len());
+
+    loop {
+        let record = reader.bytes(3).ok_or_else(truncated)?;
+        if record == IPS_EOF {
+            break;
+        }
+        let offset = (record[0] as usize) << 16 | (record[1] as usize) << 8 | record[2] as usize;
+
+        let size = reader.u16_be().ok_or_else(truncated)? as usize;
+        let data = if size == 0 {
+            let run_length = reader.u16_be().ok_or_else(truncated)? as usize;
+            let value = reader.byte().ok_or_else(truncated)?;
+            vec![value; run_length]
+        } else {
+            reader.bytes(size).ok_or_else(truncated)?.to_vec()
+        };
+
+        if rom.len() < offset + data.len() {
+            rom.resize(offset + data.len(), 0);
+        }
+        rom[offset..offset + data.len()].copy_from_slice(&data);
+    }
+
+    if let Some(size) = reader.bytes(3) {
+        rom.truncate((size[0] as usize) << 16 | (size[1] as usize) << 8 | size[2] as usize);
+    }
+
+    Ok(rom)
+}
+
+/// UPS: source and target sizes, then runs of (bytes to skip, bytes XORed
+/// with the source up to and including a 0), then the checksum footer.
+fn apply_ups(source: &[u8], patch: &[u8]) -> Result<Vec<u8>, CartridgeError> {
+    let truncated = || CartridgeError::BadPatch("UPS patch truncated");
+    let out_of_range = || CartridgeError::BadPatch("UPS patch writes outside the ROM");
+    let footer = check_footer(source, patch)?;
+    let mut reader = PatchReader::new(&patch[..footer], UPS_MAGIC.len());
+
+    let _source_size = reader.number().ok_or_else(truncated)?;
+    let target_size = reader.number().ok_or_else(truncated)?;
+    if target_size > MAX_TARGET_SIZE {
+        return Err(CartridgeError::BadPatch("UPS target too large"));
+    }
+
+    let mut target = source.to_vec();
+    target.resize(target_size, 0);
+
+    let mut position: usize = 0;
+    while !reader.at_end() {
+        let skip = reader.number().ok_or_else(truncated)?;
+        position = position.checked_add(skip).ok_or_else(out_of_range)?;
+        loop {
+            let value = reader.byte().ok_or_else(truncated)?;
+            if let Some(byte) = target.get_mut(position) {
+                *byte = source.get(position).copied().unwrap_or(0) ^ value;
+            }
+            position = position.checked_add(1).ok_or_else(out_of_range)?;
+            if value == 0 {
+                break;
+            }
+        }
+    }
+
+    check_target(&target, patch)?;
+    Ok(target)
+}
+
+/// BPS: source and target sizes and metadata, then commands building the
+/// target from the source, the patch and earlier target bytes, then the
+/// checksum footer.
+fn apply_bps(source: &[u8], patch: &[u8]) -> Result<Vec<u8>, CartridgeError> {
+    const SOURCE_READ: usize = 0;
+    const TARGET_READ: usize = 1;
+    const SOURCE_COPY: usize = 2;
+
+    let truncated = || CartridgeError::BadPatch("BPS patch truncated");
+    let out_of_range = || CartridgeError::BadPatch("BPS copy outside the ROM");
+    let footer = check_footer(source, patch)?;
+    let mut reader = PatchReader::new(&patch[..footer], BPS_MAGIC.len());
+
+    let _source_size = reader.number().ok_or_else(truncated)?;
+    let target_size = reader.number().ok_or_else(truncated)?;
+    if target_size > MAX_TARGET_SIZE {
+        return Err(CartridgeError::BadPatch("BPS target too large"));
+    }
+    let metadata_size = reader.number().ok_or_else(truncated)?;
+    reader.bytes(metadata_size).ok_or_else(truncated)?;
+
+    let mut target = Vec::with_capacity(target_size);
+    let mut source_offset: isize = 0;
+    let mut target_offset: isize = 0;
+
+    while !reader.at_end() {
+        let command = reader.number().ok_or_else(truncated)?;
+        let length = (command >> 2) + 1;
+        if target.len().checked_add(length).is_none_or(|end| end > target_size) {
+            return Err(CartridgeError::BadPatch("BPS output larger than its target size"));
+        }
+
+        match command & 0x03 {
+            SOURCE_READ => {
+                let begin = target.len();
+                let data = source.get(begin..begin + length).ok_or_else(out_of_range)?;
+                target.extend_from_slice(data);
+            }
+            TARGET_READ => target.extend_from_slice(reader.bytes(length).ok_or_else(truncated)?),
+            action => {
+                let offset = reader.number().ok_or_else(truncated)?;
+                let delta = (offset >> 1) as isize * if offset & 1 != 0 { -1 } else { 1 };
+
+                if action == SOURCE_COPY {
+                    source_offset = source_offset.checked_add(delta).ok_or_else(out_of_range)?;
+                    let begin = usize::try_from(source_offset).map_err(|_| out_of_range())?;
+                    let end = begin.checked_add(length).ok_or_else(out_of_range)?;
+                    let data = source.get(begin..end).ok_or_else(out_of_range)?;
+                    target.extend_from_slice(data);
+                    source_offset = end as isize;
+                } else {
+                    // Target copies may overlap the bytes being written
+                    target_offset = target_offset.checked_add(delta).ok_or_else(out_of_range)?;
+                    for _ in 0..length {
+                        let index = usize::try_from(target_offset).map_err(|_| out_of_range())?;
+                        let value = *target.get(index).ok_or_else(out_of_range)?;
+                        target.push(value);
+                        target_offset += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    if target.len() != target_size {
+        return Err(CartridgeError::BadPatch("BPS output has the wrong size"));
+    }
+    check_target(&target, patch)?;
+    Ok(target)
+}
+
+/// Check the patch's own CRC and that it was made for `source`.
+/// Returns where the footer starts.
+fn check_footer(source: &[u8], patch: &[u8]) -> Result<usize, CartridgeError> {
+    let footer = patch
+        .len()
+        .checked_sub(FOOTER_SIZE)
+        .ok_or(CartridgeError::BadPatch("patch truncated"))?;
+
+    if crc32(&patch[..patch.len() - 4]) != read_u32(patch, patch.len() - 4) {
+        return Err(CartridgeError::BadPatch("patch checksum mismatch"));
+    }
+
+    let expected = read_u32(patch, footer);
+    let found = crc32(source);
+    if expected != found {
+        return Err(CartridgeError::PatchBaseMismatch { expected, found });
+    }
+
+    Ok(footer)
+}
+
+fn check_target(target: &[u8], patch: &[u8]) -> Result<(), CartridgeError> {
+    if crc32(target) != read_u32(patch, patch.len() - 8) {
+        return Err(CartridgeError::BadPatch("patched ROM checksum mismatch"));
+    }
+    Ok(())
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = Crc32::new();
+    crc.update(data);
+    crc.finish()
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+struct PatchReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> PatchReader<'a> {
+    fn new(data: &'a [u8], position: usize) -> Self {
+        PatchReader { data, position }
+    }
+
+    fn at_end(&self) -> bool {
+        self.position >= self.data.len()
+    }
+
+    fn byte(&mut self) -> Option<u8> {
+        let value = *self.data.get(self.position)?;
+        self.position += 1;
+        Some(value)
+    }
+
+    fn bytes(&mut self, count: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.position..self.position.checked_add(count)?)?;
+        self.position += count;
+        Some(bytes)
+    }
+
+    fn u16_be(&mut self) -> Option<u16> {
+        let bytes = self.bytes(2)?;
+        Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    /// UPS/BPS variable-length number: 7 bits per byte, least significant
+    /// first, with bit 7 marking the last byte.
+    fn number(&mut self) -> Option<usize> {
+        let mut value: usize = 0;
+        let mut shift: usize = 1;
+        loop {
+            let byte = self.byte()?;
+            value = value.checked_add(((byte & 0x7F) as usize).checked_mul(shift)?)?;
+            if byte & 0x80 != 0 {
+                return Some(value);
+            }
+            shift = shift.checked_mul(0x80)?;
+            value = value.checked_add(shift)?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// UPS/BPS variable-length number.
+    fn number(mut value: usize) -> Vec<u8> {
+        let mut out = Vec::new();
+        loop {
+            let byte = (value & 0x7F) as u8;
+            value >>= 7;
+            if value == 0 {
+                out.push(byte | 0x80);
+                return out;
+            }
+            out.push(byte);
+            value -= 1;
+        }
+    }
+
+    /// Finish a patch body with a valid footer for `source`.
+    fn with_footer(mut patch: Vec<u8>, source: &[u8]) -> Vec<u8> {
+        patch.extend_from_slice(&crc32(source).to_le_bytes());
+        patch.extend_from_slice(&0u32.to_le_bytes());
+        patch.extend_from_slice(&crc32(&patch).to_le_bytes());
+        patch
+    }
+
+    fn is_bad_patch<T>(result: Result<T, CartridgeError>) -> bool {
+        matches!(result, Err(CartridgeError::BadPatch(_)))
+    }
+
+    #[test]
+    fn ups_rejects_huge_target() {
+        let source = [0u8; 16];
+        let mut patch = UPS_MAGIC.to_vec();
+        patch.extend(number(source.len()));
+        patch.extend(number(1 << 40));
+
+        assert!(is_bad_patch(apply_ups(&source, &with_footer(patch, &source))));
+    }
+
+    #[test]
+    fn ups_rejects_offset_overflow() {
+        let source = [0u8; 16];
+        let mut patch = UPS_MAGIC.to_vec();
+        patch.extend(number(source.len()));
+        patch.extend(number(source.len()));
+        for _ in 0..4 {
+            patch.extend(number(usize::MAX / 4));
+            patch.push(0x00);
+        }
+
+        assert!(is_bad_patch(apply_ups(&source, &with_footer(patch, &source))));
+    }
+
+    #[test]
+    fn bps_rejects_huge_target() {
+        let source = [0u8; 16];
+        let mut patch = BPS_MAGIC.to_vec();
+        patch.extend(number(source.len()));
+        patch.extend(number(1 << 40));
+        patch.extend(number(0));
+
+        assert!(is_bad_patch(apply_bps(&source, &with_footer(patch, &source))));
+    }
+
+    #[test]
+    fn bps_rejects_output_past_target_size() {
+        let source = [0u8; 16];
+        let mut patch = BPS_MAGIC.to_vec();
+        patch.extend(number(source.len()));
+        patch.extend(number(source.len()));
+        patch.extend(number(0));
+        // Target copy of 2^60 bytes, overlapping its own output
+        patch.extend(number((1 << 62) | 3));
+        patch.extend(number(0));
+
+        assert!(is_bad_patch(apply_bps(&source, &with_footer(patch, &source))));
+    }
+}
@@ -0,0 +1,198 @@
+use super::{CartridgeError, Mirroring, Timing};
+
+pub const UNIF_TAG: [u8; 4] = [0x55, 0x4E, 0x49, 0x46]; // "UNIF"
+const UNIF_HEADER_SIZE: usize = 0x20;
+const CHUNK_HEADER_SIZE: usize = 8;
+
+/// Maker prefixes that come before the board in UNIF board names
+const BOARD_PREFIXES: [&str; 11] = [
+    "NES-", "HVC-", "UNL-", "BTL-", "AVE-", "BMC-",
+    "CAMERICA-", "IREM-", "KONAMI-", "SUNSOFT-", "TAITO-",
+];
+
+/// Contents of a UNIF image.
+///
+/// After a 32-byte header ("UNIF", revision, padding) the file is a list of
+/// chunks, each a 4-byte ID, a little-endian 32-bit length and the data:
+///
+/// MAPR: Board name, null terminated
+/// PRG0-PRGF: PRG ROM, concatenated in hex order
+/// CHR0-CHRF: CHR ROM, concatenated in hex order
+/// MIRR: 0 horizontal, 1 vertical, 2/3 single screen, 4 four-screen, 5 mapper controlled
+/// BATR: Present when PRG RAM is battery backed
+/// TVCI: 0 NTSC, 1 PAL, 2 both
+/// VROR: Present when CHR is RAM even though CHR chunks exist
+///
+/// Other chunks (NAME, READ, DINF, CTRL, ...) are ignored.
+pub struct UnifImage {
+    pub board: String,
+    pub prg_rom: Vec<u8>,
+    pub chr_rom: Vec<u8>,
+    /// None for boards that control mirroring themselves
+    pub mirroring: Option<Mirroring>,
+    pub has_battery: bool,
+    pub has_chr_ram: bool,
+    pub timing: Timing,
+}
+
+impl UnifImage {
+    pub fn parse(raw: &[u8]) -> Result<UnifImage, CartridgeError> {
+        if raw.len() < UNIF_HEADER_SIZE {
+            return Err(CartridgeError::TruncatedHeader { found: raw.len() });
+        }
+
+        let mut board = None;
+        let mut prg_chunks: [Option<&[u8]>; 16] = [None; 16];
+        let mut chr_chunks: [Option<&[u8]>; 16] = [None; 16];
+        let mut image = UnifImage {
+            board: String::new(),
+            prg_rom: Vec::new(),
+            chr_rom: Vec::new(),
+            mirroring: None,
+            has_battery: false,
+            has_chr_ram: false,
+            timing: Timing::Ntsc,
+        };
+
+        let mut position = UNIF_HEADER_SIZE;
+        while position < raw.len() {
+            let chunk_header = raw
+                .get(position..position + CHUNK_HEADER_SIZE)
+                .ok_or_else(|| CartridgeError::TruncatedChunk(chunk_name(&raw[position..])))?;
+            let id: [u8; 4] = chunk_header[0..4].try_into().unwrap();
+            let length = u32::from_le_bytes(chunk_header[4..8].try_into().unwrap()) as usize;
+
+            let begin = position + CHUNK_HEADER_SIZE;
+            let data = begin
+                .checked_add(length)
+                .and_then(|end| raw.get(begin..end))
+                .ok_or_else(|| CartridgeError::TruncatedChunk(chunk_name(&id)))?;
+            position = begin + length;
+
+            match &id {
+                b"MAPR" => {
+                    let name = data.split(|&b| b == 0).next().unwrap_or(&[]);
+                    board = Some(String::from_utf8_lossy(name).trim().to_string());
+                }
+                [b'P', b'R', b'G', index] => {
+                    if let Some(slot) = hex_digit(*index) {
+                        prg_chunks[slot] = Some(data);
+                    }
+                }
+                [b'C', b'H', b'R', index] => {
+                    if let Some(slot) = hex_digit(*index) {
+                        chr_chunks[slot] = Some(data);
+                    }
+                }
+                b"MIRR" => {
+                    image.mirroring = match data.first() {
+                        Some(0) => Some(Mirroring::Horizontal),
+                        Some(1) => Some(Mirroring::Vertical),
+                        Some(2) => Some(Mirroring::SingleScreenLower),
+                        Some(3) => Some(Mirroring::SingleScreenUpper),
+                        Some(4) => Some(Mirroring::FourScreen),
+                        _ => None,
+                    };
+                }
+                b"BATR" => image.has_battery = true,
+                b"VROR" => image.has_chr_ram = true,
+                b"TVCI" => {
+                    image.timing = match data.first() {
+                        Some(1) => Timing::Pal,
+                        Some(2) => Timing::MultiRegion,
+                        _ => Timing::Ntsc,
+                    };
+                }
+                _ => {}
+            }
+        }
+
+        image.board = board.ok_or(CartridgeError::MissingChunk("MAPR"))?;
+        image.prg_rom = prg_chunks.iter().flatten().flat_map(|chunk| chunk.iter().copied()).collect();
+        image.chr_rom = chr_chunks.iter().flatten().flat_map(|chunk| chunk.iter().copied()).collect();
+        if image.prg_rom.is_empty() {
+            return Err(CartridgeError::MissingChunk("PRG0"));
+        }
+
+        Ok(image)
+    }
+
+    /// iNES mapper and submapper for the board, if it is one we emulate.
+    ///
+    /// Board names are usually "<prefix>-<board>", where the prefix (NES,
+    /// HVC, UNL, BTL, ...) only says who made it. Some have no prefix, and
+    /// some boards have a dash in their own name ("NINA-001").
+    pub fn mapper(&self) -> Option<(u16, u8)> {
+        let board = BOARD_PREFIXES
+            .iter()
+            .find_map(|prefix| self.board.strip_prefix(prefix))
+            .unwrap_or(&self.board);
+
+        let mapper = match board {
+            "NROM" | "NROM-128" | "NROM-256" | "RROM" | "RROM-128" => 0,
+            "SAROM" | "SBROM" | "SCROM" | "SC1ROM" | "SEROM" | "SFROM" | "SGROM" | "SHROM"
+            | "SJROM" | "SKROM" | "SLROM" | "SL1ROM" | "SL2ROM" | "SL3ROM" | "SLRROM" | "SNROM"
+            | "SOROM" | "SUROM" | "SXROM" => 1,
+            "UNROM" | "UOROM" => 2,
+            "CNROM" => 3,
+            "TBROM" | "TEROM" | "TFROM" | "TGROM" | "TKROM" | "TLROM" | "TL1ROM" | "TL2ROM"
+            | "TNROM" | "TR1ROM" | "TSROM" | "TVROM" => 4,
+            "EKROM" | "ELROM" | "ETROM" | "EWROM" => 5,
+            "AMROM" | "ANROM" | "AN1ROM" | "AOROM" => 7,
+            "PNROM" | "PEEOROM" => 9,
+            "FJROM" | "FKROM" => 10,
+            "COLORDREAMS" => 11,
+            "BNROM" | "NINA-001" | "NINA-01" => 34,
+            "GNROM" | "MHROM" => 66,
+            "JLROM" | "JSROM" | "BTR" => 69,
+            "CAMERICA" | "BF9093" | "BF9096" | "BF9097" => 71,
+            _ => return None,
+        };
+
+        Some((mapper, 0))
+    }
+}
+
+fn hex_digit(c: u8) -> Option<usize> {
+    (c as char).to_digit(16).map(|digit| digit as usize)
+}
+
+/// Printable name of a chunk ID, for errors.
+fn chunk_name(id: &[u8]) -> String {
+    String::from_utf8_lossy(&id[..id.len().min(4)]).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// UNIF image with the given board name and 16 KB of PRG ROM.
+    fn unif_image(board: &str) -> Vec<u8> {
+        let mut raw = vec![0; UNIF_HEADER_SIZE];
+        raw[0..4].copy_from_slice(&UNIF_TAG);
+        for (id, data) in [(b"MAPR", board.as_bytes().to_vec()), (b"PRG0", vec![0; 0x4000])] {
+            raw.extend_from_slice(id);
+            raw.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            raw.extend_from_slice(&data);
+        }
+        raw
+    }
+
+    fn mapper(board: &str) -> Option<(u16, u8)> {
+        UnifImage::parse(&unif_image(board)).unwrap().mapper()
+    }
+
+    #[test]
+    fn maker_prefix_is_stripped() {
+        assert_eq!(mapper("NES-SLROM"), Some((1, 0)));
+        assert_eq!(mapper("AVE-NINA-01"), Some((34, 0)));
+        assert_eq!(mapper("CAMERICA-BF9093"), Some((71, 0)));
+    }
+
+    #[test]
+    fn unprefixed_board_keeps_its_name() {
+        assert_eq!(mapper("NINA-001"), Some((34, 0)));
+        assert_eq!(mapper("UNROM"), Some((2, 0)));
+        assert_eq!(mapper("FOO-UNROM"), None);
+    }
+}
@@ -0,0 +1,28 @@
+use std::path::PathBuf;
+
+use super::apu::expansion::ExpansionVolumes;
+use super::bus::RamPattern;
+
+/// Emulator options that are not part of the ROM image.
+#[derive(Clone, Default)]
+pub struct Config {
+    /// Draw every sprite on a scanline instead of only the first eight.
+    pub no_sprite_limit: bool,
+    /// Emulate bus conflicts on discrete logic boards (UxROM, CNROM, AxROM),
+    /// where a bank select write is ANDed with the ROM byte at that address.
+    /// Boards marked with NES 2.0 submapper 2 always have them.
+    pub bus_conflicts: bool,
+    /// Per-chip gain for cartridge expansion audio.
+    pub expansion_volumes: ExpansionVolumes,
+    /// File to load from a .zip archive, instead of the first ROM in it.
+    pub archive_entry: Option<String>,
+    /// IPS, UPS or BPS patch to apply. Without it, a patch next to the ROM
+    /// with the same name is used if there is one.
+    pub patch: Option<PathBuf>,
+    /// Constant used by the unstable LXA/XAA opcodes, instead of $EE.
+    pub magic_constant: Option<u8>,
+    /// Famicom Disk System BIOS, needed to run .fds disk images.
+    pub fds_bios: Option<PathBuf>,
+    /// What CPU RAM holds at power on.
+    pub ram_pattern: RamPattern,
+}
@@ -1,3 +1,4 @@
+mod dma;
 mod emulator;
 mod instructions;
 mod registers;
@@ -6,18 +7,30 @@ mod trace;
 use self::registers::ProcessorStatus;
 
 use super::Bus;
-use instructions::{AddressingMode, Instruction, InstructionVariant, INSTRUCTIONS};
+use dma::Dma;
+use instructions::{Access, AddressingMode, Instruction, InstructionVariant, INSTRUCTIONS};
 use registers::Registers;
 
 pub type Addr = u16;
 
+/// Constant ORed into A by the unstable LXA and XAA opcodes. It depends on
+/// the chip and its temperature; $EE matches most NES consoles.
+const DEFAULT_MAGIC_CONSTANT: u8 = 0xEE;
+
 ///
 /// 6502 Microprocessor
 ///
+/// Every bus access takes one CPU cycle, including the dummy reads and writes
+/// the real chip makes, and the PPU and APU are clocked before each one.
+///
 pub struct Cpu {
     regs: Registers,
     bus: Bus,
     cycles: usize,
+    /// Set by KIL/JAM, only a reset gets the CPU going again
+    halted: bool,
+    magic_constant: u8,
+    dma: Dma,
 }
 
 impl Cpu {
@@ -28,34 +41,78 @@ impl Cpu {
             regs,
             bus,
             cycles: 7,
+            halted: false,
+            magic_constant: DEFAULT_MAGIC_CONSTANT,
+            dma: Dma::default(),
         }
     }
 
+    pub fn set_magic_constant(&mut self, magic_constant: u8) {
+        self.magic_constant = magic_constant;
+    }
+
     pub fn execute(&mut self) {
-        let opcode = self.bus.read_u8(self.regs.pc);
+        if self.halted {
+            // The rest of the console keeps running while the CPU is jammed
+            self.tick(1);
+            return;
+        }
+
+        let opcode = self.read(self.regs.pc);
         let instruction = Cpu::decode(opcode);
 
         // Trace::print_state(self, instruction);
 
+        let irq_disabled_before = self
+            .regs
+            .status
+            .contains(ProcessorStatus::INTERRUPT_DISABLE);
+
         self.emulate(instruction);
 
+        // CLI, SEI and PLP change the I flag after the interrupt poll on their
+        // last cycle, so the old value decides whether an IRQ is taken right
+        // after them. RTI restores the flag before polling and takes effect
+        // immediately.
+        let irq_disabled = match instruction.variant {
+            InstructionVariant::CLI | InstructionVariant::SEI | InstructionVariant::PLP => {
+                irq_disabled_before
+            }
+            _ => self
+                .regs
+                .status
+                .contains(ProcessorStatus::INTERRUPT_DISABLE),
+        };
+
         if self.bus.poll_nmi_status() {
             self.interrupt_nmi();
+        } else if !irq_disabled && self.bus.poll_irq_status() {
+            self.interrupt_irq();
         }
     }
 
+    /// Reset button. The reset sequence is an interrupt with its stack
+    /// writes turned into reads, 7 cycles: two dummy reads of PC, SP goes
+    /// down by 3 without touching the stack, then the new PC is fetched
+    /// from $FFFC. A, X, Y and the other flags keep their values.
     pub fn reset(&mut self) {
-        self.regs.sp -= 0x3;
+        self.halted = false;
+        self.dma = Dma::default();
+        self.bus.reset();
+
+        self.read(self.regs.pc);
+        self.read(self.regs.pc);
+
+        for _ in 0..3 {
+            self.stack_dummy_read();
+            self.regs.sp = self.regs.sp.wrapping_sub(1);
+        }
+
         self.regs
             .status
             .set(ProcessorStatus::INTERRUPT_DISABLE, true);
 
-        // Silence APU ($4015 = 0)
-        // APU triangle phase is reset to 0 (i.e outputs a value of 15, the first setp of its waveform)
-        // APU DPCM output ANDed with 1 (upper 6 bits cleared)
-        // APU Frame Counter:
-        //      2A03E, G, various clones: APU Frame Counter reset.
-        //      2A03letterless: APU frame counter retains old value
+        self.regs.pc = self.read_u16(0xFFFC);
     }
 
     pub fn power_up(&mut self) {
@@ -80,6 +137,20 @@ impl Cpu {
     }
 
     pub fn interrupt_nmi(&mut self) {
+        self.interrupt(0xFFFA);
+    }
+
+    pub fn interrupt_irq(&mut self) {
+        self.interrupt(0xFFFE);
+    }
+
+    /// Hardware interrupt sequence, 7 cycles:
+    /// two dummy reads of PC, push PCH, PCL and P (with B clear),
+    /// then fetch the new PC from the vector.
+    fn interrupt(&mut self, vector: Addr) {
+        self.read(self.regs.pc);
+        self.read(self.regs.pc);
+
         self.stack_push_u16(self.regs.pc);
 
         let mut flags = self.regs.status;
@@ -91,8 +162,47 @@ impl Cpu {
             .status
             .set(ProcessorStatus::INTERRUPT_DISABLE, true);
 
-        self.tick(2);
-        self.regs.pc = self.bus.read_u16(0xFFFA);
+        self.regs.pc = self.read_u16(vector);
+    }
+
+    /// Store for SHA, SHX, SHY and TAS. The value is ANDed with the high byte
+    /// of the base address plus one, and when indexing crosses a page the
+    /// high byte of the target address is replaced by the stored value.
+    fn unstable_store(&mut self, mode: AddressingMode, value: u8) {
+        let addr = self.resolve_adressing(mode, Access::Write);
+        let index = match mode {
+            AddressingMode::AbsoluteX => self.regs.idx_x,
+            _ => self.regs.idx_y,
+        };
+
+        let base = addr.wrapping_sub(index as u16);
+        let base_high = (base >> 8) as u8;
+        let value = value & base_high.wrapping_add(1);
+        let addr = if Cpu::is_page_cross(base, addr) {
+            Addr::from_le_bytes([addr as u8, value])
+        } else {
+            addr
+        };
+
+        self.write(addr, value);
+    }
+
+    /// Read-modify-write instructions read the operand, write it back
+    /// unchanged while the ALU works on it, then write the result. In
+    /// accumulator mode they work on A instead. Returns the result.
+    fn read_modify_write(&mut self, mode: AddressingMode, modify: fn(&mut Cpu, u8) -> u8) -> u8 {
+        if let AddressingMode::Accumulator = mode {
+            self.regs.acc = modify(self, self.regs.acc);
+            return self.regs.acc;
+        }
+
+        let addr = self.resolve_adressing(mode, Access::ReadModifyWrite);
+        let op = self.read(addr);
+        self.write(addr, op);
+
+        let result = modify(self, op);
+        self.write(addr, result);
+        result
     }
 
     fn decode(opcode: u8) -> &'static Instruction {
@@ -111,7 +221,7 @@ impl Cpu {
 
     fn stack_push_u8(&mut self, op: u8) {
         let addr = Addr::from_le_bytes([self.regs.sp, 0x01]);
-        self.bus.write_u8(addr, op);
+        self.write(addr, op);
 
         self.regs.sp -= 1;
     }
@@ -127,14 +237,39 @@ impl Cpu {
         self.regs.sp += 1;
 
         let addr = Addr::from_le_bytes([self.regs.sp, 0x01]);
-        self.bus.read_u8(addr)
+        self.read(addr)
+    }
+
+    /// Read of the current stack slot while SP is being adjusted, before
+    /// pulls and in JSR.
+    fn stack_dummy_read(&mut self) {
+        let addr = Addr::from_le_bytes([self.regs.sp, 0x01]);
+        self.read(addr);
     }
 
     pub fn is_page_cross(addr1: Addr, addr2: Addr) -> bool {
         addr1 & 0xFF00 != addr2 & 0xFF00
     }
 
-    pub fn tick(&mut self, tick: u8) {
+    /// Read a byte, spending one CPU cycle. Pending DMA halts the CPU first.
+    fn read(&mut self, addr: Addr) -> u8 {
+        self.run_dma(addr);
+        self.tick(1);
+        self.bus.read_u8(addr)
+    }
+
+    /// Write a byte, spending one CPU cycle.
+    fn write(&mut self, addr: Addr, value: u8) {
+        self.tick(1);
+        self.bus.write_u8(addr, value);
+    }
+
+    fn read_u16(&mut self, addr: Addr) -> u16 {
+        u16::from_le_bytes([self.read(addr), self.read(addr.wrapping_add(1))])
+    }
+
+    /// Clock the PPU (3 dots per cycle) and the APU ahead of a bus access.
+    fn tick(&mut self, tick: u8) {
         self.cycles += tick as usize;
         self.bus.ppu_tick(tick * 3);
         // Tick APU once per CPU cycle
@@ -151,63 +286,54 @@ impl Cpu {
         self.bus.joypad1_mut().set_button_pressed(button, pressed);
     }
 
-    fn resolve_adressing(&mut self, mode: AddressingMode) -> (Addr, bool) {
+    /// Fetch the operand and compute the effective address, with the dummy
+    /// reads the 6502 makes while it adds the index registers.
+    fn resolve_adressing(&mut self, mode: AddressingMode, access: Access) -> Addr {
         match mode {
-            AddressingMode::Implied => (0xFFFF, false),
-            AddressingMode::Relative => (self.regs.pc, false),
-            AddressingMode::Immediate => (self.regs.pc, false),
+            AddressingMode::Implied => 0xFFFF,
+            AddressingMode::Relative => self.regs.pc,
+            AddressingMode::Immediate => self.regs.pc,
             AddressingMode::ZeroPage => {
-                let op = self.bus.read_u8(self.regs.pc);
-                (Addr::from_le_bytes([op, 0x00]), false)
+                let op = self.read(self.regs.pc);
+                Addr::from_le_bytes([op, 0x00])
             }
             AddressingMode::ZeroPageX => {
-                let mut op = self.bus.read_u8(self.regs.pc);
-                op = op.wrapping_add(self.regs.idx_x);
-                (Addr::from_le_bytes([op, 0x00]), false)
+                let op = self.read(self.regs.pc);
+                self.read(op as Addr);
+                Addr::from_le_bytes([op.wrapping_add(self.regs.idx_x), 0x00])
             }
             AddressingMode::ZeroPageY => {
-                let mut op = self.bus.read_u8(self.regs.pc);
-                op = op.wrapping_add(self.regs.idx_y);
-                (Addr::from_le_bytes([op, 0x00]), false)
+                let op = self.read(self.regs.pc);
+                self.read(op as Addr);
+                Addr::from_le_bytes([op.wrapping_add(self.regs.idx_y), 0x00])
             }
-            AddressingMode::Absolute => (self.bus.read_u16(self.regs.pc), false),
+            AddressingMode::Absolute => self.read_u16(self.regs.pc),
             AddressingMode::AbsoluteX => {
-                let addr = self.bus.read_u16(self.regs.pc);
-                let final_addr = addr.wrapping_add(self.regs.idx_x as u16);
-
-                (final_addr, Cpu::is_page_cross(addr, final_addr))
+                let addr = self.read_u16(self.regs.pc);
+                self.index(addr, self.regs.idx_x, access)
             }
             AddressingMode::AbsoluteY => {
-                let addr = self.bus.read_u16(self.regs.pc);
-                let final_addr = addr.wrapping_add(self.regs.idx_y as u16);
-
-                (final_addr, Cpu::is_page_cross(addr, final_addr))
+                let addr = self.read_u16(self.regs.pc);
+                self.index(addr, self.regs.idx_y, access)
             }
             AddressingMode::IndirectX => {
-                let op = self.bus.read_u8(self.regs.pc);
-                let lb = self
-                    .bus
-                    .read_u8((op as Addr + self.regs.idx_x as Addr) & 0x00FF);
-                let hb = self
-                    .bus
-                    .read_u8((op as Addr + self.regs.idx_x as Addr + 0x1) & 0x00FF);
-
-                (Addr::from_le_bytes([lb, hb]), false)
+                let op = self.read(self.regs.pc);
+                self.read(op as Addr);
+                let lb = self.read((op as Addr + self.regs.idx_x as Addr) & 0x00FF);
+                let hb = self.read((op as Addr + self.regs.idx_x as Addr + 0x1) & 0x00FF);
+
+                Addr::from_le_bytes([lb, hb])
             }
             AddressingMode::IndirectY => {
-                let op = self.bus.read_u8(self.regs.pc);
-                let lb = self.bus.read_u8(op as Addr);
-                let hb = self.bus.read_u8((op as Addr + 0x1) & 0x00FF);
+                let op = self.read(self.regs.pc);
+                let lb = self.read(op as Addr);
+                let hb = self.read((op as Addr + 0x1) & 0x00FF);
 
                 let addr = Addr::from_le_bytes([lb, hb]);
-                let final_addr = addr.wrapping_add(self.regs.idx_y as u16);
-
-                (final_addr, Cpu::is_page_cross(addr, final_addr))
+                self.index(addr, self.regs.idx_y, access)
             }
             AddressingMode::Indirect => {
-                let indirect_addr = self.bus.read_u16(self.regs.pc);
-                let lb: u8;
-                let hb: u8;
+                let indirect_addr = self.read_u16(self.regs.pc);
 
                 // Note:
                 // An original 6502 has does not correctly fetch the target address
@@ -216,14 +342,10 @@ impl Cpu {
                 // This is fixed in some later chips like the 65SC02, but for compatibility programs ensure
                 // the indirect vector is not at the end of the page.
                 // We're building a NES emulator so we will emulate the classic 6502 behaviour.
-                if indirect_addr & 0x00FF == 0x00FF {
-                    lb = self.bus.read_u8(indirect_addr);
-                    hb = self.bus.read_u8(indirect_addr & 0xFF00);
-                } else {
-                    (lb, hb) = self.bus.read_u16(indirect_addr).to_le_bytes().into();
-                }
-
-                (Addr::from_le_bytes([lb, hb]), false)
+                let lb = self.read(indirect_addr);
+                let hb = self.read((indirect_addr & 0xFF00) | (indirect_addr.wrapping_add(1) & 0x00FF));
+
+                Addr::from_le_bytes([lb, hb])
             }
             _ => {
                 panic!("Cannot resolve addresing for mode {:?}", mode);
@@ -231,21 +353,121 @@ impl Cpu {
         }
     }
 
+    /// Add an index register to a base address. The low byte is added first
+    /// and the CPU reads from the unfixed address (same page as the base)
+    /// while it carries into the high byte. Loads skip that read when there
+    /// is no carry.
+    fn index(&mut self, addr: Addr, index: u8, access: Access) -> Addr {
+        let final_addr = addr.wrapping_add(index as u16);
+
+        if access != Access::Read || Cpu::is_page_cross(addr, final_addr) {
+            self.read((addr & 0xFF00) | (final_addr & 0x00FF));
+        }
+
+        final_addr
+    }
+
     fn emulate(&mut self, instruction: &Instruction) {
         // Increment PC to get over the instruction opcode
         // so the emu functions can fetch the operand directly.
         self.regs.pc += 1;
+        let start_cycles = self.cycles;
+
+        // Single-byte instructions read the next byte and throw it away
+        if let AddressingMode::Implied | AddressingMode::Accumulator = instruction.mode {
+            self.read(self.regs.pc);
+        }
 
         (instruction.emu_fn)(self, instruction);
 
-        self.tick(instruction.cycles);
+        // The table holds the cycle count without page crossings and taken branches
+        debug_assert!(self.cycles - start_cycles + 1 >= instruction.cycles as usize);
 
         match instruction.variant {
             // These instructions modify the PC directly, no need to add the length.
-            InstructionVariant::JMP | InstructionVariant::JSR | InstructionVariant::RTS => {}
+            InstructionVariant::JMP
+            | InstructionVariant::JSR
+            | InstructionVariant::RTS
+            | InstructionVariant::BRK => {}
             _ => {
                 self.regs.pc += instruction.length as u16 - 1;
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::nes::apu::Apu;
+    use crate::nes::bus::RamPattern;
+    use crate::nes::cartridge::{Cartridge, Mirroring};
+    use crate::nes::config::Config;
+    use crate::nes::mapper::{self, Mapper};
+    use crate::nes::ppu::Ppu;
+
+    /// IRQ/BRK vector of the test consoles.
+    const IRQ_HANDLER: Addr = 0xC100;
+
+    /// Power up a console with a 32 KB board running `code` from $C000.
+    fn console(mapper: u8, code: &[u8]) -> (Cpu, Rc<RefCell<dyn Mapper>>) {
+        let mut raw = vec![0; 0x10 + 0x8000 + 0x2000];
+        raw[0..4].copy_from_slice(b"NES\x1A");
+        raw[4] = 2; // 32 KB PRG ROM
+        raw[5] = 1; // 8 KB CHR ROM
+        raw[6] = mapper << 4;
+
+        // Second 16 KB bank, at $C000 at power on
+        let fixed_bank = 0x10 + 0x4000;
+        raw[fixed_bank..fixed_bank + code.len()].copy_from_slice(code);
+        raw[fixed_bank + 0x3FFC..fixed_bank + 0x3FFE].copy_from_slice(&[0x00, 0xC0]);
+        raw[fixed_bank + 0x3FFE..fixed_bank + 0x4000].copy_from_slice(&IRQ_HANDLER.to_le_bytes());
+
+        let cartridge = Cartridge::from_bytes(&raw).unwrap();
+        let mapper = mapper::new(cartridge, &Config::default()).unwrap();
+        let ppu = Ppu::new(mapper.clone());
+        let apu = Apu::new(Arc::new(Mutex::new(Vec::new())));
+        let bus = Bus::new(mapper.clone(), ppu, apu, RamPattern::Zeros);
+
+        let mut cpu = Cpu::new(bus);
+        cpu.power_up();
+        (cpu, mapper)
+    }
+
+    #[test]
+    fn read_modify_write_loads_one_mmc1_bit() {
+        // INC $8000 reads $00, writes $00 back and then $01 on the next
+        // cycle. MMC1 ignores the second write, so five of them load a
+        // control value of 0 (one-screen mirroring) rather than 0b01010.
+        let code = [0xEE, 0x00, 0x80].repeat(5);
+        let (mut cpu, mapper) = console(1, &code);
+
+        for _ in 0..5 {
+            cpu.execute();
+        }
+
+        assert_eq!(mapper.borrow().mirroring(), Mirroring::SingleScreenLower);
+        assert_eq!(cpu.regs.pc, 0xC000 + code.len() as u16);
+    }
+
+    #[test]
+    fn brk_jumps_to_vector_and_pushes_return_address() {
+        // BRK, padding byte
+        let (mut cpu, _) = console(0, &[0x00, 0xEA]);
+
+        cpu.execute();
+
+        assert_eq!(cpu.regs.pc, IRQ_HANDLER);
+        assert_eq!(cpu.regs.sp, 0xFA);
+        // Return address is the opcode address + 2, skipping the padding byte
+        assert_eq!(cpu.bus.read_u8(0x01FD), 0xC0);
+        assert_eq!(cpu.bus.read_u8(0x01FC), 0x02);
+        let flags = ProcessorStatus::from_bits_truncate(cpu.bus.read_u8(0x01FB));
+        assert!(flags.contains(ProcessorStatus::BREAK_CMD));
+        assert!(cpu.regs.status.contains(ProcessorStatus::INTERRUPT_DISABLE));
+    }
+}
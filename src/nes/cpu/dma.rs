@@ -0,0 +1,105 @@
+use super::{Addr, Cpu};
+
+/// The 2A03's DMA units. OAM DMA copies a page of CPU memory to $2004 and the
+/// DMC DMA fetches sample bytes, both by halting the CPU on its next read
+/// cycle and taking over the bus.
+///
+/// DMA reads happen on get cycles and writes on put cycles, alternating with
+/// the APU clock, so a transfer can spend an extra cycle to line up: OAM DMA
+/// takes 513 or 514 cycles and a DMC fetch 3 or 4. While halted the CPU keeps
+/// repeating the read it was about to make, which is why DMC fetches can
+/// double-read $2007 and the controller ports. When both units run together
+/// the OAM transfer's cycles stand in for the DMC's halt and dummy cycles.
+#[derive(Default)]
+pub struct Dma {
+    /// Page being copied by OAM DMA
+    oam_page: Option<u8>,
+    /// Address of the sample byte the DMC is waiting for
+    dmc_addr: Option<Addr>,
+    need_halt: bool,
+    need_dummy_read: bool,
+}
+
+impl Cpu {
+    /// Run any DMA that is waiting, before the CPU reads `addr`. Writes can't
+    /// be halted, so a DMA requested during a write waits for the next read.
+    pub(super) fn run_dma(&mut self, addr: Addr) {
+        self.poll_dma_requests();
+        if !self.dma.need_halt {
+            return;
+        }
+
+        // Halt cycle: the CPU's read happens but its result is thrown away
+        self.dma_cycle();
+        self.bus.read_u8(addr);
+
+        // Consecutive reads of the controller ports only clock them once
+        let skip_dummy_reads = matches!(addr, 0x4016 | 0x4017);
+
+        let mut oam_offset: u8 = 0;
+        let mut oam_cycles: u16 = 0;
+        let mut value: u8 = 0;
+
+        while self.dma.dmc_addr.is_some() || self.dma.oam_page.is_some() {
+            let get_cycle = self.cycles.is_multiple_of(2);
+            let dmc_ready = !self.dma.need_halt && !self.dma.need_dummy_read;
+
+            match (get_cycle, self.dma.dmc_addr, self.dma.oam_page) {
+                (true, Some(dmc_addr), _) if dmc_ready => {
+                    self.dma_cycle();
+                    let sample = self.bus.read_u8(dmc_addr);
+                    self.bus.dmc_fill_buffer(sample);
+                    self.dma.dmc_addr = None;
+                }
+                (true, _, Some(page)) => {
+                    self.dma_cycle();
+                    value = self.bus.read_u8(Addr::from_le_bytes([oam_offset, page]));
+                    oam_offset = oam_offset.wrapping_add(1);
+                    oam_cycles += 1;
+                }
+                // Put cycle after an OAM DMA read
+                (false, _, Some(_)) if oam_cycles % 2 == 1 => {
+                    self.dma_cycle();
+                    self.bus.write_u8(0x2004, value);
+                    oam_cycles += 1;
+                    if oam_cycles == 512 {
+                        self.dma.oam_page = None;
+                    }
+                }
+                // DMC halt or dummy cycle, or alignment to a get cycle
+                _ => {
+                    self.dma_cycle();
+                    if !skip_dummy_reads {
+                        self.bus.read_u8(addr);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Spend one cycle on DMA. Any cycle counts as the halt and then the
+    /// dummy cycle of a DMC fetch.
+    fn dma_cycle(&mut self) {
+        if self.dma.need_halt {
+            self.dma.need_halt = false;
+        } else if self.dma.need_dummy_read {
+            self.dma.need_dummy_read = false;
+        }
+
+        self.tick(1);
+        self.poll_dma_requests();
+    }
+
+    fn poll_dma_requests(&mut self) {
+        if let Some(page) = self.bus.take_oam_dma_request() {
+            self.dma.oam_page = Some(page);
+            self.dma.need_halt = true;
+        }
+
+        if let Some(addr) = self.bus.take_dmc_dma_request() {
+            self.dma.dmc_addr = Some(addr);
+            self.dma.need_halt = true;
+            self.dma.need_dummy_read = true;
+        }
+    }
+}
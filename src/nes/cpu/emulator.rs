@@ -1,5 +1,5 @@
 use super::{
-    instructions::{AddressingMode, Instruction},
+    instructions::{Access, AddressingMode, Instruction},
     registers::ProcessorStatus,
     Cpu,
 };
@@ -8,40 +8,15 @@ pub struct Emu;
 
 impl Emu {
     pub fn adc(cpu: &mut Cpu, instr: &Instruction) {
-        let (addr, page_cross) = cpu.resolve_adressing(instr.mode);
-        let op = cpu.bus.read_u8(addr);
+        let addr = cpu.resolve_adressing(instr.mode, Access::Read);
+        let op = cpu.read(addr);
 
-        let carry = cpu.regs.status.contains(ProcessorStatus::CARRY_FLAG);
-        let carry: u8 = if carry { 0x1 } else { 0x0 };
-
-        let result: u16 = cpu.regs.acc as u16 + op as u16 + carry as u16;
-        let carry = result > 0xFF;
-
-        let ops_have_same_sign = (cpu.regs.acc ^ op) & 0x80 == 0x0;
-        let result_has_same_sign = (cpu.regs.acc ^ result as u8) & 0x80 == 0x0;
-
-        // Overflow flag is set if operands have the same sign and the result has a different sign.
-        let overflow = ops_have_same_sign & !result_has_same_sign;
-
-        // Overflow flag indicates signed overflow.
-        // Carry indicates unsigned overflow.
-        cpu.regs
-            .status
-            .set_carry_flag(carry)
-            .set_zero_flag(result as u8)
-            .set_overflow_flag(overflow)
-            .set_negative_flag(result as u8);
-
-        if page_cross {
-            cpu.tick(1);
-        }
-
-        cpu.regs.acc = result as u8;
+        Emu::add_with_carry(cpu, op);
     }
 
     pub fn and(cpu: &mut Cpu, instr: &Instruction) {
-        let (addr, page_cross) = cpu.resolve_adressing(instr.mode);
-        let op = cpu.bus.read_u8(addr);
+        let addr = cpu.resolve_adressing(instr.mode, Access::Read);
+        let op = cpu.read(addr);
 
         cpu.regs.acc &= op;
 
@@ -49,47 +24,26 @@ impl Emu {
             .status
             .set_zero_flag(cpu.regs.acc)
             .set_negative_flag(cpu.regs.acc);
-
-        if page_cross {
-            cpu.tick(1);
-        }
     }
 
     pub fn asl(cpu: &mut Cpu, instr: &Instruction) {
-        let mut op: u8;
-        if let AddressingMode::Accumulator = instr.mode {
-            op = cpu.regs.acc;
-        } else {
-            let (addr, _) = cpu.resolve_adressing(instr.mode);
-            op = cpu.bus.read_u8(addr);
-        }
-
-        // Put bit 7 into carry flag.
-        let is_bit_set = op & (0x1 << 7) != 0;
-        cpu.regs.status.set(ProcessorStatus::CARRY_FLAG, is_bit_set);
-
-        op <<= 0x1;
-
-        cpu.regs.status.set_negative_flag(op).set_zero_flag(op);
-
-        if let AddressingMode::Accumulator = instr.mode {
-            cpu.regs.acc = op;
-        } else {
-            let (addr, _) = cpu.resolve_adressing(instr.mode);
-            cpu.bus.write_u8(addr, op);
-        }
+        cpu.read_modify_write(instr.mode, Emu::shift_left);
     }
 
     fn branch(cpu: &mut Cpu, condition: bool) {
+        let op = cpu.read(cpu.regs.pc) as i8;
+
         if condition {
-            cpu.tick(1); // +1 on success
+            // +1 on success, reading the next opcode
+            let next_pc = cpu.regs.pc.wrapping_add(1);
+            cpu.read(next_pc);
 
-            let op = cpu.bus.read_i8(cpu.regs.pc);
             let new_pc = cpu.regs.pc.wrapping_add_signed(op as i16);
 
             // Add +1 on PC when verifying page cross account for operand skipping.
-            if Cpu::is_page_cross(new_pc + 1, cpu.regs.pc.wrapping_add(1)) {
-                cpu.tick(1);
+            // The CPU first jumps within the old page and reads from there.
+            if Cpu::is_page_cross(new_pc.wrapping_add(1), next_pc) {
+                cpu.read((next_pc & 0xFF00) | (new_pc.wrapping_add(1) & 0x00FF));
             }
 
             cpu.regs.pc = new_pc;
@@ -118,7 +72,11 @@ impl Emu {
         cpu.stack_push_u16(cpu.regs.pc + 1);
         cpu.stack_push_u8(flags.bits());
 
-        let new_pc = cpu.bus.read_u16(0xFFFE);
+        cpu.regs
+            .status
+            .set(ProcessorStatus::INTERRUPT_DISABLE, true);
+
+        let new_pc = cpu.read_u16(0xFFFE);
         cpu.set_pc(new_pc);
     }
 
@@ -155,8 +113,8 @@ impl Emu {
     }
 
     pub fn bit(cpu: &mut Cpu, instr: &Instruction) {
-        let (addr, _) = cpu.resolve_adressing(instr.mode);
-        let mut op = cpu.bus.read_u8(addr);
+        let addr = cpu.resolve_adressing(instr.mode, Access::Read);
+        let mut op = cpu.read(addr);
 
         let is_neg_set = op & (0x1 << 7) != 0x0;
         let is_of_set = op & (0x1 << 6) != 0x0;
@@ -196,8 +154,8 @@ impl Emu {
     }
 
     pub fn cmp(cpu: &mut Cpu, instr: &Instruction) {
-        let (addr, page_cross) = cpu.resolve_adressing(instr.mode);
-        let op = cpu.bus.read_u8(addr);
+        let addr = cpu.resolve_adressing(instr.mode, Access::Read);
+        let op = cpu.read(addr);
 
         let res = cpu.regs.acc.wrapping_sub(op);
 
@@ -206,15 +164,11 @@ impl Emu {
             .set_carry_flag(cpu.regs.acc >= op)
             .set_zero_flag(res)
             .set_negative_flag(res);
-
-        if page_cross {
-            cpu.tick(1);
-        }
     }
 
     pub fn cpx(cpu: &mut Cpu, instr: &Instruction) {
-        let (addr, _) = cpu.resolve_adressing(instr.mode);
-        let op = cpu.bus.read_u8(addr);
+        let addr = cpu.resolve_adressing(instr.mode, Access::Read);
+        let op = cpu.read(addr);
 
         let res = cpu.regs.idx_x.wrapping_sub(op);
 
@@ -226,8 +180,8 @@ impl Emu {
     }
 
     pub fn cpy(cpu: &mut Cpu, instr: &Instruction) {
-        let (addr, _) = cpu.resolve_adressing(instr.mode);
-        let op = cpu.bus.read_u8(addr);
+        let addr = cpu.resolve_adressing(instr.mode, Access::Read);
+        let op = cpu.read(addr);
 
         let res = cpu.regs.idx_y.wrapping_sub(op);
 
@@ -239,14 +193,7 @@ impl Emu {
     }
 
     pub fn dec(cpu: &mut Cpu, instr: &Instruction) {
-        let (addr, _) = cpu.resolve_adressing(instr.mode);
-        let mut op = cpu.bus.read_u8(addr);
-
-        op = op.wrapping_sub(1);
-
-        cpu.regs.status.set_zero_flag(op).set_negative_flag(op);
-
-        cpu.bus.write_u8(addr, op);
+        cpu.read_modify_write(instr.mode, Emu::decrement);
     }
 
     pub fn dex(cpu: &mut Cpu, _instr: &Instruction) {
@@ -268,8 +215,8 @@ impl Emu {
     }
 
     pub fn eor(cpu: &mut Cpu, instr: &Instruction) {
-        let (addr, page_cross) = cpu.resolve_adressing(instr.mode);
-        let op = cpu.bus.read_u8(addr);
+        let addr = cpu.resolve_adressing(instr.mode, Access::Read);
+        let op = cpu.read(addr);
 
         cpu.regs.acc ^= op;
 
@@ -277,21 +224,10 @@ impl Emu {
             .status
             .set_zero_flag(cpu.regs.acc)
             .set_negative_flag(cpu.regs.acc);
-
-        if page_cross {
-            cpu.tick(1);
-        }
     }
 
     pub fn inc(cpu: &mut Cpu, instr: &Instruction) {
-        let (addr, _) = cpu.resolve_adressing(instr.mode);
-        let mut op = cpu.bus.read_u8(addr);
-
-        op = op.wrapping_add(1);
-
-        cpu.regs.status.set_zero_flag(op).set_negative_flag(op);
-
-        cpu.bus.write_u8(addr, op);
+        cpu.read_modify_write(instr.mode, Emu::increment);
     }
 
     pub fn inx(cpu: &mut Cpu, _instr: &Instruction) {
@@ -313,92 +249,64 @@ impl Emu {
     }
 
     pub fn jmp(cpu: &mut Cpu, instr: &Instruction) {
-        let (addr, _) = cpu.resolve_adressing(instr.mode);
+        let addr = cpu.resolve_adressing(instr.mode, Access::Read);
 
         cpu.regs.pc = addr;
     }
 
     pub fn jsr(cpu: &mut Cpu, instr: &Instruction) {
-        let (addr, _) = cpu.resolve_adressing(instr.mode);
+        // The low byte of the target is fetched before the return address is
+        // pushed, and the high byte after.
+        let lb = cpu.read(cpu.regs.pc);
+        cpu.stack_dummy_read();
 
         cpu.regs.pc += instr.length as u16 - 2;
         cpu.stack_push_u16(cpu.regs.pc);
 
-        cpu.regs.pc = addr;
+        let hb = cpu.read(cpu.regs.pc);
+        cpu.regs.pc = u16::from_le_bytes([lb, hb]);
     }
 
     pub fn lda(cpu: &mut Cpu, instr: &Instruction) {
-        let (addr, page_cross) = cpu.resolve_adressing(instr.mode);
-        let op = cpu.bus.read_u8(addr);
+        let addr = cpu.resolve_adressing(instr.mode, Access::Read);
+        let op = cpu.read(addr);
 
         cpu.regs.acc = op;
         cpu.regs.status.set_zero_flag(op).set_negative_flag(op);
-
-        if page_cross {
-            cpu.tick(1);
-        }
     }
 
     pub fn ldx(cpu: &mut Cpu, instr: &Instruction) {
-        let (addr, page_cross) = cpu.resolve_adressing(instr.mode);
-        let op = cpu.bus.read_u8(addr);
+        let addr = cpu.resolve_adressing(instr.mode, Access::Read);
+        let op = cpu.read(addr);
 
         cpu.regs.idx_x = op;
         cpu.regs.status.set_zero_flag(op).set_negative_flag(op);
-
-        if page_cross {
-            cpu.tick(1);
-        }
     }
 
     pub fn ldy(cpu: &mut Cpu, instr: &Instruction) {
-        let (addr, page_cross) = cpu.resolve_adressing(instr.mode);
-        let op = cpu.bus.read_u8(addr);
+        let addr = cpu.resolve_adressing(instr.mode, Access::Read);
+        let op = cpu.read(addr);
 
         cpu.regs.idx_y = op;
         cpu.regs.status.set_zero_flag(op).set_negative_flag(op);
-
-        if page_cross {
-            cpu.tick(1);
-        }
     }
 
     pub fn lsr(cpu: &mut Cpu, instr: &Instruction) {
-        let mut op: u8;
-        if let AddressingMode::Accumulator = instr.mode {
-            op = cpu.regs.acc;
-        } else {
-            let (addr, _) = cpu.resolve_adressing(instr.mode);
-            op = cpu.bus.read_u8(addr);
-        }
-
-        // Put bit 0 into carry flag.
-        let is_bit_set = op & 0x1 != 0x0;
-        cpu.regs.status.set(ProcessorStatus::CARRY_FLAG, is_bit_set);
-
-        op >>= 0x1;
-
-        cpu.regs.status.set_negative_flag(op).set_zero_flag(op);
-
-        if let AddressingMode::Accumulator = instr.mode {
-            cpu.regs.acc = op;
-        } else {
-            let (addr, _) = cpu.resolve_adressing(instr.mode);
-            cpu.bus.write_u8(addr, op);
-        }
+        cpu.read_modify_write(instr.mode, Emu::shift_right);
     }
 
     pub fn nop(cpu: &mut Cpu, instr: &Instruction) {
-        let (_, page_cross) = cpu.resolve_adressing(instr.mode);
-        if page_cross {
-            cpu.tick(1);
+        // Unofficial NOPs with an operand still read it
+        if !matches!(instr.mode, AddressingMode::Implied) {
+            let addr = cpu.resolve_adressing(instr.mode, Access::Read);
+            cpu.read(addr);
         }
         // *cracks open a cold one*
     }
 
     pub fn ora(cpu: &mut Cpu, instr: &Instruction) {
-        let (addr, page_cross) = cpu.resolve_adressing(instr.mode);
-        let op = cpu.bus.read_u8(addr);
+        let addr = cpu.resolve_adressing(instr.mode, Access::Read);
+        let op = cpu.read(addr);
 
         cpu.regs.acc |= op;
 
@@ -406,10 +314,6 @@ impl Emu {
             .status
             .set_zero_flag(cpu.regs.acc)
             .set_negative_flag(cpu.regs.acc);
-
-        if page_cross {
-            cpu.tick(1);
-        }
     }
 
     pub fn pha(cpu: &mut Cpu, _instr: &Instruction) {
@@ -427,6 +331,7 @@ impl Emu {
     }
 
     pub fn pla(cpu: &mut Cpu, _instr: &Instruction) {
+        cpu.stack_dummy_read();
         cpu.regs.acc = cpu.stack_pop_u8();
 
         cpu.regs
@@ -436,6 +341,7 @@ impl Emu {
     }
 
     pub fn plp(cpu: &mut Cpu, _instr: &Instruction) {
+        cpu.stack_dummy_read();
         let new_status = cpu.stack_pop_u8();
         cpu.regs.status = ProcessorStatus::from_bits(new_status).unwrap();
 
@@ -445,69 +351,16 @@ impl Emu {
     }
 
     pub fn rol(cpu: &mut Cpu, instr: &Instruction) {
-        let mut op: u8;
-        if let AddressingMode::Accumulator = instr.mode {
-            op = cpu.regs.acc;
-        } else {
-            let (addr, _) = cpu.resolve_adressing(instr.mode);
-            op = cpu.bus.read_u8(addr);
-        }
-
-        // Save current carry flag
-        let is_current_carry_set = cpu.regs.status.contains(ProcessorStatus::CARRY_FLAG);
-
-        let is_bit_set = op & (0x1 << 7) != 0x0;
-        cpu.regs.status.set(ProcessorStatus::CARRY_FLAG, is_bit_set);
-
-        op <<= 0x1;
-
-        if is_current_carry_set {
-            op |= 0x1;
-        }
-
-        cpu.regs.status.set_negative_flag(op).set_zero_flag(op);
-
-        if let AddressingMode::Accumulator = instr.mode {
-            cpu.regs.acc = op;
-        } else {
-            let (addr, _) = cpu.resolve_adressing(instr.mode);
-            cpu.bus.write_u8(addr, op);
-        }
+        cpu.read_modify_write(instr.mode, Emu::rotate_left);
     }
 
     pub fn ror(cpu: &mut Cpu, instr: &Instruction) {
-        let mut op: u8;
-        if let AddressingMode::Accumulator = instr.mode {
-            op = cpu.regs.acc;
-        } else {
-            let (addr, _) = cpu.resolve_adressing(instr.mode);
-            op = cpu.bus.read_u8(addr);
-        }
-
-        // Save current carry flag
-        let is_current_carry_set = cpu.regs.status.contains(ProcessorStatus::CARRY_FLAG);
-
-        let is_bit_set = op & 0x1 != 0x0;
-        cpu.regs.status.set(ProcessorStatus::CARRY_FLAG, is_bit_set);
-
-        op >>= 0x1;
-
-        if is_current_carry_set {
-            op |= 0x1 << 7;
-        }
-
-        cpu.regs.status.set_negative_flag(op).set_zero_flag(op);
-
-        if let AddressingMode::Accumulator = instr.mode {
-            cpu.regs.acc = op;
-        } else {
-            let (addr, _) = cpu.resolve_adressing(instr.mode);
-            cpu.bus.write_u8(addr, op);
-        }
+        cpu.read_modify_write(instr.mode, Emu::rotate_right);
     }
 
     pub fn rti(cpu: &mut Cpu, _instr: &Instruction) {
         // Returrn from interrupt.
+        cpu.stack_dummy_read();
         let flags = cpu.stack_pop_u8();
         cpu.regs.status = ProcessorStatus::from_bits(flags).unwrap();
 
@@ -519,41 +372,21 @@ impl Emu {
     }
 
     pub fn rts(cpu: &mut Cpu, _instr: &Instruction) {
-        cpu.regs.pc = cpu.stack_pop_u16() + 1;
+        cpu.stack_dummy_read();
+        let return_addr = cpu.stack_pop_u16();
+
+        // The last cycle reads the byte at the return address before moving past it
+        cpu.read(return_addr);
+        cpu.regs.pc = return_addr + 1;
     }
 
     pub fn sbc(cpu: &mut Cpu, instr: &Instruction) {
-        let (addr, page_cross) = cpu.resolve_adressing(instr.mode);
+        let addr = cpu.resolve_adressing(instr.mode, Access::Read);
 
         // Same implementation as ADC but with negated operator.
-        let op = !cpu.bus.read_u8(addr);
-
-        let carry = cpu.regs.status.contains(ProcessorStatus::CARRY_FLAG);
-        let carry: u8 = if carry { 0x1 } else { 0x0 };
+        let op = !cpu.read(addr);
 
-        let result: u16 = cpu.regs.acc as u16 + op as u16 + carry as u16;
-        let carry = result > 0xFF;
-
-        let ops_have_same_sign = (cpu.regs.acc ^ op) & 0x80 == 0x0;
-        let result_has_same_sign = (cpu.regs.acc ^ result as u8) & 0x80 == 0x0;
-
-        // Overflow flag is set if operands have the same sign and the result has a different sign.
-        let overflow = ops_have_same_sign & !result_has_same_sign;
-
-        // Overflow flag indicates signed overflow.
-        // Carry indicates unsigned overflow.
-        cpu.regs
-            .status
-            .set_carry_flag(carry)
-            .set_zero_flag(result as u8)
-            .set_overflow_flag(overflow)
-            .set_negative_flag(result as u8);
-
-        cpu.regs.acc = result as u8;
-
-        if page_cross {
-            cpu.tick(1);
-        }
+        Emu::add_with_carry(cpu, op);
     }
 
     pub fn sec(cpu: &mut Cpu, _instr: &Instruction) {
@@ -571,18 +404,18 @@ impl Emu {
     }
 
     pub fn sta(cpu: &mut Cpu, instr: &Instruction) {
-        let (addr, _) = cpu.resolve_adressing(instr.mode);
-        cpu.bus.write_u8(addr, cpu.regs.acc);
+        let addr = cpu.resolve_adressing(instr.mode, Access::Write);
+        cpu.write(addr, cpu.regs.acc);
     }
 
     pub fn stx(cpu: &mut Cpu, instr: &Instruction) {
-        let (addr, _) = cpu.resolve_adressing(instr.mode);
-        cpu.bus.write_u8(addr, cpu.regs.idx_x);
+        let addr = cpu.resolve_adressing(instr.mode, Access::Write);
+        cpu.write(addr, cpu.regs.idx_x);
     }
 
     pub fn sty(cpu: &mut Cpu, instr: &Instruction) {
-        let (addr, _) = cpu.resolve_adressing(instr.mode);
-        cpu.bus.write_u8(addr, cpu.regs.idx_y);
+        let addr = cpu.resolve_adressing(instr.mode, Access::Write);
+        cpu.write(addr, cpu.regs.idx_y);
     }
 
     pub fn tax(cpu: &mut Cpu, _instr: &Instruction) {
@@ -635,34 +468,25 @@ impl Emu {
     }
 
     pub fn lax(cpu: &mut Cpu, instr: &Instruction) {
-        let (addr, page_cross) = cpu.resolve_adressing(instr.mode);
-        let op = cpu.bus.read_u8(addr);
+        let addr = cpu.resolve_adressing(instr.mode, Access::Read);
+        let op = cpu.read(addr);
 
         cpu.regs.idx_x = op;
         cpu.regs.acc = op;
         cpu.regs.status.set_zero_flag(op).set_negative_flag(op);
-
-        if page_cross {
-            cpu.tick(1);
-        }
     }
 
     pub fn sax(cpu: &mut Cpu, instr: &Instruction) {
-        let (addr, _) = cpu.resolve_adressing(instr.mode);
+        let addr = cpu.resolve_adressing(instr.mode, Access::Write);
         let op = cpu.regs.acc & cpu.regs.idx_x;
 
-        cpu.bus.write_u8(addr, op);
+        cpu.write(addr, op);
     }
 
     pub fn dcp(cpu: &mut Cpu, instr: &Instruction) {
-        let (addr, _) = cpu.resolve_adressing(instr.mode);
-        let mut op = cpu.bus.read_u8(addr);
-
-        op = op.wrapping_sub(1);
+        let op = cpu.read_modify_write(instr.mode, Emu::decrement);
         let res = cpu.regs.acc.wrapping_sub(op);
 
-        cpu.bus.write_u8(addr, op);
-
         cpu.regs
             .status
             .set_carry_flag(cpu.regs.acc >= op)
@@ -671,134 +495,129 @@ impl Emu {
     }
 
     pub fn isb(cpu: &mut Cpu, instr: &Instruction) {
-        let (addr, _) = cpu.resolve_adressing(instr.mode);
-        let mut op = cpu.bus.read_u8(addr);
+        let op = cpu.read_modify_write(instr.mode, Emu::increment);
 
-        op = op.wrapping_add(1);
-        cpu.bus.write_u8(addr, op);
+        // Same implementation as SBC.
+        Emu::add_with_carry(cpu, !op);
+    }
 
-        // Same implementation as ADC but with negated operator.
-        let op = !op;
+    pub fn slo(cpu: &mut Cpu, instr: &Instruction) {
+        let op = cpu.read_modify_write(instr.mode, Emu::shift_left);
 
-        let carry = cpu.regs.status.contains(ProcessorStatus::CARRY_FLAG);
-        let carry: u8 = if carry { 0x1 } else { 0x0 };
+        cpu.regs.acc |= op;
 
-        let result: u16 = cpu.regs.acc as u16 + op as u16 + carry as u16;
-        let carry = result > 0xFF;
+        cpu.regs
+            .status
+            .set_zero_flag(cpu.regs.acc)
+            .set_negative_flag(cpu.regs.acc);
+    }
 
-        let ops_have_same_sign = (cpu.regs.acc ^ op) & 0x80 == 0x0;
-        let result_has_same_sign = (cpu.regs.acc ^ result as u8) & 0x80 == 0x0;
+    pub fn rla(cpu: &mut Cpu, instr: &Instruction) {
+        let op = cpu.read_modify_write(instr.mode, Emu::rotate_left);
 
-        // Overflow flag is set if operands have the same sign and the result has a different sign.
-        let overflow = ops_have_same_sign & !result_has_same_sign;
+        cpu.regs.acc &= op;
 
-        // Overflow flag indicates signed overflow.
-        // Carry indicates unsigned overflow.
         cpu.regs
             .status
-            .set_carry_flag(carry)
-            .set_zero_flag(result as u8)
-            .set_overflow_flag(overflow)
-            .set_negative_flag(result as u8);
-
-        cpu.regs.acc = result as u8;
+            .set_zero_flag(cpu.regs.acc)
+            .set_negative_flag(cpu.regs.acc);
     }
 
-    pub fn slo(cpu: &mut Cpu, instr: &Instruction) {
-        let mut op: u8;
-        if let AddressingMode::Accumulator = instr.mode {
-            op = cpu.regs.acc;
-        } else {
-            let (addr, _) = cpu.resolve_adressing(instr.mode);
-            op = cpu.bus.read_u8(addr);
-        }
+    pub fn sre(cpu: &mut Cpu, instr: &Instruction) {
+        let op = cpu.read_modify_write(instr.mode, Emu::shift_right);
 
-        // Put bit 7 into carry flag.
-        let is_bit_set = op & (0x1 << 7) != 0;
-        cpu.regs.status.set(ProcessorStatus::CARRY_FLAG, is_bit_set);
+        cpu.regs.acc ^= op;
 
-        op <<= 0x1;
+        cpu.regs
+            .status
+            .set_zero_flag(cpu.regs.acc)
+            .set_negative_flag(cpu.regs.acc);
+    }
 
-        cpu.regs.status.set_negative_flag(op).set_zero_flag(op);
+    pub fn rra(cpu: &mut Cpu, instr: &Instruction) {
+        let op = cpu.read_modify_write(instr.mode, Emu::rotate_right);
 
-        if let AddressingMode::Accumulator = instr.mode {
-            cpu.regs.acc = op;
-        } else {
-            let (addr, _) = cpu.resolve_adressing(instr.mode);
-            cpu.bus.write_u8(addr, op);
-        }
+        // The carry out of the rotate goes into the addition
+        Emu::add_with_carry(cpu, op);
+    }
 
-        cpu.regs.acc |= op;
+    pub fn anc(cpu: &mut Cpu, instr: &Instruction) {
+        let addr = cpu.resolve_adressing(instr.mode, Access::Read);
+        cpu.regs.acc &= cpu.read(addr);
 
+        // Bit 7 is copied into carry, as if the result was shifted by ASL/ROL
+        let is_bit_set = cpu.regs.acc & 0x80 != 0x0;
         cpu.regs
             .status
+            .set_carry_flag(is_bit_set)
             .set_zero_flag(cpu.regs.acc)
             .set_negative_flag(cpu.regs.acc);
     }
 
-    pub fn rla(cpu: &mut Cpu, instr: &Instruction) {
-        let mut op: u8;
-        if let AddressingMode::Accumulator = instr.mode {
-            op = cpu.regs.acc;
-        } else {
-            let (addr, _) = cpu.resolve_adressing(instr.mode);
-            op = cpu.bus.read_u8(addr);
-        }
-
-        // Save current carry flag
-        let is_current_carry_set = cpu.regs.status.contains(ProcessorStatus::CARRY_FLAG);
+    pub fn alr(cpu: &mut Cpu, instr: &Instruction) {
+        let addr = cpu.resolve_adressing(instr.mode, Access::Read);
+        let op = cpu.regs.acc & cpu.read(addr);
 
-        let is_bit_set = op & (0x1 << 7) != 0x0;
-        cpu.regs.status.set(ProcessorStatus::CARRY_FLAG, is_bit_set);
+        // AND followed by LSR A
+        cpu.regs.acc = op >> 1;
+        cpu.regs
+            .status
+            .set_carry_flag(op & 0x1 != 0x0)
+            .set_zero_flag(cpu.regs.acc)
+            .set_negative_flag(cpu.regs.acc);
+    }
 
-        op <<= 0x1;
+    pub fn arr(cpu: &mut Cpu, instr: &Instruction) {
+        let addr = cpu.resolve_adressing(instr.mode, Access::Read);
+        let op = cpu.regs.acc & cpu.read(addr);
 
-        if is_current_carry_set {
-            op |= 0x1;
-        }
+        // AND followed by ROR A, but carry and overflow come from the adder:
+        // C is bit 6 of the result and V is bit 6 XOR bit 5.
+        let carry = cpu.regs.status.contains(ProcessorStatus::CARRY_FLAG);
+        let result = (op >> 1) | if carry { 0x80 } else { 0x0 };
 
-        cpu.regs.status.set_negative_flag(op).set_zero_flag(op);
+        cpu.regs.acc = result;
+        cpu.regs
+            .status
+            .set_carry_flag(result & 0x40 != 0x0)
+            .set_overflow_flag(((result >> 6) ^ (result >> 5)) & 0x1 != 0x0)
+            .set_zero_flag(result)
+            .set_negative_flag(result);
+    }
 
-        if let AddressingMode::Accumulator = instr.mode {
-            cpu.regs.acc = op;
-        } else {
-            let (addr, _) = cpu.resolve_adressing(instr.mode);
-            cpu.bus.write_u8(addr, op);
-        }
+    pub fn axs(cpu: &mut Cpu, instr: &Instruction) {
+        let addr = cpu.resolve_adressing(instr.mode, Access::Read);
+        let op = cpu.read(addr);
 
-        cpu.regs.acc &= op;
+        // X = (A & X) - op, compared like CMP so the carry ignores the old carry
+        let and = cpu.regs.acc & cpu.regs.idx_x;
+        cpu.regs.idx_x = and.wrapping_sub(op);
 
         cpu.regs
             .status
-            .set_zero_flag(cpu.regs.acc)
-            .set_negative_flag(cpu.regs.acc);
+            .set_carry_flag(and >= op)
+            .set_zero_flag(cpu.regs.idx_x)
+            .set_negative_flag(cpu.regs.idx_x);
     }
 
-    pub fn sre(cpu: &mut Cpu, instr: &Instruction) {
-        let mut op: u8;
-        if let AddressingMode::Accumulator = instr.mode {
-            op = cpu.regs.acc;
-        } else {
-            let (addr, _) = cpu.resolve_adressing(instr.mode);
-            op = cpu.bus.read_u8(addr);
-        }
-
-        // Put bit 0 into carry flag.
-        let is_bit_set = op & 0x1 != 0x0;
-        cpu.regs.status.set(ProcessorStatus::CARRY_FLAG, is_bit_set);
+    pub fn lxa(cpu: &mut Cpu, instr: &Instruction) {
+        let addr = cpu.resolve_adressing(instr.mode, Access::Read);
+        let op = cpu.read(addr);
 
-        op >>= 0x1;
+        // Unstable: A is ORed with a chip-dependent constant before the AND
+        let result = (cpu.regs.acc | cpu.magic_constant) & op;
+        cpu.regs.acc = result;
+        cpu.regs.idx_x = result;
 
-        cpu.regs.status.set_negative_flag(op).set_zero_flag(op);
+        cpu.regs.status.set_zero_flag(result).set_negative_flag(result);
+    }
 
-        if let AddressingMode::Accumulator = instr.mode {
-            cpu.regs.acc = op;
-        } else {
-            let (addr, _) = cpu.resolve_adressing(instr.mode);
-            cpu.bus.write_u8(addr, op);
-        }
+    pub fn xaa(cpu: &mut Cpu, instr: &Instruction) {
+        let addr = cpu.resolve_adressing(instr.mode, Access::Read);
+        let op = cpu.read(addr);
 
-        cpu.regs.acc ^= op;
+        // Unstable: A is ORed with a chip-dependent constant before the AND
+        cpu.regs.acc = (cpu.regs.acc | cpu.magic_constant) & cpu.regs.idx_x & op;
 
         cpu.regs
             .status
@@ -806,36 +625,41 @@ impl Emu {
             .set_negative_flag(cpu.regs.acc);
     }
 
-    pub fn rra(cpu: &mut Cpu, instr: &Instruction) {
-        let mut op: u8;
-        if let AddressingMode::Accumulator = instr.mode {
-            op = cpu.regs.acc;
-        } else {
-            let (addr, _) = cpu.resolve_adressing(instr.mode);
-            op = cpu.bus.read_u8(addr);
-        }
+    pub fn las(cpu: &mut Cpu, instr: &Instruction) {
+        let addr = cpu.resolve_adressing(instr.mode, Access::Read);
+        let op = cpu.read(addr) & cpu.regs.sp;
 
-        // Save current carry flag
-        let is_current_carry_set = cpu.regs.status.contains(ProcessorStatus::CARRY_FLAG);
+        cpu.regs.acc = op;
+        cpu.regs.idx_x = op;
+        cpu.regs.sp = op;
+        cpu.regs.status.set_zero_flag(op).set_negative_flag(op);
+    }
 
-        let is_bit_set = op & 0x1 != 0x0;
-        cpu.regs.status.set(ProcessorStatus::CARRY_FLAG, is_bit_set);
+    pub fn sha(cpu: &mut Cpu, instr: &Instruction) {
+        let value = cpu.regs.acc & cpu.regs.idx_x;
+        cpu.unstable_store(instr.mode, value);
+    }
 
-        op >>= 0x1;
+    pub fn shx(cpu: &mut Cpu, instr: &Instruction) {
+        cpu.unstable_store(instr.mode, cpu.regs.idx_x);
+    }
 
-        if is_current_carry_set {
-            op |= 0x1 << 7;
-        }
+    pub fn shy(cpu: &mut Cpu, instr: &Instruction) {
+        cpu.unstable_store(instr.mode, cpu.regs.idx_y);
+    }
 
-        cpu.regs.status.set_negative_flag(op).set_zero_flag(op);
+    pub fn tas(cpu: &mut Cpu, instr: &Instruction) {
+        cpu.regs.sp = cpu.regs.acc & cpu.regs.idx_x;
+        cpu.unstable_store(instr.mode, cpu.regs.sp);
+    }
 
-        if let AddressingMode::Accumulator = instr.mode {
-            cpu.regs.acc = op;
-        } else {
-            let (addr, _) = cpu.resolve_adressing(instr.mode);
-            cpu.bus.write_u8(addr, op);
-        }
+    pub fn kil(cpu: &mut Cpu, _instr: &Instruction) {
+        // The CPU locks up until it is reset
+        cpu.halted = true;
+    }
 
+    /// A = A + op + C, shared by ADC, SBC (with the operand inverted), ISB and RRA.
+    fn add_with_carry(cpu: &mut Cpu, op: u8) {
         let carry = cpu.regs.status.contains(ProcessorStatus::CARRY_FLAG);
         let carry: u8 = if carry { 0x1 } else { 0x0 };
 
@@ -859,4 +683,72 @@ impl Emu {
 
         cpu.regs.acc = result as u8;
     }
+
+    fn shift_left(cpu: &mut Cpu, mut op: u8) -> u8 {
+        // Put bit 7 into carry flag.
+        let is_bit_set = op & (0x1 << 7) != 0;
+        cpu.regs.status.set(ProcessorStatus::CARRY_FLAG, is_bit_set);
+
+        op <<= 0x1;
+
+        cpu.regs.status.set_negative_flag(op).set_zero_flag(op);
+        op
+    }
+
+    fn shift_right(cpu: &mut Cpu, mut op: u8) -> u8 {
+        // Put bit 0 into carry flag.
+        let is_bit_set = op & 0x1 != 0x0;
+        cpu.regs.status.set(ProcessorStatus::CARRY_FLAG, is_bit_set);
+
+        op >>= 0x1;
+
+        cpu.regs.status.set_negative_flag(op).set_zero_flag(op);
+        op
+    }
+
+    fn rotate_left(cpu: &mut Cpu, mut op: u8) -> u8 {
+        // Save current carry flag
+        let is_current_carry_set = cpu.regs.status.contains(ProcessorStatus::CARRY_FLAG);
+
+        let is_bit_set = op & (0x1 << 7) != 0x0;
+        cpu.regs.status.set(ProcessorStatus::CARRY_FLAG, is_bit_set);
+
+        op <<= 0x1;
+
+        if is_current_carry_set {
+            op |= 0x1;
+        }
+
+        cpu.regs.status.set_negative_flag(op).set_zero_flag(op);
+        op
+    }
+
+    fn rotate_right(cpu: &mut Cpu, mut op: u8) -> u8 {
+        // Save current carry flag
+        let is_current_carry_set = cpu.regs.status.contains(ProcessorStatus::CARRY_FLAG);
+
+        let is_bit_set = op & 0x1 != 0x0;
+        cpu.regs.status.set(ProcessorStatus::CARRY_FLAG, is_bit_set);
+
+        op >>= 0x1;
+
+        if is_current_carry_set {
+            op |= 0x1 << 7;
+        }
+
+        cpu.regs.status.set_negative_flag(op).set_zero_flag(op);
+        op
+    }
+
+    fn increment(cpu: &mut Cpu, op: u8) -> u8 {
+        let op = op.wrapping_add(1);
+        cpu.regs.status.set_zero_flag(op).set_negative_flag(op);
+        op
+    }
+
+    fn decrement(cpu: &mut Cpu, op: u8) -> u8 {
+        let op = op.wrapping_sub(1);
+        cpu.regs.status.set_zero_flag(op).set_negative_flag(op);
+        op
+    }
 }
@@ -22,6 +22,16 @@ pub enum AddressingMode {
     IndirectY,
 }
 
+/// How an instruction uses the memory operand. Stores and read-modify-write
+/// instructions always spend the extra cycle of the indexed modes, loads only
+/// when indexing crosses a page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+    ReadModifyWrite,
+}
+
 #[rustfmt::skip]
 #[allow(clippy::upper_case_acronyms)]
 #[derive(Debug)]
@@ -30,7 +40,8 @@ pub enum InstructionVariant {
     SEC, CLI, SEI, CLV, CLD, SED, INC, JMP, JSR, LDA, LDX, LDY, LSR, NOP, ORA, TAX, TXA, DEX, INX,
     TAY, TYA, DEY, INY, ROL, ROR, RTI, RTS, SBC, STA, TXS, TSX, PHA, PLA, PHP, PLP, STX, STY,
     // Unofficial opcodes
-    LAX, SAX, DCP, ISB, SLO, RLA, SRE, RRA,
+    LAX, SAX, DCP, ISB, SLO, RLA, SRE, RRA, ANC, ALR, ARR, AXS, LXA, XAA, LAS, SHA, SHX, SHY, TAS,
+    KIL,
 }
 
 pub struct Instruction {
@@ -267,6 +278,10 @@ pub static INSTRUCTIONS: phf::Map<u8, Instruction> = phf_map! {
     0xD4u8 => Instruction{variant: InstructionVariant::NOP, mode: AddressingMode::ZeroPageX, length: 2, cycles: 4, emu_fn: Emu::nop},
     0xF4u8 => Instruction{variant: InstructionVariant::NOP, mode: AddressingMode::ZeroPageX, length: 2, cycles: 4, emu_fn: Emu::nop},
     0x80u8 => Instruction{variant: InstructionVariant::NOP, mode: AddressingMode::Immediate, length: 2, cycles: 2, emu_fn: Emu::nop},
+    0x82u8 => Instruction{variant: InstructionVariant::NOP, mode: AddressingMode::Immediate, length: 2, cycles: 2, emu_fn: Emu::nop},
+    0x89u8 => Instruction{variant: InstructionVariant::NOP, mode: AddressingMode::Immediate, length: 2, cycles: 2, emu_fn: Emu::nop},
+    0xC2u8 => Instruction{variant: InstructionVariant::NOP, mode: AddressingMode::Immediate, length: 2, cycles: 2, emu_fn: Emu::nop},
+    0xE2u8 => Instruction{variant: InstructionVariant::NOP, mode: AddressingMode::Immediate, length: 2, cycles: 2, emu_fn: Emu::nop},
     0x1Cu8 => Instruction{variant: InstructionVariant::NOP, mode: AddressingMode::AbsoluteX, length: 3, cycles: 4, emu_fn: Emu::nop},
     0x3Cu8 => Instruction{variant: InstructionVariant::NOP, mode: AddressingMode::AbsoluteX, length: 3, cycles: 4, emu_fn: Emu::nop},
     0x5Cu8 => Instruction{variant: InstructionVariant::NOP, mode: AddressingMode::AbsoluteX, length: 3, cycles: 4, emu_fn: Emu::nop},
@@ -350,4 +365,45 @@ pub static INSTRUCTIONS: phf::Map<u8, Instruction> = phf_map! {
     0x7Bu8 => Instruction{variant: InstructionVariant::RRA, mode: AddressingMode::AbsoluteY, length: 3, cycles: 7, emu_fn: Emu::rra},
     0x63u8 => Instruction{variant: InstructionVariant::RRA, mode: AddressingMode::IndirectX, length: 2, cycles: 8, emu_fn: Emu::rra},
     0x73u8 => Instruction{variant: InstructionVariant::RRA, mode: AddressingMode::IndirectY, length: 2, cycles: 8, emu_fn: Emu::rra},
+
+    // Unofficial ANC instruction
+    0x0Bu8 => Instruction{variant: InstructionVariant::ANC, mode: AddressingMode::Immediate, length: 2, cycles: 2, emu_fn: Emu::anc},
+    0x2Bu8 => Instruction{variant: InstructionVariant::ANC, mode: AddressingMode::Immediate, length: 2, cycles: 2, emu_fn: Emu::anc},
+
+    // Unofficial ALR instruction
+    0x4Bu8 => Instruction{variant: InstructionVariant::ALR, mode: AddressingMode::Immediate, length: 2, cycles: 2, emu_fn: Emu::alr},
+
+    // Unofficial ARR instruction
+    0x6Bu8 => Instruction{variant: InstructionVariant::ARR, mode: AddressingMode::Immediate, length: 2, cycles: 2, emu_fn: Emu::arr},
+
+    // Unofficial AXS instruction
+    0xCBu8 => Instruction{variant: InstructionVariant::AXS, mode: AddressingMode::Immediate, length: 2, cycles: 2, emu_fn: Emu::axs},
+
+    // Unofficial LXA and XAA instructions
+    0xABu8 => Instruction{variant: InstructionVariant::LXA, mode: AddressingMode::Immediate, length: 2, cycles: 2, emu_fn: Emu::lxa},
+    0x8Bu8 => Instruction{variant: InstructionVariant::XAA, mode: AddressingMode::Immediate, length: 2, cycles: 2, emu_fn: Emu::xaa},
+
+    // Unofficial LAS instruction
+    0xBBu8 => Instruction{variant: InstructionVariant::LAS, mode: AddressingMode::AbsoluteY, length: 3, cycles: 4, emu_fn: Emu::las},
+
+    // Unofficial SHA, SHX, SHY and TAS instructions
+    0x9Fu8 => Instruction{variant: InstructionVariant::SHA, mode: AddressingMode::AbsoluteY, length: 3, cycles: 5, emu_fn: Emu::sha},
+    0x93u8 => Instruction{variant: InstructionVariant::SHA, mode: AddressingMode::IndirectY, length: 2, cycles: 6, emu_fn: Emu::sha},
+    0x9Eu8 => Instruction{variant: InstructionVariant::SHX, mode: AddressingMode::AbsoluteY, length: 3, cycles: 5, emu_fn: Emu::shx},
+    0x9Cu8 => Instruction{variant: InstructionVariant::SHY, mode: AddressingMode::AbsoluteX, length: 3, cycles: 5, emu_fn: Emu::shy},
+    0x9Bu8 => Instruction{variant: InstructionVariant::TAS, mode: AddressingMode::AbsoluteY, length: 3, cycles: 5, emu_fn: Emu::tas},
+
+    // Unofficial KIL instruction
+    0x02u8 => Instruction{variant: InstructionVariant::KIL, mode: AddressingMode::Implied, length: 1, cycles: 2, emu_fn: Emu::kil},
+    0x12u8 => Instruction{variant: InstructionVariant::KIL, mode: AddressingMode::Implied, length: 1, cycles: 2, emu_fn: Emu::kil},
+    0x22u8 => Instruction{variant: InstructionVariant::KIL, mode: AddressingMode::Implied, length: 1, cycles: 2, emu_fn: Emu::kil},
+    0x32u8 => Instruction{variant: InstructionVariant::KIL, mode: AddressingMode::Implied, length: 1, cycles: 2, emu_fn: Emu::kil},
+    0x42u8 => Instruction{variant: InstructionVariant::KIL, mode: AddressingMode::Implied, length: 1, cycles: 2, emu_fn: Emu::kil},
+    0x52u8 => Instruction{variant: InstructionVariant::KIL, mode: AddressingMode::Implied, length: 1, cycles: 2, emu_fn: Emu::kil},
+    0x62u8 => Instruction{variant: InstructionVariant::KIL, mode: AddressingMode::Implied, length: 1, cycles: 2, emu_fn: Emu::kil},
+    0x72u8 => Instruction{variant: InstructionVariant::KIL, mode: AddressingMode::Implied, length: 1, cycles: 2, emu_fn: Emu::kil},
+    0x92u8 => Instruction{variant: InstructionVariant::KIL, mode: AddressingMode::Implied, length: 1, cycles: 2, emu_fn: Emu::kil},
+    0xB2u8 => Instruction{variant: InstructionVariant::KIL, mode: AddressingMode::Implied, length: 1, cycles: 2, emu_fn: Emu::kil},
+    0xD2u8 => Instruction{variant: InstructionVariant::KIL, mode: AddressingMode::Implied, length: 1, cycles: 2, emu_fn: Emu::kil},
+    0xF2u8 => Instruction{variant: InstructionVariant::KIL, mode: AddressingMode::Implied, length: 1, cycles: 2, emu_fn: Emu::kil},
 };
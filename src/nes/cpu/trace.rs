@@ -12,6 +12,33 @@ impl Trace {
         let print_operand = !matches!(instr.variant, InstructionVariant::JSR)
             && !matches!(instr.variant, InstructionVariant::JMP);
 
+        // Operand address, read straight from the bus so tracing doesn't
+        // clock the rest of the console
+        let effective_address = |cpu: &mut Cpu| -> Addr {
+            let pc = cpu.regs.pc;
+            match instr.mode {
+                AddressingMode::ZeroPage => cpu.bus.read_u8(pc) as Addr,
+                AddressingMode::ZeroPageX => cpu.bus.read_u8(pc).wrapping_add(cpu.regs.idx_x) as Addr,
+                AddressingMode::ZeroPageY => cpu.bus.read_u8(pc).wrapping_add(cpu.regs.idx_y) as Addr,
+                AddressingMode::Absolute => cpu.bus.read_u16(pc),
+                AddressingMode::AbsoluteX => cpu.bus.read_u16(pc).wrapping_add(cpu.regs.idx_x as Addr),
+                AddressingMode::AbsoluteY => cpu.bus.read_u16(pc).wrapping_add(cpu.regs.idx_y as Addr),
+                AddressingMode::IndirectX => {
+                    let op = cpu.bus.read_u8(pc).wrapping_add(cpu.regs.idx_x);
+                    let lb = cpu.bus.read_u8(op as Addr);
+                    let hb = cpu.bus.read_u8(op.wrapping_add(1) as Addr);
+                    Addr::from_le_bytes([lb, hb])
+                }
+                AddressingMode::IndirectY => {
+                    let op = cpu.bus.read_u8(pc);
+                    let lb = cpu.bus.read_u8(op as Addr);
+                    let hb = cpu.bus.read_u8(op.wrapping_add(1) as Addr);
+                    Addr::from_le_bytes([lb, hb]).wrapping_add(cpu.regs.idx_y as Addr)
+                }
+                _ => pc,
+            }
+        };
+
         cpu.regs.pc += 1;
 
         match instr.mode {
@@ -22,7 +49,7 @@ impl Trace {
                 print!("{: <28}", "A");
             }
             AddressingMode::Relative => {
-                let op = cpu.bus.read_i8(cpu.regs.pc);
+                let op = cpu.bus.read_u8(cpu.regs.pc) as i8;
                 let mut addr = cpu.regs.pc + 1;
                 addr = addr.wrapping_add_signed(op as i16);
                 print!("${: <27}", format!("{:02X}", addr));
@@ -34,7 +61,7 @@ impl Trace {
             AddressingMode::ZeroPage => {
                 let immediate: u8 = cpu.bus.read_u8(cpu.regs.pc);
                 if print_operand {
-                    let (addr, _) = cpu.resolve_adressing(instr.mode);
+                    let addr = effective_address(cpu);
                     let op = cpu.bus.read_u8(addr);
                     print!("${: <27}", format!("{:02X} = {:02X}", immediate, op));
                 } else {
@@ -44,7 +71,7 @@ impl Trace {
             AddressingMode::ZeroPageX => {
                 let immediate = cpu.bus.read_u8(cpu.regs.pc);
                 if print_operand {
-                    let (addr, _) = cpu.resolve_adressing(instr.mode);
+                    let addr = effective_address(cpu);
                     let op = cpu.bus.read_u8(addr);
                     print!(
                         "${: <27}",
@@ -57,7 +84,7 @@ impl Trace {
             AddressingMode::ZeroPageY => {
                 let immediate = cpu.bus.read_u8(cpu.regs.pc);
                 if print_operand {
-                    let (addr, _) = cpu.resolve_adressing(instr.mode);
+                    let addr = effective_address(cpu);
                     let op = cpu.bus.read_u8(addr);
                     print!(
                         "${: <27}",
@@ -78,7 +105,7 @@ impl Trace {
             }
             AddressingMode::AbsoluteX => {
                 let addr = cpu.bus.read_u16(cpu.regs.pc);
-                let (final_addr, _) = cpu.resolve_adressing(instr.mode);
+                let final_addr = effective_address(cpu);
                 let op = cpu.bus.read_u8(final_addr);
                 print!(
                     "${: <27}",
@@ -87,7 +114,7 @@ impl Trace {
             }
             AddressingMode::AbsoluteY => {
                 let addr = cpu.bus.read_u16(cpu.regs.pc);
-                let (final_addr, _) = cpu.resolve_adressing(instr.mode);
+                let final_addr = effective_address(cpu);
                 let op = cpu.bus.read_u8(final_addr);
                 print!(
                     "${: <27}",
@@ -96,7 +123,7 @@ impl Trace {
             }
             AddressingMode::IndirectX => {
                 let immediate = cpu.bus.read_u8(cpu.regs.pc);
-                let (addr, _) = cpu.resolve_adressing(instr.mode);
+                let addr = effective_address(cpu);
                 let op = cpu.bus.read_u8(addr);
                 print!(
                     "(${: <26}",
@@ -111,7 +138,7 @@ impl Trace {
             }
             AddressingMode::IndirectY => {
                 let immediate = cpu.bus.read_u8(cpu.regs.pc);
-                let (addr, _) = cpu.resolve_adressing(instr.mode);
+                let addr = effective_address(cpu);
                 let op = cpu.bus.read_u8(addr);
                 print!(
                     "(${: <26}",
@@ -172,6 +199,18 @@ impl Trace {
             InstructionVariant::RLA => true,
             InstructionVariant::SRE => true,
             InstructionVariant::RRA => true,
+            InstructionVariant::ANC => true,
+            InstructionVariant::ALR => true,
+            InstructionVariant::ARR => true,
+            InstructionVariant::AXS => true,
+            InstructionVariant::LXA => true,
+            InstructionVariant::XAA => true,
+            InstructionVariant::LAS => true,
+            InstructionVariant::SHA => true,
+            InstructionVariant::SHX => true,
+            InstructionVariant::SHY => true,
+            InstructionVariant::TAS => true,
+            InstructionVariant::KIL => true,
             _ => false,
         };
 
@@ -37,6 +37,11 @@ impl Joypad {
         }
     }
 
+    /// Console reset: the 2A03 clears its OUT pins, dropping the strobe.
+    pub fn reset(&mut self) {
+        self.strobe = false;
+    }
+
     pub fn write(&mut self, value: u8) {
         self.strobe = value & 1 == 1;
         if self.strobe {
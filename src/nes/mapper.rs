@@ -0,0 +1,156 @@
+mod axrom;
+mod bnrom;
+mod camerica;
+mod cnrom;
+mod color_dreams;
+mod fds;
+mod fme7;
+mod gxrom;
+mod mmc1;
+mod mmc2;
+mod mmc3;
+mod mmc5;
+mod namco163;
+mod nrom;
+mod uxrom;
+mod vrc6;
+mod vrc7;
+mod vrc_irq;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use axrom::Axrom;
+use bnrom::Bnrom;
+use camerica::Camerica;
+use cnrom::Cnrom;
+use color_dreams::ColorDreams;
+use fds::Fds;
+use fme7::Fme7;
+use gxrom::Gxrom;
+use mmc1::Mmc1;
+use mmc2::Mmc2;
+use mmc3::Mmc3;
+use mmc5::Mmc5;
+use namco163::Namco163;
+use nrom::Nrom;
+use uxrom::Uxrom;
+use vrc6::Vrc6;
+use vrc7::Vrc7;
+
+use super::apu::expansion::ExpansionAudio;
+use super::cartridge::{Cartridge, CartridgeError, Mirroring, FDS_MAPPER};
+use super::config::Config;
+
+/// Cartridge board logic.
+///
+/// The mapper owns the cartridge memory and decides what the CPU sees in
+/// cartridge space ($4020-$FFFF) and what the PPU sees in the pattern
+/// tables ($0000-$1FFF). Bank switching registers are written through
+/// `cpu_write`, and PRG RAM at $6000-$7FFF is served by `cpu_read`/`cpu_write`.
+pub trait Mapper {
+    /// CPU read in cartridge space ($4020-$FFFF).
+    fn cpu_read(&mut self, addr: u16) -> u8;
+
+    /// CPU write in cartridge space ($4020-$FFFF).
+    fn cpu_write(&mut self, addr: u16, value: u8);
+
+    /// PPU read from the pattern tables ($0000-$1FFF).
+    fn ppu_read(&mut self, addr: u16) -> u8;
+
+    /// PPU write to the pattern tables ($0000-$1FFF).
+    fn ppu_write(&mut self, addr: u16, value: u8);
+
+    /// Observe an address the PPU drives onto its bus (background fetches,
+    /// the sprite pattern table at dot 257 and PPUADDR/PPUDATA accesses).
+    /// `ppu_dot` is a free-running dot counter so boards that watch A12 can
+    /// filter out short pulses.
+    fn ppu_address(&mut self, _addr: u16, _ppu_dot: u64) {}
+
+    /// Current nametable mirroring, which some boards control at runtime.
+    fn mirroring(&self) -> Mirroring;
+
+    /// 1 KB page backing logical nametable 0-3: pages 0-1 are console VRAM,
+    /// pages 2-3 are cartridge VRAM. Follows `mirroring` unless the board
+    /// maps each nametable separately.
+    fn nametable_page(&self, nametable: u16) -> usize {
+        self.mirroring().page(nametable)
+    }
+
+    /// Nametable read ($2000-$2FFF) for boards that supply nametable data
+    /// themselves. `None` leaves the read to the page from `nametable_page`.
+    fn nametable_read(&mut self, _addr: u16) -> Option<u8> {
+        None
+    }
+
+    /// Nametable write ($2000-$2FFF). Returns true if the board took it.
+    fn nametable_write(&mut self, _addr: u16, _value: u8) -> bool {
+        false
+    }
+
+    /// The PPU is starting (true) or has finished (false) fetching sprite
+    /// patterns for the next scanline.
+    fn ppu_sprite_fetches(&mut self, _active: bool) {}
+
+    /// Observe a CPU write to a PPU register ($2000-$2007).
+    fn ppu_register_write(&mut self, _addr: u16, _value: u8) {}
+
+    /// Advance board logic that runs on the CPU clock, such as cycle-counting
+    /// IRQ timers. Called once per CPU cycle.
+    fn cpu_tick(&mut self) {}
+
+    /// Sound chip on the board, clocked and mixed by the APU.
+    fn expansion_audio(&mut self) -> Option<&mut dyn ExpansionAudio> {
+        None
+    }
+
+    /// Frontend request to eject the disk and insert the next side, on
+    /// boards with a disk drive.
+    fn switch_disk_side(&mut self) {}
+
+    /// Console reset button. The cartridge connector has no reset line, so
+    /// boards keep their registers unless they override this.
+    fn reset(&mut self) {}
+
+    /// The cartridge the board was built from, for saving its PRG RAM.
+    fn cartridge(&self) -> &Cartridge;
+
+    /// Mutable access to the cartridge, for nametable RAM on four-screen boards.
+    fn cartridge_mut(&mut self) -> &mut Cartridge;
+
+    /// Whether the board is asserting the CPU IRQ line.
+    fn irq_pending(&self) -> bool {
+        false
+    }
+}
+
+/// Build the mapper selected by the cartridge header.
+pub fn new(cartridge: Cartridge, config: &Config) -> Result<Rc<RefCell<dyn Mapper>>, CartridgeError> {
+    // For the discrete logic boards, NES 2.0 submapper 2 marks bus conflicts
+    let bus_conflicts = config.bus_conflicts || cartridge.submapper == 2;
+
+    let mapper: Rc<RefCell<dyn Mapper>> = match cartridge.mapper {
+        0 => Rc::new(RefCell::new(Nrom::new(cartridge))),
+        1 => Rc::new(RefCell::new(Mmc1::new(cartridge))),
+        2 => Rc::new(RefCell::new(Uxrom::new(cartridge, bus_conflicts))),
+        3 => Rc::new(RefCell::new(Cnrom::new(cartridge, bus_conflicts))),
+        4 => Rc::new(RefCell::new(Mmc3::new(cartridge))),
+        5 => Rc::new(RefCell::new(Mmc5::new(cartridge))),
+        7 => Rc::new(RefCell::new(Axrom::new(cartridge, bus_conflicts))),
+        9 => Rc::new(RefCell::new(Mmc2::new(cartridge, false))),
+        10 => Rc::new(RefCell::new(Mmc2::new(cartridge, true))),
+        11 => Rc::new(RefCell::new(ColorDreams::new(cartridge, bus_conflicts))),
+        19 => Rc::new(RefCell::new(Namco163::new(cartridge))),
+        FDS_MAPPER if cartridge.disk.is_some() => Rc::new(RefCell::new(Fds::new(cartridge))),
+        24 => Rc::new(RefCell::new(Vrc6::new(cartridge, false))),
+        26 => Rc::new(RefCell::new(Vrc6::new(cartridge, true))),
+        34 => Rc::new(RefCell::new(Bnrom::new(cartridge, bus_conflicts))),
+        66 => Rc::new(RefCell::new(Gxrom::new(cartridge, bus_conflicts))),
+        69 => Rc::new(RefCell::new(Fme7::new(cartridge))),
+        71 => Rc::new(RefCell::new(Camerica::new(cartridge))),
+        85 => Rc::new(RefCell::new(Vrc7::new(cartridge))),
+        id => return Err(CartridgeError::UnsupportedMapper(id)),
+    };
+
+    Ok(mapper)
+}
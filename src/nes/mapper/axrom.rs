@@ -0,0 +1,86 @@
+use crate::nes::cartridge::{Cartridge, Mirroring};
+
+use super::Mapper;
+
+const PRG_BANK_SIZE: usize = 0x8000;
+
+/// AxROM (mapper 7)
+///
+/// Any write to $8000-$FFFF selects the 32 KB PRG bank (bits 0-2) and the
+/// single-screen nametable (bit 4). CHR is 8 KB RAM.
+pub struct Axrom {
+    cart: Cartridge,
+    bank_select: u8,
+    bus_conflicts: bool,
+}
+
+impl Axrom {
+    pub fn new(cart: Cartridge, bus_conflicts: bool) -> Self {
+        Axrom {
+            cart,
+            bank_select: 0,
+            bus_conflicts,
+        }
+    }
+
+    fn read_prg(&self, addr: u16) -> u8 {
+        let prg_len = self.cart.prg_rom.len();
+        if prg_len == 0 {
+            return 0;
+        }
+
+        let bank = (self.bank_select & 0x07) as usize;
+        let offset = bank * PRG_BANK_SIZE + (addr as usize & (PRG_BANK_SIZE - 1));
+        self.cart.prg_rom[offset % prg_len]
+    }
+}
+
+impl Mapper for Axrom {
+    fn cpu_read(&mut self, addr: u16) -> u8 {
+        match addr {
+            0x6000..=0x7FFF => self.cart.read_prg_ram(addr),
+            0x8000..=0xFFFF => self.read_prg(addr),
+            _ => 0,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x6000..=0x7FFF => self.cart.write_prg_ram(addr, value),
+            0x8000..=0xFFFF => {
+                self.bank_select = if self.bus_conflicts {
+                    value & self.read_prg(addr)
+                } else {
+                    value
+                };
+            }
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, addr: u16) -> u8 {
+        self.cart.chr.get(addr as usize).copied().unwrap_or(0)
+    }
+
+    fn ppu_write(&mut self, addr: u16, value: u8) {
+        if (addr as usize) < self.cart.chr.len() {
+            self.cart.write_chr(addr as usize, value);
+        }
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        if self.bank_select & 0x10 == 0 {
+            Mirroring::SingleScreenLower
+        } else {
+            Mirroring::SingleScreenUpper
+        }
+    }
+
+    fn cartridge(&self) -> &Cartridge {
+        &self.cart
+    }
+
+    fn cartridge_mut(&mut self) -> &mut Cartridge {
+        &mut self.cart
+    }
+}
@@ -0,0 +1,126 @@
+use crate::nes::cartridge::{Cartridge, Mirroring};
+
+use super::Mapper;
+
+const PRG_BANK_SIZE: usize = 0x8000;
+const CHR_BANK_SIZE: usize = 0x1000;
+
+/// BNROM and NINA-001 (mapper 34)
+///
+/// BNROM:    Any write to $8000-$FFFF selects the 32 KB PRG bank. CHR is
+///           8 KB RAM.
+/// NINA-001: $7FFD: 32 KB PRG bank
+///           $7FFE: 4 KB CHR bank at $0000
+///           $7FFF: 4 KB CHR bank at $1000
+///           The registers sit on top of the PRG RAM at $6000-$7FFF.
+///
+/// The two boards share the mapper number. NES 2.0 submapper 1 is
+/// NINA-001 and submapper 2 is BNROM, otherwise CHR ROM larger than 8 KB
+/// means NINA-001.
+pub struct Bnrom {
+    cart: Cartridge,
+    nina: bool,
+    prg_bank: u8,
+    chr_banks: [u8; 2],
+    bus_conflicts: bool,
+}
+
+impl Bnrom {
+    pub fn new(cart: Cartridge, bus_conflicts: bool) -> Self {
+        let nina = match cart.submapper {
+            1 => true,
+            2 => false,
+            _ => !cart.has_chr_ram && cart.chr.len() > 2 * CHR_BANK_SIZE,
+        };
+        Bnrom {
+            cart,
+            nina,
+            prg_bank: 0,
+            chr_banks: [0, 1],
+            // Submapper 2 is BNROM, which always has bus conflicts
+            bus_conflicts: bus_conflicts && !nina,
+        }
+    }
+
+    fn read_prg(&self, addr: u16) -> u8 {
+        let prg_len = self.cart.prg_rom.len();
+        if prg_len == 0 {
+            return 0;
+        }
+
+        let offset = self.prg_bank as usize * PRG_BANK_SIZE + (addr as usize & (PRG_BANK_SIZE - 1));
+        self.cart.prg_rom[offset % prg_len]
+    }
+
+    fn chr_index(&self, addr: u16) -> Option<usize> {
+        let chr_len = self.cart.chr.len();
+        if chr_len == 0 {
+            return None;
+        }
+
+        if !self.nina {
+            return Some(addr as usize % chr_len);
+        }
+        let bank = self.chr_banks[(addr >> 12) as usize & 1] as usize;
+        Some((bank * CHR_BANK_SIZE + (addr as usize & (CHR_BANK_SIZE - 1))) % chr_len)
+    }
+}
+
+impl Mapper for Bnrom {
+    fn cpu_read(&mut self, addr: u16) -> u8 {
+        match addr {
+            0x6000..=0x7FFF => self.cart.read_prg_ram(addr),
+            0x8000..=0xFFFF => self.read_prg(addr),
+            _ => 0,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x6000..=0x7FFF => {
+                self.cart.write_prg_ram(addr, value);
+                if self.nina {
+                    match addr {
+                        0x7FFD => self.prg_bank = value & 0x01,
+                        0x7FFE => self.chr_banks[0] = value & 0x0F,
+                        0x7FFF => self.chr_banks[1] = value & 0x0F,
+                        _ => {}
+                    }
+                }
+            }
+            0x8000..=0xFFFF if !self.nina => {
+                self.prg_bank = if self.bus_conflicts {
+                    value & self.read_prg(addr)
+                } else {
+                    value
+                };
+            }
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, addr: u16) -> u8 {
+        match self.chr_index(addr) {
+            Some(index) => self.cart.chr[index],
+            None => 0,
+        }
+    }
+
+    fn ppu_write(&mut self, addr: u16, value: u8) {
+        if let Some(index) = self.chr_index(addr) {
+            self.cart.write_chr(index, value);
+        }
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.cart.mirroring
+    }
+
+    fn cartridge(&self) -> &Cartridge {
+        &self.cart
+    }
+
+    fn cartridge_mut(&mut self) -> &mut Cartridge {
+        &mut self.cart
+    }
+}
@@ -0,0 +1,99 @@
+use crate::nes::cartridge::{Cartridge, Mirroring};
+
+use super::Mapper;
+
+const PRG_BANK_SIZE: usize = 0x4000;
+
+/// Camerica/Codemasters BF909x (mapper 71)
+///
+/// $8000-$BFFF: Switchable 16 KB PRG bank
+/// $C000-$FFFF: Fixed to the last 16 KB PRG bank
+///
+/// $9000-$9FFF: Single-screen nametable (bit 4), only on the BF9097 board
+///              used by Fire Hawk
+/// $C000-$FFFF: PRG bank select
+///
+/// CHR is 8 KB RAM. There are no bus conflicts.
+pub struct Camerica {
+    cart: Cartridge,
+    prg_bank: u8,
+    mirroring: Mirroring,
+}
+
+impl Camerica {
+    pub fn new(cart: Cartridge) -> Self {
+        let mirroring = cart.mirroring;
+        Camerica {
+            cart,
+            prg_bank: 0,
+            mirroring,
+        }
+    }
+
+    fn prg_bank_count(&self) -> usize {
+        (self.cart.prg_rom.len() / PRG_BANK_SIZE).max(1)
+    }
+
+    fn read_prg(&self, addr: u16) -> u8 {
+        if self.cart.prg_rom.is_empty() {
+            return 0;
+        }
+
+        let bank = if addr < 0xC000 {
+            self.prg_bank as usize % self.prg_bank_count()
+        } else {
+            self.prg_bank_count() - 1
+        };
+        let offset = bank * PRG_BANK_SIZE + (addr as usize & (PRG_BANK_SIZE - 1));
+        self.cart.prg_rom[offset % self.cart.prg_rom.len()]
+    }
+}
+
+impl Mapper for Camerica {
+    fn cpu_read(&mut self, addr: u16) -> u8 {
+        match addr {
+            0x6000..=0x7FFF => self.cart.read_prg_ram(addr),
+            0x8000..=0xFFFF => self.read_prg(addr),
+            _ => 0,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x6000..=0x7FFF => self.cart.write_prg_ram(addr, value),
+            // NES 2.0 submapper 1 marks the BF9097, but old dumps of Fire
+            // Hawk don't say so; other boards never write here
+            0x9000..=0x9FFF => {
+                self.mirroring = if value & 0x10 == 0 {
+                    Mirroring::SingleScreenLower
+                } else {
+                    Mirroring::SingleScreenUpper
+                };
+            }
+            0xC000..=0xFFFF => self.prg_bank = value,
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, addr: u16) -> u8 {
+        self.cart.chr.get(addr as usize).copied().unwrap_or(0)
+    }
+
+    fn ppu_write(&mut self, addr: u16, value: u8) {
+        if (addr as usize) < self.cart.chr.len() {
+            self.cart.write_chr(addr as usize, value);
+        }
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    fn cartridge(&self) -> &Cartridge {
+        &self.cart
+    }
+
+    fn cartridge_mut(&mut self) -> &mut Cartridge {
+        &mut self.cart
+    }
+}
@@ -0,0 +1,82 @@
+use crate::nes::cartridge::{Cartridge, Mirroring};
+
+use super::Mapper;
+
+const CHR_BANK_SIZE: usize = 0x2000;
+
+/// CNROM (mapper 3)
+///
+/// PRG is fixed like NROM. Any write to $8000-$FFFF selects the 8 KB CHR bank.
+pub struct Cnrom {
+    cart: Cartridge,
+    chr_bank: u8,
+    bus_conflicts: bool,
+}
+
+impl Cnrom {
+    pub fn new(cart: Cartridge, bus_conflicts: bool) -> Self {
+        Cnrom {
+            cart,
+            chr_bank: 0,
+            bus_conflicts,
+        }
+    }
+
+    fn read_prg(&self, addr: u16) -> u8 {
+        let prg_len = self.cart.prg_rom.len();
+        if prg_len == 0 {
+            return 0;
+        }
+        self.cart.prg_rom[(addr - 0x8000) as usize % prg_len]
+    }
+
+    fn chr_index(&self, addr: u16) -> usize {
+        let offset = self.chr_bank as usize * CHR_BANK_SIZE + addr as usize;
+        offset % self.cart.chr.len()
+    }
+}
+
+impl Mapper for Cnrom {
+    fn cpu_read(&mut self, addr: u16) -> u8 {
+        match addr {
+            0x6000..=0x7FFF => self.cart.read_prg_ram(addr),
+            0x8000..=0xFFFF => self.read_prg(addr),
+            _ => 0,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x6000..=0x7FFF => self.cart.write_prg_ram(addr, value),
+            0x8000..=0xFFFF => {
+                self.chr_bank = if self.bus_conflicts {
+                    value & self.read_prg(addr)
+                } else {
+                    value
+                };
+            }
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, addr: u16) -> u8 {
+        self.cart.chr[self.chr_index(addr)]
+    }
+
+    fn ppu_write(&mut self, addr: u16, value: u8) {
+        let index = self.chr_index(addr);
+        self.cart.write_chr(index, value);
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.cart.mirroring
+    }
+
+    fn cartridge(&self) -> &Cartridge {
+        &self.cart
+    }
+
+    fn cartridge_mut(&mut self) -> &mut Cartridge {
+        &mut self.cart
+    }
+}
@@ -0,0 +1,87 @@
+use crate::nes::cartridge::{Cartridge, Mirroring};
+
+use super::Mapper;
+
+const PRG_BANK_SIZE: usize = 0x8000;
+const CHR_BANK_SIZE: usize = 0x2000;
+
+/// Color Dreams (mapper 11)
+///
+/// Any write to $8000-$FFFF selects the 32 KB PRG bank (bits 0-1) and the
+/// 8 KB CHR bank (bits 4-7).
+pub struct ColorDreams {
+    cart: Cartridge,
+    bank_select: u8,
+    bus_conflicts: bool,
+}
+
+impl ColorDreams {
+    pub fn new(cart: Cartridge, bus_conflicts: bool) -> Self {
+        ColorDreams {
+            cart,
+            bank_select: 0,
+            bus_conflicts,
+        }
+    }
+
+    fn read_prg(&self, addr: u16) -> u8 {
+        let prg_len = self.cart.prg_rom.len();
+        if prg_len == 0 {
+            return 0;
+        }
+
+        let bank = (self.bank_select & 0x03) as usize;
+        let offset = bank * PRG_BANK_SIZE + (addr as usize & (PRG_BANK_SIZE - 1));
+        self.cart.prg_rom[offset % prg_len]
+    }
+
+    fn chr_index(&self, addr: u16) -> usize {
+        let bank = (self.bank_select >> 4) as usize;
+        (bank * CHR_BANK_SIZE + addr as usize) % self.cart.chr.len()
+    }
+}
+
+impl Mapper for ColorDreams {
+    fn cpu_read(&mut self, addr: u16) -> u8 {
+        match addr {
+            0x6000..=0x7FFF => self.cart.read_prg_ram(addr),
+            0x8000..=0xFFFF => self.read_prg(addr),
+            _ => 0,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x6000..=0x7FFF => self.cart.write_prg_ram(addr, value),
+            0x8000..=0xFFFF => {
+                self.bank_select = if self.bus_conflicts {
+                    value & self.read_prg(addr)
+                } else {
+                    value
+                };
+            }
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, addr: u16) -> u8 {
+        self.cart.chr[self.chr_index(addr)]
+    }
+
+    fn ppu_write(&mut self, addr: u16, value: u8) {
+        let index = self.chr_index(addr);
+        self.cart.write_chr(index, value);
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.cart.mirroring
+    }
+
+    fn cartridge(&self) -> &Cartridge {
+        &self.cart
+    }
+
+    fn cartridge_mut(&mut self) -> &mut Cartridge {
+        &mut self.cart
+    }
+}
@@ -0,0 +1,405 @@
+use crate::nes::apu::expansion::ExpansionAudio;
+use crate::nes::apu::fds::FdsAudio;
+use crate::nes::cartridge::{Cartridge, Mirroring};
+
+use super::Mapper;
+
+/// CPU cycles before the drive starts reading after the head returns to the
+/// start of the disk
+const SPIN_UP_CYCLES: u32 = 50_000;
+
+/// CPU cycles per byte under the head (~96 kbit/s)
+const BYTE_CYCLES: u32 = 150;
+
+/// CPU cycles the disk stays out of the drive when switching sides, long
+/// enough for the BIOS to notice the disk was ejected (~1 second)
+const EJECT_CYCLES: u32 = 1_789_773;
+
+/// Famicom Disk System RAM adapter (mapper 20)
+///
+/// $6000-$DFFF: 32 KB PRG RAM       $E000-$FFFF: Disk BIOS
+///
+/// $4020-$4021: Timer IRQ reload value (low, high)
+/// $4022: Timer IRQ control: repeat (bit 0), enable (bit 1)
+/// $4023: Master I/O enable: disk registers (bit 0), sound registers (bit 1)
+/// $4024: Write data
+/// $4025: Control: motor on (bit 0), transfer reset (bit 1), read mode (bit 2),
+///        horizontal mirroring (bit 3), CRC control (bit 4), start of block
+///        (bit 6), disk IRQ enable (bit 7)
+/// $4030: Status: timer IRQ (bit 0), byte transferred (bit 1), end of head (bit 6)
+/// $4031: Read data
+/// $4032: Drive status: no disk (bit 0), not ready (bit 1), write protected (bit 2)
+/// $4033: External connector, battery good (bit 7)
+/// $4040-$4097: Sound
+///
+/// The drive moves the head across the disk one byte every 150 CPU cycles
+/// while the motor runs, raising the disk IRQ for every byte transferred,
+/// and returns to the start once it reaches the end.
+///
+/// Switching sides ejects the disk and inserts the next side a second later,
+/// so the BIOS sees the disk leave the drive.
+pub struct Fds {
+    cart: Cartridge,
+    mirroring: Mirroring,
+
+    disk_registers_enabled: bool,
+    sound_registers_enabled: bool,
+
+    timer_reload: u16,
+    timer_counter: u16,
+    timer_repeat: bool,
+    timer_enabled: bool,
+    timer_irq: bool,
+
+    motor_on: bool,
+    reset_transfer: bool,
+    read_mode: bool,
+    crc_control: bool,
+    previous_crc_control: bool,
+    disk_ready: bool,
+    disk_irq_enabled: bool,
+    disk_irq: bool,
+
+    /// Side in the drive, None while ejected
+    side: Option<usize>,
+    /// Side to insert once `eject_delay` runs out
+    next_side: usize,
+    eject_delay: u32,
+
+    position: usize,
+    delay: u32,
+    scanning: bool,
+    end_of_head: bool,
+    gap_ended: bool,
+    transfer_complete: bool,
+    read_data: u8,
+    write_data: u8,
+    crc: u16,
+
+    audio: FdsAudio,
+}
+
+impl Fds {
+    pub fn new(cart: Cartridge) -> Self {
+        Fds {
+            cart,
+            mirroring: Mirroring::Horizontal,
+            disk_registers_enabled: false,
+            sound_registers_enabled: false,
+            timer_reload: 0,
+            timer_counter: 0,
+            timer_repeat: false,
+            timer_enabled: false,
+            timer_irq: false,
+            motor_on: false,
+            reset_transfer: false,
+            read_mode: true,
+            crc_control: false,
+            previous_crc_control: false,
+            disk_ready: false,
+            disk_irq_enabled: false,
+            disk_irq: false,
+            side: Some(0),
+            next_side: 0,
+            eject_delay: 0,
+            position: 0,
+            delay: 0,
+            scanning: false,
+            end_of_head: true,
+            gap_ended: false,
+            transfer_complete: false,
+            read_data: 0,
+            write_data: 0,
+            crc: 0,
+            audio: FdsAudio::new(),
+        }
+    }
+
+    fn side_count(&self) -> usize {
+        self.cart.disk.as_ref().map_or(0, |disk| disk.side_count())
+    }
+
+    fn side_size(&self) -> usize {
+        self.cart.disk.as_ref().map_or(0, |disk| disk.side_size())
+    }
+
+    fn disk_inserted(&self) -> bool {
+        self.side.is_some() && self.side_count() > 0
+    }
+
+    fn read_disk(&self) -> u8 {
+        match (&self.cart.disk, self.side) {
+            (Some(disk), Some(side)) => disk.side(side).get(self.position).copied().unwrap_or(0),
+            _ => 0,
+        }
+    }
+
+    fn write_disk(&mut self, value: u8) {
+        if let (Some(disk), Some(side)) = (&mut self.cart.disk, self.side) {
+            if let Some(byte) = disk.side_mut(side).get_mut(self.position) {
+                *byte = value;
+            }
+        }
+    }
+
+    /// CRC-16/KERMIT, as the drive computes it over each block.
+    fn update_crc(&mut self, value: u8) {
+        let mut data = value as u16;
+        for _ in 0..8 {
+            let carry = (self.crc ^ data) & 0x01 != 0;
+            self.crc >>= 1;
+            if carry {
+                self.crc ^= 0x8408;
+            }
+            data >>= 1;
+        }
+    }
+
+    fn tick_timer(&mut self) {
+        if !self.timer_enabled {
+            return;
+        }
+
+        if self.timer_counter == 0 {
+            self.timer_irq = true;
+            self.timer_counter = self.timer_reload;
+            if !self.timer_repeat {
+                self.timer_enabled = false;
+            }
+        } else {
+            self.timer_counter -= 1;
+        }
+    }
+
+    fn tick_drive(&mut self) {
+        if self.eject_delay > 0 {
+            self.eject_delay -= 1;
+            if self.eject_delay == 0 {
+                self.side = Some(self.next_side);
+            }
+        }
+
+        if !self.disk_inserted() || !self.motor_on {
+            self.end_of_head = true;
+            self.scanning = false;
+            return;
+        }
+
+        if self.reset_transfer && !self.scanning {
+            return;
+        }
+
+        if self.end_of_head {
+            self.delay = SPIN_UP_CYCLES;
+            self.end_of_head = false;
+            self.position = 0;
+            self.gap_ended = false;
+            return;
+        }
+
+        if self.delay > 0 {
+            self.delay -= 1;
+            return;
+        }
+
+        self.scanning = true;
+        let mut raise_irq = self.disk_irq_enabled;
+
+        if self.read_mode {
+            let data = self.read_disk();
+            if !self.previous_crc_control {
+                self.update_crc(data);
+            }
+
+            if !self.disk_ready {
+                self.gap_ended = false;
+                self.crc = 0;
+            } else if data != 0 && !self.gap_ended {
+                // The $80 start mark ends the gap, without raising the IRQ
+                self.gap_ended = true;
+                raise_irq = false;
+            }
+
+            if self.gap_ended {
+                self.transfer_complete = true;
+                self.read_data = data;
+                if raise_irq {
+                    self.disk_irq = true;
+                }
+            }
+        } else {
+            let mut data = 0;
+            if !self.crc_control {
+                self.transfer_complete = true;
+                data = self.write_data;
+                if raise_irq {
+                    self.disk_irq = true;
+                }
+            }
+            if !self.disk_ready {
+                data = 0;
+            }
+
+            if !self.crc_control {
+                self.update_crc(data);
+            } else {
+                data = self.crc as u8;
+                self.crc >>= 8;
+            }
+
+            self.write_disk(data);
+            self.gap_ended = false;
+        }
+
+        self.previous_crc_control = self.crc_control;
+
+        self.position += 1;
+        if self.position >= self.side_size() {
+            self.motor_on = false;
+            self.end_of_head = true;
+        } else {
+            self.delay = BYTE_CYCLES;
+        }
+    }
+
+    fn write_control(&mut self, value: u8) {
+        self.motor_on = value & 0x01 != 0;
+        self.reset_transfer = value & 0x02 != 0;
+        self.read_mode = value & 0x04 != 0;
+        self.mirroring = if value & 0x08 != 0 {
+            Mirroring::Horizontal
+        } else {
+            Mirroring::Vertical
+        };
+        self.crc_control = value & 0x10 != 0;
+        self.disk_ready = value & 0x40 != 0;
+        self.disk_irq_enabled = value & 0x80 != 0;
+        self.disk_irq = false;
+    }
+}
+
+impl Mapper for Fds {
+    fn cpu_read(&mut self, addr: u16) -> u8 {
+        match addr {
+            0x4030 if self.disk_registers_enabled => {
+                let mut status = 0;
+                if self.timer_irq {
+                    status |= 0x01;
+                }
+                if self.transfer_complete {
+                    status |= 0x02;
+                }
+                if self.end_of_head {
+                    status |= 0x40;
+                }
+                self.timer_irq = false;
+                self.disk_irq = false;
+                self.transfer_complete = false;
+                status
+            }
+            0x4031 if self.disk_registers_enabled => {
+                self.transfer_complete = false;
+                self.disk_irq = false;
+                self.read_data
+            }
+            0x4032 if self.disk_registers_enabled => {
+                let inserted = self.disk_inserted();
+                let mut status = 0;
+                if !inserted {
+                    status |= 0x05;
+                }
+                if !inserted || !self.scanning {
+                    status |= 0x02;
+                }
+                status
+            }
+            0x4033 if self.disk_registers_enabled => 0x80,
+            0x4040..=0x4097 if self.sound_registers_enabled => self.audio.read_register(addr).unwrap_or(0),
+            0x6000..=0xDFFF => self.cart.read_prg_ram(addr),
+            0xE000..=0xFFFF => self.cart.prg_rom[addr as usize & 0x1FFF],
+            _ => 0,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x4020 => self.timer_reload = (self.timer_reload & 0xFF00) | value as u16,
+            0x4021 => self.timer_reload = (self.timer_reload & 0x00FF) | (value as u16) << 8,
+            0x4022 => {
+                self.timer_repeat = value & 0x01 != 0;
+                self.timer_enabled = value & 0x02 != 0 && self.disk_registers_enabled;
+                if self.timer_enabled {
+                    self.timer_counter = self.timer_reload;
+                } else {
+                    self.timer_irq = false;
+                }
+            }
+            0x4023 => {
+                self.disk_registers_enabled = value & 0x01 != 0;
+                self.sound_registers_enabled = value & 0x02 != 0;
+                if !self.disk_registers_enabled {
+                    self.timer_enabled = false;
+                    self.timer_irq = false;
+                    self.disk_irq = false;
+                }
+            }
+            0x4024 if self.disk_registers_enabled => {
+                self.write_data = value;
+                self.transfer_complete = false;
+                self.disk_irq = false;
+            }
+            0x4025 if self.disk_registers_enabled => self.write_control(value),
+            0x4040..=0x4097 if self.sound_registers_enabled => self.audio.write_register(addr, value),
+            0x6000..=0xDFFF => self.cart.write_prg_ram(addr, value),
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, addr: u16) -> u8 {
+        self.cart.chr[addr as usize & 0x1FFF]
+    }
+
+    fn ppu_write(&mut self, addr: u16, value: u8) {
+        self.cart.write_chr(addr as usize & 0x1FFF, value);
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    fn cpu_tick(&mut self) {
+        self.tick_timer();
+        self.tick_drive();
+    }
+
+    fn expansion_audio(&mut self) -> Option<&mut dyn ExpansionAudio> {
+        Some(&mut self.audio)
+    }
+
+    fn switch_disk_side(&mut self) {
+        let sides = self.side_count();
+        if sides == 0 {
+            return;
+        }
+
+        self.next_side = match self.side {
+            Some(side) => (side + 1) % sides,
+            None => (self.next_side + 1) % sides,
+        };
+        self.side = None;
+        self.eject_delay = EJECT_CYCLES;
+        println!("Inserting disk side {} of {sides}", self.next_side + 1);
+    }
+
+    fn cartridge(&self) -> &Cartridge {
+        &self.cart
+    }
+
+    fn cartridge_mut(&mut self) -> &mut Cartridge {
+        &mut self.cart
+    }
+
+    fn irq_pending(&self) -> bool {
+        self.timer_irq || self.disk_irq
+    }
+}
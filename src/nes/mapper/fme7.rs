@@ -0,0 +1,178 @@
+use crate::nes::apu::expansion::ExpansionAudio;
+use crate::nes::apu::sunsoft5b::Sunsoft5bAudio;
+use crate::nes::cartridge::{Cartridge, Mirroring};
+
+use super::Mapper;
+
+const PRG_BANK_SIZE: usize = 0x2000;
+const CHR_BANK_SIZE: usize = 0x0400;
+
+/// Sunsoft FME-7 / 5A / 5B (mapper 69)
+///
+/// $8000-$9FFF: Command      $A000-$BFFF: Parameter
+/// $C000-$DFFF: Audio register select (5B)      $E000-$FFFF: Audio register data (5B)
+///
+/// Commands:
+/// $0-$7: CHR banks 0-7 (1 KB)
+/// $8:    8 KB bank at $6000: bank (bits 0-5), RAM instead of ROM (bit 6), RAM enable (bit 7)
+/// $9-$B: 8 KB PRG banks at $8000, $A000 and $C000
+/// $C:    Mirroring (0: vertical, 1: horizontal, 2: single lower, 3: single upper)
+/// $D:    IRQ control: IRQ enable (bit 0), counter enable (bit 7); acknowledges the IRQ
+/// $E-$F: IRQ counter low/high byte
+///
+/// $E000-$FFFF is fixed to the last 8 KB PRG bank. The 16-bit IRQ counter
+/// counts down every CPU cycle and fires when it wraps from $0000 to $FFFF.
+pub struct Fme7 {
+    cart: Cartridge,
+
+    command: u8,
+    prg_banks: [u8; 4],
+    chr_banks: [u8; 8],
+    mirroring: Mirroring,
+
+    irq_counter: u16,
+    irq_enabled: bool,
+    irq_counter_enabled: bool,
+    irq_pending: bool,
+
+    audio: Sunsoft5bAudio,
+}
+
+impl Fme7 {
+    pub fn new(cart: Cartridge) -> Self {
+        let mirroring = cart.mirroring;
+        Fme7 {
+            cart,
+            command: 0,
+            prg_banks: [0; 4],
+            chr_banks: [0; 8],
+            mirroring,
+            irq_counter: 0,
+            irq_enabled: false,
+            irq_counter_enabled: false,
+            irq_pending: false,
+            audio: Sunsoft5bAudio::new(),
+        }
+    }
+
+    fn prg_ram_selected(&self) -> bool {
+        self.prg_banks[0] & 0x40 != 0
+    }
+
+    fn prg_ram_enabled(&self) -> bool {
+        self.prg_banks[0] & 0xC0 == 0xC0
+    }
+
+    fn read_prg(&self, addr: u16) -> u8 {
+        if self.cart.prg_rom.is_empty() {
+            return 0;
+        }
+
+        let bank_count = (self.cart.prg_rom.len() / PRG_BANK_SIZE).max(1);
+        let bank = match addr {
+            0x6000..=0xDFFF => self.prg_banks[(addr as usize - 0x6000) / PRG_BANK_SIZE] as usize & 0x3F,
+            _ => bank_count - 1,
+        };
+        let offset = (bank % bank_count) * PRG_BANK_SIZE + (addr as usize & (PRG_BANK_SIZE - 1));
+        self.cart.prg_rom[offset % self.cart.prg_rom.len()]
+    }
+
+    fn chr_index(&self, addr: u16) -> Option<usize> {
+        let chr_len = self.cart.chr.len();
+        if chr_len == 0 {
+            return None;
+        }
+
+        let bank = self.chr_banks[(addr as usize / CHR_BANK_SIZE) & 0x07] as usize;
+        let offset = bank * CHR_BANK_SIZE + (addr as usize & (CHR_BANK_SIZE - 1));
+        Some(offset % chr_len)
+    }
+
+    fn write_parameter(&mut self, value: u8) {
+        match self.command {
+            reg @ 0x0..=0x7 => self.chr_banks[reg as usize] = value,
+            reg @ 0x8..=0xB => self.prg_banks[(reg - 0x8) as usize] = value,
+            0xC => {
+                self.mirroring = match value & 0x03 {
+                    0 => Mirroring::Vertical,
+                    1 => Mirroring::Horizontal,
+                    2 => Mirroring::SingleScreenLower,
+                    _ => Mirroring::SingleScreenUpper,
+                };
+            }
+            0xD => {
+                self.irq_enabled = value & 0x01 != 0;
+                self.irq_counter_enabled = value & 0x80 != 0;
+                self.irq_pending = false;
+            }
+            0xE => self.irq_counter = (self.irq_counter & 0xFF00) | value as u16,
+            _ => self.irq_counter = (self.irq_counter & 0x00FF) | (value as u16) << 8,
+        }
+    }
+}
+
+impl Mapper for Fme7 {
+    fn cpu_read(&mut self, addr: u16) -> u8 {
+        match addr {
+            0x6000..=0x7FFF if self.prg_ram_enabled() => self.cart.read_prg_ram(addr),
+            0x6000..=0x7FFF if self.prg_ram_selected() => 0,
+            0x6000..=0xFFFF => self.read_prg(addr),
+            _ => 0,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x6000..=0x7FFF if self.prg_ram_enabled() => self.cart.write_prg_ram(addr, value),
+            0x8000..=0x9FFF => self.command = value & 0x0F,
+            0xA000..=0xBFFF => self.write_parameter(value),
+            0xC000..=0xDFFF => self.audio.write_address(value),
+            0xE000..=0xFFFF => self.audio.write_data(value),
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, addr: u16) -> u8 {
+        match self.chr_index(addr) {
+            Some(index) => self.cart.chr[index],
+            None => 0,
+        }
+    }
+
+    fn ppu_write(&mut self, addr: u16, value: u8) {
+        if let Some(index) = self.chr_index(addr) {
+            self.cart.write_chr(index, value);
+        }
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    fn cpu_tick(&mut self) {
+        if !self.irq_counter_enabled {
+            return;
+        }
+
+        self.irq_counter = self.irq_counter.wrapping_sub(1);
+        if self.irq_counter == 0xFFFF && self.irq_enabled {
+            self.irq_pending = true;
+        }
+    }
+
+    fn expansion_audio(&mut self) -> Option<&mut dyn ExpansionAudio> {
+        Some(&mut self.audio)
+    }
+
+    fn cartridge(&self) -> &Cartridge {
+        &self.cart
+    }
+
+    fn cartridge_mut(&mut self) -> &mut Cartridge {
+        &mut self.cart
+    }
+
+    fn irq_pending(&self) -> bool {
+        self.irq_pending
+    }
+}
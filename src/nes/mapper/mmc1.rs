@@ -0,0 +1,213 @@
+use crate::nes::cartridge::{Cartridge, Mirroring};
+
+use super::Mapper;
+
+const PRG_BANK_SIZE: usize = 0x4000;
+const CHR_BANK_SIZE: usize = 0x1000;
+
+/// MMC1 (mapper 1)
+///
+/// Registers are loaded serially: each write to $8000-$FFFF shifts bit 0
+/// into a 5-bit shift register, and the fifth write copies the value into
+/// the register selected by address bits 13-14. Writing a value with bit 7
+/// set resets the shift register and locks the PRG bank mode to 3.
+/// Writes on consecutive CPU cycles are ignored after the first, so the
+/// dummy write of a read-modify-write instruction loads only one bit.
+///
+/// $8000-$9FFF: Control (mirroring, PRG bank mode, CHR bank mode)
+/// $A000-$BFFF: CHR bank 0
+/// $C000-$DFFF: CHR bank 1
+/// $E000-$FFFF: PRG bank (bit 4 disables PRG RAM)
+pub struct Mmc1 {
+    cart: Cartridge,
+
+    shift_register: u8,
+    shift_count: u8,
+    cpu_cycle: u64,
+    last_write_cycle: u64,
+
+    control: u8,
+    chr_bank_0: u8,
+    chr_bank_1: u8,
+    prg_bank: u8,
+}
+
+impl Mmc1 {
+    pub fn new(cart: Cartridge) -> Self {
+        Mmc1 {
+            cart,
+            shift_register: 0,
+            shift_count: 0,
+            cpu_cycle: 0,
+            last_write_cycle: 0,
+            // Power-on state: PRG bank mode 3 (last bank fixed at $C000)
+            control: 0x0C,
+            chr_bank_0: 0,
+            chr_bank_1: 0,
+            prg_bank: 0,
+        }
+    }
+
+    fn write_register(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x8000..=0x9FFF => self.control = value,
+            0xA000..=0xBFFF => self.chr_bank_0 = value,
+            0xC000..=0xDFFF => self.chr_bank_1 = value,
+            0xE000..=0xFFFF => self.prg_bank = value,
+            _ => {}
+        }
+    }
+
+    fn prg_ram_enabled(&self) -> bool {
+        self.prg_bank & 0x10 == 0
+    }
+
+    fn prg_bank_count(&self) -> usize {
+        (self.cart.prg_rom.len() / PRG_BANK_SIZE).max(1)
+    }
+
+    /// 512 KB boards (SUROM) use CHR bank bit 4 to select the 256 KB PRG half.
+    fn prg_outer_bank(&self) -> usize {
+        if self.cart.prg_rom.len() > 0x40000 {
+            self.chr_bank_0 as usize & 0x10
+        } else {
+            0
+        }
+    }
+
+    /// Resolve a CPU address in $8000-$FFFF to a 16 KB PRG bank number.
+    fn prg_bank_for(&self, addr: u16) -> usize {
+        let bank = (self.prg_bank & 0x0F) as usize;
+        let last = (self.prg_bank_count() - 1) & 0x0F;
+        let upper_half = addr >= 0xC000;
+
+        let bank = match (self.control >> 2) & 0b11 {
+            // 32 KB mode: ignore low bit of bank number
+            0 | 1 => (bank & !1) | upper_half as usize,
+            // Fix first bank at $8000, switch 16 KB bank at $C000
+            2 => {
+                if upper_half {
+                    bank
+                } else {
+                    0
+                }
+            }
+            // Fix last bank at $C000, switch 16 KB bank at $8000
+            _ => {
+                if upper_half {
+                    last
+                } else {
+                    bank
+                }
+            }
+        };
+
+        self.prg_outer_bank() | bank
+    }
+
+    /// Resolve a PPU address in $0000-$1FFF to a 4 KB CHR bank number.
+    fn chr_bank_for(&self, addr: u16) -> usize {
+        if self.control & 0x10 == 0 {
+            // 8 KB mode: ignore low bit of bank number
+            (self.chr_bank_0 as usize & !1) | (addr >= 0x1000) as usize
+        } else if addr < 0x1000 {
+            self.chr_bank_0 as usize
+        } else {
+            self.chr_bank_1 as usize
+        }
+    }
+
+    fn chr_index(&self, addr: u16) -> Option<usize> {
+        let chr_len = self.cart.chr.len();
+        if chr_len == 0 {
+            return None;
+        }
+
+        let bank = self.chr_bank_for(addr);
+        let offset = bank * CHR_BANK_SIZE + (addr as usize & (CHR_BANK_SIZE - 1));
+        Some(offset % chr_len)
+    }
+}
+
+impl Mapper for Mmc1 {
+    fn cpu_read(&mut self, addr: u16) -> u8 {
+        match addr {
+            0x6000..=0x7FFF if self.prg_ram_enabled() => self.cart.read_prg_ram(addr),
+            0x8000..=0xFFFF => {
+                if self.cart.prg_rom.is_empty() {
+                    return 0;
+                }
+                let bank = self.prg_bank_for(addr) % self.prg_bank_count();
+                let offset = bank * PRG_BANK_SIZE + (addr as usize & (PRG_BANK_SIZE - 1));
+                self.cart.prg_rom[offset]
+            }
+            _ => 0,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, value: u8) {
+        if addr < 0x8000 {
+            if (0x6000..=0x7FFF).contains(&addr) && self.prg_ram_enabled() {
+                self.cart.write_prg_ram(addr, value);
+            }
+            return;
+        }
+
+        let consecutive = self.cpu_cycle == self.last_write_cycle + 1;
+        self.last_write_cycle = self.cpu_cycle;
+        if consecutive {
+            return;
+        }
+
+        // Bit 7 set: reset the shift register and lock PRG mode 3
+        if value & 0x80 != 0 {
+            self.shift_register = 0;
+            self.shift_count = 0;
+            self.control |= 0x0C;
+            return;
+        }
+
+        self.shift_register |= (value & 0x01) << self.shift_count;
+        self.shift_count += 1;
+
+        if self.shift_count == 5 {
+            self.write_register(addr, self.shift_register);
+            self.shift_register = 0;
+            self.shift_count = 0;
+        }
+    }
+
+    fn ppu_read(&mut self, addr: u16) -> u8 {
+        match self.chr_index(addr) {
+            Some(index) => self.cart.chr[index],
+            None => 0,
+        }
+    }
+
+    fn ppu_write(&mut self, addr: u16, value: u8) {
+        if let Some(index) = self.chr_index(addr) {
+            self.cart.write_chr(index, value);
+        }
+    }
+
+    fn cpu_tick(&mut self) {
+        self.cpu_cycle += 1;
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        match self.control & 0b11 {
+            0 => Mirroring::SingleScreenLower,
+            1 => Mirroring::SingleScreenUpper,
+            2 => Mirroring::Vertical,
+            _ => Mirroring::Horizontal,
+        }
+    }
+
+    fn cartridge(&self) -> &Cartridge {
+        &self.cart
+    }
+
+    fn cartridge_mut(&mut self) -> &mut Cartridge {
+        &mut self.cart
+    }
+}
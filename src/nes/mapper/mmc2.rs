@@ -0,0 +1,153 @@
+use crate::nes::cartridge::{Cartridge, Mirroring};
+
+use super::Mapper;
+
+const PRG_BANK_SIZE_MMC2: usize = 0x2000;
+const PRG_BANK_SIZE_MMC4: usize = 0x4000;
+const CHR_BANK_SIZE: usize = 0x1000;
+
+/// MMC2 (mapper 9) and MMC4 (mapper 10)
+///
+/// $A000-$AFFF: PRG bank at $8000 (MMC2: 8 KB, MMC4: 16 KB), the rest is
+///              fixed to the last banks
+/// $B000-$BFFF: 4 KB CHR bank at $0000 when latch 0 is $FD
+/// $C000-$CFFF: 4 KB CHR bank at $0000 when latch 0 is $FE
+/// $D000-$DFFF: 4 KB CHR bank at $1000 when latch 1 is $FD
+/// $E000-$EFFF: 4 KB CHR bank at $1000 when latch 1 is $FE
+/// $F000-$FFFF: Mirroring
+///
+/// Each pattern table has a latch that flips after the PPU fetches tile $FD
+/// or $FE from it, switching CHR banks mid-scanline without CPU help.
+pub struct Mmc2 {
+    cart: Cartridge,
+    mmc4: bool,
+
+    prg_bank: u8,
+    chr_banks: [[u8; 2]; 2],
+    latches: [usize; 2],
+    mirroring: Mirroring,
+}
+
+impl Mmc2 {
+    pub fn new(cart: Cartridge, mmc4: bool) -> Self {
+        let mirroring = cart.mirroring;
+        Mmc2 {
+            cart,
+            mmc4,
+            prg_bank: 0,
+            chr_banks: [[0; 2]; 2],
+            latches: [1; 2],
+            mirroring,
+        }
+    }
+
+    fn prg_bank_size(&self) -> usize {
+        if self.mmc4 {
+            PRG_BANK_SIZE_MMC4
+        } else {
+            PRG_BANK_SIZE_MMC2
+        }
+    }
+
+    fn read_prg(&self, addr: u16) -> u8 {
+        let prg_len = self.cart.prg_rom.len();
+        if prg_len == 0 {
+            return 0;
+        }
+
+        let bank_size = self.prg_bank_size();
+        let bank_count = (prg_len / bank_size).max(1);
+        let slot = (addr as usize - 0x8000) / bank_size;
+        let slot_count = 0x8000 / bank_size;
+        let bank = if slot == 0 {
+            self.prg_bank as usize
+        } else {
+            // The slots after the first map to the last banks in order
+            bank_count.saturating_sub(slot_count - slot)
+        };
+        let offset = bank * bank_size + (addr as usize & (bank_size - 1));
+        self.cart.prg_rom[offset % prg_len]
+    }
+
+    fn chr_index(&self, addr: u16) -> Option<usize> {
+        let chr_len = self.cart.chr.len();
+        if chr_len == 0 {
+            return None;
+        }
+
+        let table = (addr >> 12) as usize & 1;
+        let bank = self.chr_banks[table][self.latches[table]] as usize;
+        Some((bank * CHR_BANK_SIZE + (addr as usize & (CHR_BANK_SIZE - 1))) % chr_len)
+    }
+
+    /// Flip the latches after the fetch that triggers them. MMC2 only
+    /// watches the single address $0FD8/$0FE8 in the left pattern table,
+    /// MMC4 and the right table watch the whole 8-byte range.
+    fn update_latches(&mut self, addr: u16) {
+        match addr {
+            0x0FD8 => self.latches[0] = 0,
+            0x0FE8 => self.latches[0] = 1,
+            0x0FD9..=0x0FDF if self.mmc4 => self.latches[0] = 0,
+            0x0FE9..=0x0FEF if self.mmc4 => self.latches[0] = 1,
+            0x1FD8..=0x1FDF => self.latches[1] = 0,
+            0x1FE8..=0x1FEF => self.latches[1] = 1,
+            _ => {}
+        }
+    }
+}
+
+impl Mapper for Mmc2 {
+    fn cpu_read(&mut self, addr: u16) -> u8 {
+        match addr {
+            0x6000..=0x7FFF => self.cart.read_prg_ram(addr),
+            0x8000..=0xFFFF => self.read_prg(addr),
+            _ => 0,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x6000..=0x7FFF => self.cart.write_prg_ram(addr, value),
+            0xA000..=0xAFFF => self.prg_bank = value & 0x0F,
+            0xB000..=0xBFFF => self.chr_banks[0][0] = value & 0x1F,
+            0xC000..=0xCFFF => self.chr_banks[0][1] = value & 0x1F,
+            0xD000..=0xDFFF => self.chr_banks[1][0] = value & 0x1F,
+            0xE000..=0xEFFF => self.chr_banks[1][1] = value & 0x1F,
+            0xF000..=0xFFFF => {
+                self.mirroring = if value & 1 == 0 {
+                    Mirroring::Vertical
+                } else {
+                    Mirroring::Horizontal
+                };
+            }
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, addr: u16) -> u8 {
+        let value = match self.chr_index(addr) {
+            Some(index) => self.cart.chr[index],
+            None => 0,
+        };
+        self.update_latches(addr);
+        value
+    }
+
+    fn ppu_write(&mut self, addr: u16, value: u8) {
+        if let Some(index) = self.chr_index(addr) {
+            self.cart.write_chr(index, value);
+        }
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    fn cartridge(&self) -> &Cartridge {
+        &self.cart
+    }
+
+    fn cartridge_mut(&mut self) -> &mut Cartridge {
+        &mut self.cart
+    }
+}
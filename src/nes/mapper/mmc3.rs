@@ -0,0 +1,238 @@
+use crate::nes::cartridge::{Cartridge, Mirroring};
+
+use super::Mapper;
+
+const PRG_BANK_SIZE: usize = 0x2000;
+const CHR_BANK_SIZE: usize = 0x0400;
+
+/// Minimum number of PPU dots A12 must stay low before a rising edge
+/// clocks the IRQ counter. The real chip filters on ~3 falling M2 edges,
+/// which hides the short A12 toggles between individual sprite fetches.
+const A12_FILTER_DOTS: u64 = 10;
+
+/// MMC3 (mapper 4)
+///
+/// $8000-$9FFF even: Bank select      odd: Bank data
+/// $A000-$BFFF even: Mirroring        odd: PRG RAM protect
+/// $C000-$DFFF even: IRQ latch        odd: IRQ reload
+/// $E000-$FFFF even: IRQ disable      odd: IRQ enable
+///
+/// PRG RAM protection is not emulated: MMC6 boards share mapper 4 and use
+/// the $A001 bits differently, so PRG RAM is always readable and writable.
+///
+/// The scanline counter is clocked by rising edges of PPU A12, which happen
+/// once per scanline when backgrounds and sprites use different pattern tables.
+pub struct Mmc3 {
+    cart: Cartridge,
+
+    bank_select: u8,
+    bank_registers: [u8; 8],
+    mirroring: Mirroring,
+
+    irq_latch: u8,
+    irq_counter: u8,
+    irq_reload: bool,
+    irq_enabled: bool,
+    irq_pending: bool,
+
+    a12_high: bool,
+    a12_low_since: u64,
+}
+
+impl Mmc3 {
+    pub fn new(cart: Cartridge) -> Self {
+        let mirroring = cart.mirroring;
+        Mmc3 {
+            cart,
+            bank_select: 0,
+            bank_registers: [0, 2, 4, 5, 6, 7, 0, 1],
+            mirroring,
+            irq_latch: 0,
+            irq_counter: 0,
+            irq_reload: false,
+            irq_enabled: false,
+            irq_pending: false,
+            a12_high: false,
+            a12_low_since: 0,
+        }
+    }
+
+    fn prg_bank_count(&self) -> usize {
+        (self.cart.prg_rom.len() / PRG_BANK_SIZE).max(1)
+    }
+
+    /// Resolve a CPU address in $8000-$FFFF to an 8 KB PRG bank number.
+    fn prg_bank_for(&self, addr: u16) -> usize {
+        let second_last = self.prg_bank_count().saturating_sub(2);
+        let last = self.prg_bank_count() - 1;
+        let r6 = (self.bank_registers[6] & 0x3F) as usize;
+        let r7 = (self.bank_registers[7] & 0x3F) as usize;
+        let prg_mode = self.bank_select & 0x40 != 0;
+
+        match (addr >> 13) & 0b11 {
+            0 => {
+                if prg_mode {
+                    second_last
+                } else {
+                    r6
+                }
+            }
+            1 => r7,
+            2 => {
+                if prg_mode {
+                    r6
+                } else {
+                    second_last
+                }
+            }
+            _ => last,
+        }
+    }
+
+    /// Resolve a PPU address in $0000-$1FFF to a 1 KB CHR bank number.
+    fn chr_bank_for(&self, addr: u16) -> usize {
+        // CHR A12 inversion swaps the 2 KB and 1 KB halves
+        let addr = if self.bank_select & 0x80 != 0 {
+            addr ^ 0x1000
+        } else {
+            addr
+        };
+        let slot = (addr / CHR_BANK_SIZE as u16) as usize;
+        let r = &self.bank_registers;
+
+        match slot {
+            0 => (r[0] & 0xFE) as usize,
+            1 => (r[0] | 0x01) as usize,
+            2 => (r[1] & 0xFE) as usize,
+            3 => (r[1] | 0x01) as usize,
+            4 => r[2] as usize,
+            5 => r[3] as usize,
+            6 => r[4] as usize,
+            _ => r[5] as usize,
+        }
+    }
+
+    fn chr_index(&self, addr: u16) -> Option<usize> {
+        let chr_len = self.cart.chr.len();
+        if chr_len == 0 {
+            return None;
+        }
+
+        let bank = self.chr_bank_for(addr);
+        let offset = bank * CHR_BANK_SIZE + (addr as usize & (CHR_BANK_SIZE - 1));
+        Some(offset % chr_len)
+    }
+
+    fn clock_irq_counter(&mut self) {
+        if self.irq_counter == 0 || self.irq_reload {
+            self.irq_counter = self.irq_latch;
+            self.irq_reload = false;
+        } else {
+            self.irq_counter -= 1;
+        }
+
+        if self.irq_counter == 0 && self.irq_enabled {
+            self.irq_pending = true;
+        }
+    }
+}
+
+impl Mapper for Mmc3 {
+    fn cpu_read(&mut self, addr: u16) -> u8 {
+        match addr {
+            0x6000..=0x7FFF => self.cart.read_prg_ram(addr),
+            0x8000..=0xFFFF => {
+                if self.cart.prg_rom.is_empty() {
+                    return 0;
+                }
+                let bank = self.prg_bank_for(addr) % self.prg_bank_count();
+                let offset = bank * PRG_BANK_SIZE + (addr as usize & (PRG_BANK_SIZE - 1));
+                self.cart.prg_rom[offset]
+            }
+            _ => 0,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, value: u8) {
+        let even = addr & 1 == 0;
+
+        match addr {
+            0x6000..=0x7FFF => self.cart.write_prg_ram(addr, value),
+            0x8000..=0x9FFF => {
+                if even {
+                    self.bank_select = value;
+                } else {
+                    let register = (self.bank_select & 0x07) as usize;
+                    self.bank_registers[register] = value;
+                }
+            }
+            0xA000..=0xBFFF if even && !self.cart.has_four_screen => {
+                self.mirroring = if value & 1 == 0 {
+                    Mirroring::Vertical
+                } else {
+                    Mirroring::Horizontal
+                };
+            }
+            0xC000..=0xDFFF => {
+                if even {
+                    self.irq_latch = value;
+                } else {
+                    self.irq_counter = 0;
+                    self.irq_reload = true;
+                }
+            }
+            0xE000..=0xFFFF => {
+                if even {
+                    self.irq_enabled = false;
+                    self.irq_pending = false;
+                } else {
+                    self.irq_enabled = true;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, addr: u16) -> u8 {
+        match self.chr_index(addr) {
+            Some(index) => self.cart.chr[index],
+            None => 0,
+        }
+    }
+
+    fn ppu_write(&mut self, addr: u16, value: u8) {
+        if let Some(index) = self.chr_index(addr) {
+            self.cart.write_chr(index, value);
+        }
+    }
+
+    fn ppu_address(&mut self, addr: u16, ppu_dot: u64) {
+        let a12_high = addr & 0x1000 != 0;
+
+        if a12_high && !self.a12_high {
+            if ppu_dot.wrapping_sub(self.a12_low_since) >= A12_FILTER_DOTS {
+                self.clock_irq_counter();
+            }
+        } else if !a12_high && self.a12_high {
+            self.a12_low_since = ppu_dot;
+        }
+
+        self.a12_high = a12_high;
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    fn cartridge(&self) -> &Cartridge {
+        &self.cart
+    }
+
+    fn cartridge_mut(&mut self) -> &mut Cartridge {
+        &mut self.cart
+    }
+
+    fn irq_pending(&self) -> bool {
+        self.irq_pending
+    }
+}
@@ -0,0 +1,511 @@
+use crate::nes::apu::expansion::ExpansionAudio;
+use crate::nes::apu::mmc5::Mmc5Audio;
+use crate::nes::cartridge::{Cartridge, Mirroring};
+
+use super::Mapper;
+
+const PRG_BANK_SIZE: usize = 0x2000;
+const CHR_BANK_SIZE: usize = 0x2000;
+
+/// PPU dots without a nametable fetch after which the PPU is assumed to have
+/// stopped rendering (longer than the gap over the sprite fetches).
+const IDLE_DOTS: u64 = 341;
+
+/// Tile being fetched when a scanline is detected. The nametable bytes of
+/// tiles 0-2 are fetched at the end of the previous line.
+const TILE_AT_DETECTION: u8 = 2;
+
+/// Nametable fetches per scanline: 34 tiles plus the unused fetch at dot 257.
+const TILES_PER_LINE: u8 = 35;
+
+/// MMC5 (mapper 5)
+///
+/// $5000-$5015: Expansion audio
+/// $5100: PRG mode           $5101: CHR mode
+/// $5102/$5103: PRG RAM protect (writes allowed with $02/$01)
+/// $5104: ExRAM mode         $5105: Nametable mapping
+/// $5106: Fill-mode tile     $5107: Fill-mode attribute
+/// $5113-$5117: PRG banks    $5120-$512B: CHR banks   $5130: CHR upper bits
+/// $5200-$5202: Vertical split control, scroll and CHR bank
+/// $5203: IRQ scanline       $5204: IRQ enable / status
+/// $5205/$5206: 8x8 unsigned multiplier
+/// $5C00-$5FFF: ExRAM
+///
+/// The chip finds the start of each scanline by watching for the PPU to
+/// fetch the same nametable address three times in a row, which it does at
+/// the end of every rendered line. It counts the tile fetches after that to
+/// know which tile the PPU is working on, for the vertical split.
+///
+/// With 8x16 sprites, sprites use CHR banks $5120-$5127 and the background
+/// uses $5128-$512B. With 8x8 sprites, whichever set was written last is
+/// used for everything.
+pub struct Mmc5 {
+    cart: Cartridge,
+    audio: Mmc5Audio,
+
+    prg_mode: u8,
+    chr_mode: u8,
+    prg_ram_protect: [u8; 2],
+    exram_mode: u8,
+    nametable_mapping: u8,
+    fill_tile: u8,
+    fill_attribute: u8,
+    prg_banks: [u8; 5],
+    sprite_chr_banks: [u16; 8],
+    bg_chr_banks: [u16; 4],
+    bg_chr_written_last: bool,
+    chr_upper: u8,
+
+    split_control: u8,
+    split_scroll: u8,
+    split_chr_bank: u8,
+
+    irq_scanline: u8,
+    irq_enabled: bool,
+    irq_pending: bool,
+
+    multiplicand: u8,
+    multiplier: u8,
+
+    exram: [u8; 0x400],
+
+    // PPU snooping
+    tall_sprites: bool,
+    sprite_fetches: bool,
+    in_frame: bool,
+    scanline: u8,
+    last_nametable_addr: u16,
+    nametable_repeats: u8,
+    last_nametable_dot: u64,
+    tile: u8,
+    prefetch: bool, // Fetching the first tiles of the next line
+
+    // Overrides for the pattern fetches of the current background tile
+    split_fine_y: Option<u8>,
+    exattr_bank: Option<usize>,
+}
+
+impl Mmc5 {
+    pub fn new(cart: Cartridge) -> Self {
+        Mmc5 {
+            cart,
+            audio: Mmc5Audio::new(),
+            prg_mode: 3,
+            chr_mode: 0,
+            prg_ram_protect: [0; 2],
+            exram_mode: 0,
+            nametable_mapping: 0,
+            fill_tile: 0,
+            fill_attribute: 0,
+            prg_banks: [0, 0, 0, 0, 0xFF],
+            sprite_chr_banks: [0; 8],
+            bg_chr_banks: [0; 4],
+            bg_chr_written_last: false,
+            chr_upper: 0,
+            split_control: 0,
+            split_scroll: 0,
+            split_chr_bank: 0,
+            irq_scanline: 0,
+            irq_enabled: false,
+            irq_pending: false,
+            multiplicand: 0xFF,
+            multiplier: 0xFF,
+            exram: [0; 0x400],
+            tall_sprites: false,
+            sprite_fetches: false,
+            in_frame: false,
+            scanline: 0,
+            last_nametable_addr: 0,
+            nametable_repeats: 0,
+            last_nametable_dot: 0,
+            tile: 0,
+            prefetch: false,
+            split_fine_y: None,
+            exattr_bank: None,
+        }
+    }
+
+    /// Resolve a CPU address in $8000-$FFFF to (is ROM, 8 KB bank number).
+    fn prg_bank_for(&self, addr: u16) -> (bool, usize) {
+        let slot = ((addr - 0x8000) / PRG_BANK_SIZE as u16) as usize;
+
+        // Register ($5113 + index) and how many low bank bits the slot size ignores
+        let (register, size_bits) = match (self.prg_mode, slot) {
+            (0, _) => (4, 2),
+            (1, 0 | 1) => (2, 1),
+            (1, _) => (4, 1),
+            (2, 0 | 1) => (2, 1),
+            (2, 2) => (3, 0),
+            (2, _) => (4, 0),
+            (_, slot) => (slot + 1, 0),
+        };
+
+        let value = self.prg_banks[register];
+        let mask = (1 << size_bits) - 1;
+        let bank = (value as usize & 0x7F & !mask) | (slot & mask);
+
+        // $5117 always maps ROM, the others select ROM with bit 7
+        let is_rom = register == 4 || value & 0x80 != 0;
+        (is_rom, bank)
+    }
+
+    fn read_prg(&self, addr: u16) -> u8 {
+        let (is_rom, bank) = self.prg_bank_for(addr);
+        let offset = addr as usize & (PRG_BANK_SIZE - 1);
+
+        if is_rom {
+            let prg_len = self.cart.prg_rom.len();
+            if prg_len == 0 {
+                return 0;
+            }
+            self.cart.prg_rom[(bank * PRG_BANK_SIZE + offset) % prg_len]
+        } else {
+            self.read_prg_ram(bank, offset)
+        }
+    }
+
+    fn read_prg_ram(&self, bank: usize, offset: usize) -> u8 {
+        let ram_len = self.cart.prg_ram.len();
+        if ram_len == 0 {
+            return 0;
+        }
+        self.cart.prg_ram[(bank * PRG_BANK_SIZE + offset) % ram_len]
+    }
+
+    fn write_prg_ram(&mut self, bank: usize, offset: usize, value: u8) {
+        let ram_len = self.cart.prg_ram.len();
+        if ram_len == 0 || self.prg_ram_protect != [0x02, 0x01] {
+            return;
+        }
+        self.cart.prg_ram[(bank * PRG_BANK_SIZE + offset) % ram_len] = value;
+    }
+
+    fn write_register(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x5000..=0x5015 => self.audio.write_register(addr, value),
+            0x5100 => self.prg_mode = value & 0x03,
+            0x5101 => self.chr_mode = value & 0x03,
+            0x5102 => self.prg_ram_protect[0] = value & 0x03,
+            0x5103 => self.prg_ram_protect[1] = value & 0x03,
+            0x5104 => self.exram_mode = value & 0x03,
+            0x5105 => self.nametable_mapping = value,
+            0x5106 => self.fill_tile = value,
+            0x5107 => self.fill_attribute = value & 0x03,
+            0x5113..=0x5117 => self.prg_banks[(addr - 0x5113) as usize] = value,
+            0x5120..=0x5127 => {
+                let bank = ((self.chr_upper as u16) << 8) | value as u16;
+                self.sprite_chr_banks[(addr - 0x5120) as usize] = bank;
+                self.bg_chr_written_last = false;
+            }
+            0x5128..=0x512B => {
+                let bank = ((self.chr_upper as u16) << 8) | value as u16;
+                self.bg_chr_banks[(addr - 0x5128) as usize] = bank;
+                self.bg_chr_written_last = true;
+            }
+            0x5130 => self.chr_upper = value & 0x03,
+            0x5200 => self.split_control = value,
+            0x5201 => self.split_scroll = value,
+            0x5202 => self.split_chr_bank = value,
+            0x5203 => self.irq_scanline = value,
+            0x5204 => self.irq_enabled = value & 0x80 != 0,
+            0x5205 => self.multiplicand = value,
+            0x5206 => self.multiplier = value,
+            // ExRAM is read-only in mode 3
+            0x5C00..=0x5FFF if self.exram_mode != 3 => {
+                self.exram[(addr - 0x5C00) as usize] = value;
+            }
+            _ => {}
+        }
+    }
+
+    fn read_register(&mut self, addr: u16) -> u8 {
+        match addr {
+            0x5010 => self.audio.read_pcm_status(),
+            0x5015 => self.audio.read_status(),
+            0x5204 => {
+                let mut status = 0;
+                if self.irq_pending {
+                    status |= 0x80;
+                }
+                if self.in_frame {
+                    status |= 0x40;
+                }
+                self.irq_pending = false;
+                status
+            }
+            0x5205 => (self.multiplicand as u16 * self.multiplier as u16) as u8,
+            0x5206 => ((self.multiplicand as u16 * self.multiplier as u16) >> 8) as u8,
+            // ExRAM can only be read in modes 2 and 3
+            0x5C00..=0x5FFF if self.exram_mode >= 2 => self.exram[(addr - 0x5C00) as usize],
+            _ => 0,
+        }
+    }
+
+    /// Whether PPU reads should use the background CHR banks.
+    fn use_bg_chr_banks(&self) -> bool {
+        if self.tall_sprites {
+            self.in_frame && !self.sprite_fetches
+        } else {
+            self.bg_chr_written_last
+        }
+    }
+
+    fn chr_index(&self, addr: u16) -> usize {
+        let addr = addr as usize;
+
+        let offset = if let Some(bank) = self.exattr_bank {
+            // Extended attributes select a 4 KB bank per tile
+            bank * 0x1000 + (addr & 0x0FFF)
+        } else if let Some(fine_y) = self.split_fine_y {
+            // The split region has its own 4 KB bank and vertical scroll
+            let addr = (addr & 0x0FF8) | fine_y as usize;
+            self.split_chr_bank as usize * 0x1000 + addr
+        } else if self.use_bg_chr_banks() {
+            // The background set covers 4 KB, mirrored over both pattern tables
+            let size = CHR_BANK_SIZE >> self.chr_mode;
+            if self.chr_mode == 0 {
+                self.bg_chr_banks[3] as usize * size + addr
+            } else {
+                let slot = (addr & 0x0FFF) / size;
+                let register = (slot + 1) * (4 >> (self.chr_mode - 1)) - 1;
+                self.bg_chr_banks[register] as usize * size + (addr & (size - 1))
+            }
+        } else {
+            let size = CHR_BANK_SIZE >> self.chr_mode;
+            let slot = addr / size;
+            let register = (slot + 1) * (8 >> self.chr_mode) - 1;
+            self.sprite_chr_banks[register] as usize * size + (addr & (size - 1))
+        };
+
+        offset % self.cart.chr.len()
+    }
+
+    /// Source of nametable `nametable` (0-3) selected by $5105:
+    /// 0-1 = console VRAM page, 2 = ExRAM, 3 = fill mode.
+    fn nametable_source(&self, nametable: u16) -> u8 {
+        (self.nametable_mapping >> ((nametable & 0x03) * 2)) & 0x03
+    }
+
+    /// Called on the third identical nametable fetch in a row.
+    fn detect_scanline(&mut self) {
+        if self.in_frame {
+            self.scanline = self.scanline.wrapping_add(1);
+            if self.scanline == self.irq_scanline {
+                self.irq_pending = true;
+            }
+        } else {
+            self.in_frame = true;
+            self.scanline = 0;
+            self.irq_pending = false;
+        }
+        self.tile = TILE_AT_DETECTION;
+        self.prefetch = false;
+    }
+
+    /// Nametable or attribute byte for the current tile when it falls in the
+    /// split region, which is drawn from ExRAM with its own vertical scroll.
+    fn split_read(&mut self, is_attribute: bool) -> Option<u8> {
+        if self.split_control & 0x80 == 0 || self.exram_mode >= 2 || self.tile >= 32 {
+            return None;
+        }
+
+        let split_tile = self.split_control & 0x1F;
+        let in_split = if self.split_control & 0x40 != 0 {
+            self.tile >= split_tile
+        } else {
+            self.tile < split_tile
+        };
+        if !in_split {
+            return None;
+        }
+
+        let line = self.scanline as usize + self.prefetch as usize;
+        let y = (self.split_scroll as usize + line) % 240;
+        let x = self.tile as usize;
+
+        if is_attribute {
+            let attribute = self.exram[0x3C0 + (y / 32) * 8 + x / 4];
+            let shift = ((y / 16) & 1) * 4 + ((x / 2) & 1) * 2;
+            Some(((attribute >> shift) & 0x03) * 0x55)
+        } else {
+            self.split_fine_y = Some((y % 8) as u8);
+            Some(self.exram[(y / 8) * 32 + x])
+        }
+    }
+}
+
+impl Mapper for Mmc5 {
+    fn cpu_read(&mut self, addr: u16) -> u8 {
+        match addr {
+            0x5000..=0x5FFF => self.read_register(addr),
+            0x6000..=0x7FFF => {
+                let bank = (self.prg_banks[0] & 0x7F) as usize;
+                self.read_prg_ram(bank, addr as usize & (PRG_BANK_SIZE - 1))
+            }
+            0x8000..=0xFFFF => {
+                // The CPU fetching the NMI vector marks the end of the frame
+                if addr == 0xFFFA || addr == 0xFFFB {
+                    self.in_frame = false;
+                }
+
+                let value = self.read_prg(addr);
+                self.audio.observe_prg_read(addr, value);
+                value
+            }
+            _ => 0,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x5000..=0x5FFF => self.write_register(addr, value),
+            0x6000..=0x7FFF => {
+                let bank = (self.prg_banks[0] & 0x7F) as usize;
+                self.write_prg_ram(bank, addr as usize & (PRG_BANK_SIZE - 1), value);
+            }
+            0x8000..=0xDFFF => {
+                let (is_rom, bank) = self.prg_bank_for(addr);
+                if !is_rom {
+                    self.write_prg_ram(bank, addr as usize & (PRG_BANK_SIZE - 1), value);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, addr: u16) -> u8 {
+        self.cart.chr[self.chr_index(addr)]
+    }
+
+    fn ppu_write(&mut self, addr: u16, value: u8) {
+        let index = self.chr_index(addr);
+        self.cart.write_chr(index, value);
+    }
+
+    fn ppu_address(&mut self, addr: u16, ppu_dot: u64) {
+        if !(0x2000..=0x2FFF).contains(&addr) {
+            return;
+        }
+
+        if ppu_dot.wrapping_sub(self.last_nametable_dot) > IDLE_DOTS {
+            self.in_frame = false;
+        }
+        self.last_nametable_dot = ppu_dot;
+
+        // Attribute fetches don't count towards the scanline detection
+        if addr & 0x03FF >= 0x03C0 {
+            return;
+        }
+
+        if addr == self.last_nametable_addr {
+            self.nametable_repeats += 1;
+            if self.nametable_repeats == 2 {
+                self.detect_scanline();
+            }
+        } else {
+            self.last_nametable_addr = addr;
+            self.nametable_repeats = 0;
+            self.tile = (self.tile + 1) % TILES_PER_LINE;
+            if self.tile == 0 {
+                self.prefetch = true;
+            }
+        }
+    }
+
+    fn nametable_page(&self, nametable: u16) -> usize {
+        match self.nametable_source(nametable) {
+            0 => 0,
+            _ => 1,
+        }
+    }
+
+    fn nametable_read(&mut self, addr: u16) -> Option<u8> {
+        let offset = (addr & 0x03FF) as usize;
+        let is_attribute = offset >= 0x3C0;
+        let rendering = self.in_frame && !self.sprite_fetches;
+
+        if rendering {
+            if !is_attribute {
+                self.split_fine_y = None;
+                self.exattr_bank = None;
+            }
+
+            if let Some(value) = self.split_read(is_attribute) {
+                return Some(value);
+            }
+
+            // Extended attributes: each tile's ExRAM byte holds its palette
+            // (bits 6-7) and 4 KB CHR bank (bits 0-5)
+            if self.exram_mode == 1 {
+                if is_attribute {
+                    let ex = self.exram[self.last_nametable_addr as usize & 0x03FF];
+                    return Some((ex >> 6) * 0x55);
+                }
+                let ex = self.exram[offset];
+                self.exattr_bank = Some(((self.chr_upper as usize) << 6) | (ex & 0x3F) as usize);
+            }
+        }
+
+        match self.nametable_source(addr >> 10) {
+            0 | 1 => None,
+            // ExRAM only works as a nametable in modes 0 and 1
+            2 if self.exram_mode < 2 => Some(self.exram[offset]),
+            2 => Some(0),
+            _ if is_attribute => Some(self.fill_attribute * 0x55),
+            _ => Some(self.fill_tile),
+        }
+    }
+
+    fn nametable_write(&mut self, addr: u16, value: u8) -> bool {
+        match self.nametable_source(addr >> 10) {
+            0 | 1 => false,
+            2 => {
+                if self.exram_mode < 2 {
+                    self.exram[(addr & 0x03FF) as usize] = value;
+                }
+                true
+            }
+            _ => true,
+        }
+    }
+
+    fn ppu_sprite_fetches(&mut self, active: bool) {
+        self.sprite_fetches = active;
+        self.split_fine_y = None;
+        self.exattr_bank = None;
+    }
+
+    fn ppu_register_write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x2000 => self.tall_sprites = value & 0x20 != 0,
+            // Rendering disabled: the PPU stops fetching
+            0x2001 if value & 0x18 == 0 => self.in_frame = false,
+            _ => {}
+        }
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        match self.nametable_mapping {
+            0x44 => Mirroring::Vertical,
+            0x50 => Mirroring::Horizontal,
+            0x55 => Mirroring::SingleScreenUpper,
+            _ => Mirroring::SingleScreenLower,
+        }
+    }
+
+    fn cartridge(&self) -> &Cartridge {
+        &self.cart
+    }
+
+    fn cartridge_mut(&mut self) -> &mut Cartridge {
+        &mut self.cart
+    }
+
+    fn irq_pending(&self) -> bool {
+        (self.irq_pending && self.irq_enabled) || self.audio.irq_pending()
+    }
+
+    fn expansion_audio(&mut self) -> Option<&mut dyn ExpansionAudio> {
+        Some(&mut self.audio)
+    }
+}
@@ -0,0 +1,204 @@
+use crate::nes::apu::expansion::ExpansionAudio;
+use crate::nes::apu::n163::N163Audio;
+use crate::nes::cartridge::{Cartridge, Mirroring};
+
+use super::Mapper;
+
+const PRG_BANK_SIZE: usize = 0x2000;
+const CHR_BANK_SIZE: usize = 0x0400;
+
+/// Bank numbers from $E0 up select a page of console VRAM instead of CHR ROM
+const CIRAM_BANKS: u8 = 0xE0;
+
+/// IRQ counter value at which the IRQ fires and the counter stops
+const IRQ_COUNTER_MAX: u16 = 0x7FFF;
+
+/// Namco 129/163 (mapper 19)
+///
+/// $4800-$4FFF: Sound RAM data port
+/// $5000-$57FF: IRQ counter bits 0-7
+/// $5800-$5FFF: IRQ counter bits 8-14, IRQ enable (bit 7)
+/// $8000-$BFFF: CHR banks 0-7 (1 KB), one per $800
+/// $C000-$DFFF: Nametable banks 0-3 (1 KB), one per $800
+/// $E000-$E7FF: 8 KB PRG bank at $8000, sound disable (bit 6)
+/// $E800-$EFFF: 8 KB PRG bank at $A000
+/// $F000-$F7FF: 8 KB PRG bank at $C000
+/// $F800-$FFFF: PRG RAM write protect, sound RAM address
+///
+/// $E000-$FFFF is fixed to the last 8 KB PRG bank. Nametable banks below
+/// $E0 map CHR ROM into the nametables, and $E0-$FF pick a console VRAM page.
+/// The IRQ counter counts up every CPU cycle and fires when it reaches $7FFF.
+///
+/// CHR banks that select console VRAM through the $E800 bits are treated as
+/// CHR ROM banks, as the pattern table bus cannot reach console VRAM here.
+pub struct Namco163 {
+    cart: Cartridge,
+
+    prg_banks: [u8; 3],
+    chr_banks: [u8; 8],
+    nametable_banks: [u8; 4],
+    write_protect: u8,
+
+    irq_counter: u16,
+    irq_enabled: bool,
+    irq_pending: bool,
+
+    audio: N163Audio,
+}
+
+impl Namco163 {
+    pub fn new(cart: Cartridge) -> Self {
+        Namco163 {
+            cart,
+            prg_banks: [0; 3],
+            chr_banks: [0; 8],
+            nametable_banks: [CIRAM_BANKS; 4],
+            write_protect: 0,
+            irq_counter: 0,
+            irq_enabled: false,
+            irq_pending: false,
+            audio: N163Audio::new(),
+        }
+    }
+
+    /// $F800 must be $4x to enable writes, and each of bits 0-3 then
+    /// protects a 2 KB window of PRG RAM.
+    fn prg_ram_writable(&self, addr: u16) -> bool {
+        let window = (addr - 0x6000) >> 11;
+        self.write_protect & 0xF0 == 0x40 && self.write_protect & (1 << window) == 0
+    }
+
+    fn read_prg(&self, addr: u16) -> u8 {
+        if self.cart.prg_rom.is_empty() {
+            return 0;
+        }
+
+        let bank_count = (self.cart.prg_rom.len() / PRG_BANK_SIZE).max(1);
+        let bank = match addr {
+            0x8000..=0xDFFF => self.prg_banks[(addr as usize - 0x8000) / PRG_BANK_SIZE] as usize & 0x3F,
+            _ => bank_count - 1,
+        };
+        let offset = (bank % bank_count) * PRG_BANK_SIZE + (addr as usize & (PRG_BANK_SIZE - 1));
+        self.cart.prg_rom[offset % self.cart.prg_rom.len()]
+    }
+
+    fn chr_offset(&self, bank: u8, addr: u16) -> Option<usize> {
+        let chr_len = self.cart.chr.len();
+        if chr_len == 0 {
+            return None;
+        }
+
+        let offset = bank as usize * CHR_BANK_SIZE + (addr as usize & (CHR_BANK_SIZE - 1));
+        Some(offset % chr_len)
+    }
+
+    fn chr_index(&self, addr: u16) -> Option<usize> {
+        let bank = self.chr_banks[(addr as usize / CHR_BANK_SIZE) & 0x07];
+        self.chr_offset(bank, addr)
+    }
+
+    /// CHR ROM bank mapped into a nametable, if it is not console VRAM
+    fn nametable_rom_bank(&self, addr: u16) -> Option<u8> {
+        let bank = self.nametable_banks[((addr >> 10) & 0x03) as usize];
+        (bank < CIRAM_BANKS).then_some(bank)
+    }
+}
+
+impl Mapper for Namco163 {
+    fn cpu_read(&mut self, addr: u16) -> u8 {
+        match addr {
+            0x4800..=0x4FFF => self.audio.read_data(),
+            0x5000..=0x57FF => self.irq_counter as u8,
+            0x5800..=0x5FFF => (self.irq_counter >> 8) as u8 | if self.irq_enabled { 0x80 } else { 0 },
+            0x6000..=0x7FFF => self.cart.read_prg_ram(addr),
+            0x8000..=0xFFFF => self.read_prg(addr),
+            _ => 0,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x4800..=0x4FFF => self.audio.write_data(value),
+            0x5000..=0x57FF => {
+                self.irq_counter = (self.irq_counter & 0x7F00) | value as u16;
+                self.irq_pending = false;
+            }
+            0x5800..=0x5FFF => {
+                self.irq_counter = (self.irq_counter & 0x00FF) | ((value as u16 & 0x7F) << 8);
+                self.irq_enabled = value & 0x80 != 0;
+                self.irq_pending = false;
+            }
+            0x6000..=0x7FFF if self.prg_ram_writable(addr) => self.cart.write_prg_ram(addr, value),
+            0x8000..=0xBFFF => self.chr_banks[((addr - 0x8000) >> 11) as usize] = value,
+            0xC000..=0xDFFF => self.nametable_banks[((addr - 0xC000) >> 11) as usize] = value,
+            0xE000..=0xE7FF => {
+                self.prg_banks[0] = value & 0x3F;
+                self.audio.set_disabled(value & 0x40 != 0);
+            }
+            0xE800..=0xEFFF => self.prg_banks[1] = value & 0x3F,
+            0xF000..=0xF7FF => self.prg_banks[2] = value & 0x3F,
+            0xF800..=0xFFFF => {
+                self.write_protect = value;
+                self.audio.write_address(value);
+            }
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, addr: u16) -> u8 {
+        match self.chr_index(addr) {
+            Some(index) => self.cart.chr[index],
+            None => 0,
+        }
+    }
+
+    fn ppu_write(&mut self, addr: u16, value: u8) {
+        if let Some(index) = self.chr_index(addr) {
+            self.cart.write_chr(index, value);
+        }
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.cart.mirroring
+    }
+
+    fn nametable_page(&self, nametable: u16) -> usize {
+        (self.nametable_banks[(nametable & 0x03) as usize] & 0x01) as usize
+    }
+
+    fn nametable_read(&mut self, addr: u16) -> Option<u8> {
+        let bank = self.nametable_rom_bank(addr)?;
+        let index = self.chr_offset(bank, addr)?;
+        Some(self.cart.chr[index])
+    }
+
+    fn nametable_write(&mut self, addr: u16, _value: u8) -> bool {
+        // Nametables mapped to CHR ROM are read-only
+        self.nametable_rom_bank(addr).is_some()
+    }
+
+    fn cpu_tick(&mut self) {
+        if self.irq_enabled && self.irq_counter < IRQ_COUNTER_MAX {
+            self.irq_counter += 1;
+            if self.irq_counter == IRQ_COUNTER_MAX {
+                self.irq_pending = true;
+            }
+        }
+    }
+
+    fn expansion_audio(&mut self) -> Option<&mut dyn ExpansionAudio> {
+        Some(&mut self.audio)
+    }
+
+    fn cartridge(&self) -> &Cartridge {
+        &self.cart
+    }
+
+    fn cartridge_mut(&mut self) -> &mut Cartridge {
+        &mut self.cart
+    }
+
+    fn irq_pending(&self) -> bool {
+        self.irq_pending
+    }
+}
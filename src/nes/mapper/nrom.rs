@@ -0,0 +1,61 @@
+use crate::nes::cartridge::{Cartridge, Mirroring};
+
+use super::Mapper;
+
+/// NROM (mapper 0)
+///
+/// No bank switching. 16 KB PRG ROM is mirrored into both halves of
+/// $8000-$FFFF, 32 KB PRG ROM fills it. CHR is a fixed 8 KB bank of ROM or RAM.
+pub struct Nrom {
+    cart: Cartridge,
+}
+
+impl Nrom {
+    pub fn new(cart: Cartridge) -> Self {
+        Nrom { cart }
+    }
+}
+
+impl Mapper for Nrom {
+    fn cpu_read(&mut self, addr: u16) -> u8 {
+        match addr {
+            0x6000..=0x7FFF => self.cart.read_prg_ram(addr),
+            0x8000..=0xFFFF => {
+                let prg_len = self.cart.prg_rom.len();
+                if prg_len == 0 {
+                    return 0;
+                }
+                self.cart.prg_rom[(addr - 0x8000) as usize % prg_len]
+            }
+            _ => 0,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, value: u8) {
+        if let 0x6000..=0x7FFF = addr {
+            self.cart.write_prg_ram(addr, value);
+        }
+    }
+
+    fn ppu_read(&mut self, addr: u16) -> u8 {
+        self.cart.chr.get(addr as usize).copied().unwrap_or(0)
+    }
+
+    fn ppu_write(&mut self, addr: u16, value: u8) {
+        if (addr as usize) < self.cart.chr.len() {
+            self.cart.write_chr(addr as usize, value);
+        }
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.cart.mirroring
+    }
+
+    fn cartridge(&self) -> &Cartridge {
+        &self.cart
+    }
+
+    fn cartridge_mut(&mut self) -> &mut Cartridge {
+        &mut self.cart
+    }
+}
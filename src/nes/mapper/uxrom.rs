@@ -0,0 +1,93 @@
+use crate::nes::cartridge::{Cartridge, Mirroring};
+
+use super::Mapper;
+
+const PRG_BANK_SIZE: usize = 0x4000;
+
+/// UxROM (mapper 2)
+///
+/// $8000-$BFFF: Switchable 16 KB PRG bank
+/// $C000-$FFFF: Fixed to the last 16 KB PRG bank
+///
+/// Any write to $8000-$FFFF selects the bank. CHR is 8 KB, usually RAM.
+pub struct Uxrom {
+    cart: Cartridge,
+    prg_bank: u8,
+    bus_conflicts: bool,
+}
+
+impl Uxrom {
+    pub fn new(cart: Cartridge, bus_conflicts: bool) -> Self {
+        Uxrom {
+            cart,
+            prg_bank: 0,
+            bus_conflicts,
+        }
+    }
+
+    fn prg_bank_count(&self) -> usize {
+        (self.cart.prg_rom.len() / PRG_BANK_SIZE).max(1)
+    }
+
+    fn read_prg(&self, addr: u16) -> u8 {
+        if self.cart.prg_rom.is_empty() {
+            return 0;
+        }
+
+        let bank = if addr < 0xC000 {
+            self.prg_bank as usize % self.prg_bank_count()
+        } else {
+            self.prg_bank_count() - 1
+        };
+        let offset = bank * PRG_BANK_SIZE + (addr as usize & (PRG_BANK_SIZE - 1));
+        self.cart.prg_rom[offset % self.cart.prg_rom.len()]
+    }
+}
+
+impl Mapper for Uxrom {
+    fn cpu_read(&mut self, addr: u16) -> u8 {
+        match addr {
+            0x6000..=0x7FFF => self.cart.read_prg_ram(addr),
+            0x8000..=0xFFFF => self.read_prg(addr),
+            _ => 0,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x6000..=0x7FFF => self.cart.write_prg_ram(addr, value),
+            0x8000..=0xFFFF => {
+                // The ROM drives the data bus too, so the written value is
+                // ANDed with the byte at the same address
+                self.prg_bank = if self.bus_conflicts {
+                    value & self.read_prg(addr)
+                } else {
+                    value
+                };
+            }
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, addr: u16) -> u8 {
+        self.cart.chr.get(addr as usize).copied().unwrap_or(0)
+    }
+
+    fn ppu_write(&mut self, addr: u16, value: u8) {
+        if (addr as usize) < self.cart.chr.len() {
+            self.cart.write_chr(addr as usize, value);
+        }
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.cart.mirroring
+    }
+
+    fn cartridge(&self) -> &Cartridge {
+        &self.cart
+    }
+
+    fn cartridge_mut(&mut self) -> &mut Cartridge {
+        &mut self.cart
+    }
+}
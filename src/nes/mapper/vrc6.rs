@@ -0,0 +1,169 @@
+use crate::nes::apu::expansion::ExpansionAudio;
+use crate::nes::apu::vrc6::Vrc6Audio;
+use crate::nes::cartridge::{Cartridge, Mirroring};
+
+use super::vrc_irq::VrcIrq;
+use super::Mapper;
+
+const PRG_BANK_SIZE: usize = 0x2000;
+const CHR_BANK_SIZE: usize = 0x0400;
+
+/// Konami VRC6 (mapper 24: VRC6a, mapper 26: VRC6b)
+///
+/// $8000-$8003: 16 KB PRG bank at $8000
+/// $9000-$9003: Pulse 1, $9003: audio frequency control
+/// $A000-$A002: Pulse 2
+/// $B000-$B002: Sawtooth
+/// $B003:       PPU banking mode, mirroring and PRG RAM enable
+/// $C000-$C003: 8 KB PRG bank at $C000
+/// $D000-$D003: CHR banks 0-3 (1 KB)
+/// $E000-$E003: CHR banks 4-7 (1 KB)
+/// $F000:       IRQ latch     $F001: IRQ control     $F002: IRQ acknowledge
+///
+/// $E000-$FFFF is fixed to the last 8 KB PRG bank. VRC6b swaps CPU A0 and A1
+/// on the register lines.
+///
+/// Only PPU banking mode 0 with nametables in console VRAM is emulated,
+/// which is the configuration all three VRC6 games use.
+pub struct Vrc6 {
+    cart: Cartridge,
+    swap_lines: bool,
+
+    prg_16k: u8,
+    prg_8k: u8,
+    chr_banks: [u8; 8],
+    control: u8,
+
+    irq: VrcIrq,
+    audio: Vrc6Audio,
+}
+
+impl Vrc6 {
+    pub fn new(cart: Cartridge, swap_lines: bool) -> Self {
+        Vrc6 {
+            cart,
+            swap_lines,
+            prg_16k: 0,
+            prg_8k: 0,
+            chr_banks: [0; 8],
+            control: 0,
+            irq: VrcIrq::new(),
+            audio: Vrc6Audio::new(),
+        }
+    }
+
+    /// Map a CPU address to its register, undoing the VRC6b A0/A1 swap.
+    fn register(&self, addr: u16) -> u16 {
+        if self.swap_lines {
+            (addr & 0xF000) | ((addr & 0x01) << 1) | ((addr & 0x02) >> 1)
+        } else {
+            addr & 0xF003
+        }
+    }
+
+    fn prg_ram_enabled(&self) -> bool {
+        self.control & 0x80 != 0
+    }
+
+    fn read_prg(&self, addr: u16) -> u8 {
+        if self.cart.prg_rom.is_empty() {
+            return 0;
+        }
+
+        let bank_count = (self.cart.prg_rom.len() / PRG_BANK_SIZE).max(1);
+        let bank = match addr {
+            0x8000..=0xBFFF => (self.prg_16k as usize & 0x0F) * 2 + ((addr as usize >> 13) & 1),
+            0xC000..=0xDFFF => self.prg_8k as usize & 0x1F,
+            _ => bank_count - 1,
+        };
+        let offset = (bank % bank_count) * PRG_BANK_SIZE + (addr as usize & (PRG_BANK_SIZE - 1));
+        self.cart.prg_rom[offset % self.cart.prg_rom.len()]
+    }
+
+    fn chr_index(&self, addr: u16) -> Option<usize> {
+        let chr_len = self.cart.chr.len();
+        if chr_len == 0 {
+            return None;
+        }
+
+        let bank = self.chr_banks[(addr as usize / CHR_BANK_SIZE) & 0x07] as usize;
+        let offset = bank * CHR_BANK_SIZE + (addr as usize & (CHR_BANK_SIZE - 1));
+        Some(offset % chr_len)
+    }
+}
+
+impl Mapper for Vrc6 {
+    fn cpu_read(&mut self, addr: u16) -> u8 {
+        match addr {
+            0x6000..=0x7FFF if self.prg_ram_enabled() => self.cart.read_prg_ram(addr),
+            0x8000..=0xFFFF => self.read_prg(addr),
+            _ => 0,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, value: u8) {
+        if (0x6000..=0x7FFF).contains(&addr) {
+            if self.prg_ram_enabled() {
+                self.cart.write_prg_ram(addr, value);
+            }
+            return;
+        }
+
+        match self.register(addr) {
+            0x8000..=0x8003 => self.prg_16k = value,
+            reg @ (0x9000..=0x9003 | 0xA000..=0xA002 | 0xB000..=0xB002) => {
+                self.audio.write_register(reg, value)
+            }
+            0xB003 => self.control = value,
+            0xC000..=0xC003 => self.prg_8k = value,
+            reg @ 0xD000..=0xD003 => self.chr_banks[(reg & 0x03) as usize] = value,
+            reg @ 0xE000..=0xE003 => self.chr_banks[4 + (reg & 0x03) as usize] = value,
+            0xF000 => self.irq.write_latch(value),
+            0xF001 => self.irq.write_control(value),
+            0xF002 => self.irq.acknowledge(),
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, addr: u16) -> u8 {
+        match self.chr_index(addr) {
+            Some(index) => self.cart.chr[index],
+            None => 0,
+        }
+    }
+
+    fn ppu_write(&mut self, addr: u16, value: u8) {
+        if let Some(index) = self.chr_index(addr) {
+            self.cart.write_chr(index, value);
+        }
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        match (self.control >> 2) & 0x03 {
+            0 => Mirroring::Vertical,
+            1 => Mirroring::Horizontal,
+            2 => Mirroring::SingleScreenLower,
+            _ => Mirroring::SingleScreenUpper,
+        }
+    }
+
+    fn cpu_tick(&mut self) {
+        self.irq.tick();
+    }
+
+    fn expansion_audio(&mut self) -> Option<&mut dyn ExpansionAudio> {
+        Some(&mut self.audio)
+    }
+
+    fn cartridge(&self) -> &Cartridge {
+        &self.cart
+    }
+
+    fn cartridge_mut(&mut self) -> &mut Cartridge {
+        &mut self.cart
+    }
+
+    fn irq_pending(&self) -> bool {
+        self.irq.pending()
+    }
+}
@@ -0,0 +1,160 @@
+use crate::nes::apu::expansion::ExpansionAudio;
+use crate::nes::apu::vrc7::Vrc7Audio;
+use crate::nes::cartridge::{Cartridge, Mirroring};
+
+use super::vrc_irq::VrcIrq;
+use super::Mapper;
+
+const PRG_BANK_SIZE: usize = 0x2000;
+const CHR_BANK_SIZE: usize = 0x0400;
+
+/// Konami VRC7 (mapper 85)
+///
+/// $8000: 8 KB PRG bank at $8000     $8010: 8 KB PRG bank at $A000
+/// $9000: 8 KB PRG bank at $C000     $9010/$9030: Audio register select/data
+/// $A000-$D010: CHR banks 0-7 (1 KB), two per $1000 range
+/// $E000: Mirroring (bits 0-1), audio reset (bit 6), PRG RAM enable (bit 7)
+/// $E010: IRQ latch     $F000: IRQ control     $F010: IRQ acknowledge
+///
+/// $E000-$FFFF is fixed to the last 8 KB PRG bank. VRC7a boards use A4 to
+/// tell the register pairs apart and VRC7b boards use A3 ($8008, $E008, ...),
+/// so both are accepted.
+pub struct Vrc7 {
+    cart: Cartridge,
+
+    prg_banks: [u8; 3],
+    chr_banks: [u8; 8],
+    control: u8,
+
+    irq: VrcIrq,
+    audio: Vrc7Audio,
+}
+
+impl Vrc7 {
+    pub fn new(cart: Cartridge) -> Self {
+        Vrc7 {
+            cart,
+            prg_banks: [0; 3],
+            chr_banks: [0; 8],
+            control: 0,
+            irq: VrcIrq::new(),
+            audio: Vrc7Audio::new(),
+        }
+    }
+
+    fn prg_ram_enabled(&self) -> bool {
+        self.control & 0x80 != 0
+    }
+
+    fn read_prg(&self, addr: u16) -> u8 {
+        if self.cart.prg_rom.is_empty() {
+            return 0;
+        }
+
+        let bank_count = (self.cart.prg_rom.len() / PRG_BANK_SIZE).max(1);
+        let bank = match addr {
+            0x8000..=0xDFFF => self.prg_banks[(addr as usize - 0x8000) / PRG_BANK_SIZE] as usize & 0x3F,
+            _ => bank_count - 1,
+        };
+        let offset = (bank % bank_count) * PRG_BANK_SIZE + (addr as usize & (PRG_BANK_SIZE - 1));
+        self.cart.prg_rom[offset % self.cart.prg_rom.len()]
+    }
+
+    fn chr_index(&self, addr: u16) -> Option<usize> {
+        let chr_len = self.cart.chr.len();
+        if chr_len == 0 {
+            return None;
+        }
+
+        let bank = self.chr_banks[(addr as usize / CHR_BANK_SIZE) & 0x07] as usize;
+        let offset = bank * CHR_BANK_SIZE + (addr as usize & (CHR_BANK_SIZE - 1));
+        Some(offset % chr_len)
+    }
+}
+
+impl Mapper for Vrc7 {
+    fn cpu_read(&mut self, addr: u16) -> u8 {
+        match addr {
+            0x6000..=0x7FFF if self.prg_ram_enabled() => self.cart.read_prg_ram(addr),
+            0x8000..=0xFFFF => self.read_prg(addr),
+            _ => 0,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, value: u8) {
+        if (0x6000..=0x7FFF).contains(&addr) {
+            if self.prg_ram_enabled() {
+                self.cart.write_prg_ram(addr, value);
+            }
+            return;
+        }
+
+        let odd = addr & 0x0018 != 0;
+        match (addr & 0xF000, odd) {
+            (0x8000, false) => self.prg_banks[0] = value,
+            (0x8000, true) => self.prg_banks[1] = value,
+            (0x9000, false) => self.prg_banks[2] = value,
+            (0x9000, true) => {
+                if addr & 0x0020 != 0 {
+                    self.audio.write_data(value);
+                } else {
+                    self.audio.write_address(value);
+                }
+            }
+            (0xA000..=0xD000, _) => {
+                let slot = ((addr - 0xA000) >> 12) as usize * 2 + odd as usize;
+                self.chr_banks[slot] = value;
+            }
+            (0xE000, false) => {
+                self.control = value;
+                self.audio.set_reset(value & 0x40 != 0);
+            }
+            (0xE000, true) => self.irq.write_latch(value),
+            (0xF000, false) => self.irq.write_control(value),
+            (0xF000, true) => self.irq.acknowledge(),
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, addr: u16) -> u8 {
+        match self.chr_index(addr) {
+            Some(index) => self.cart.chr[index],
+            None => 0,
+        }
+    }
+
+    fn ppu_write(&mut self, addr: u16, value: u8) {
+        if let Some(index) = self.chr_index(addr) {
+            self.cart.write_chr(index, value);
+        }
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        match self.control & 0x03 {
+            0 => Mirroring::Vertical,
+            1 => Mirroring::Horizontal,
+            2 => Mirroring::SingleScreenLower,
+            _ => Mirroring::SingleScreenUpper,
+        }
+    }
+
+    fn cpu_tick(&mut self) {
+        self.irq.tick();
+    }
+
+    fn expansion_audio(&mut self) -> Option<&mut dyn ExpansionAudio> {
+        Some(&mut self.audio)
+    }
+
+    fn cartridge(&self) -> &Cartridge {
+        &self.cart
+    }
+
+    fn cartridge_mut(&mut self) -> &mut Cartridge {
+        &mut self.cart
+    }
+
+    fn irq_pending(&self) -> bool {
+        self.irq.pending()
+    }
+}
@@ -0,0 +1,87 @@
+/// Prescaler reload in scanline mode: 341 PPU dots, counted down by 3 each
+/// CPU cycle, so the counter is clocked every 113.67 CPU cycles.
+const PRESCALER_PERIOD: i16 = 341;
+
+/// Konami VRC IRQ counter, shared by the VRC6 and VRC7.
+///
+/// An 8-bit counter counts up from the latch and raises the IRQ when it
+/// overflows from $FF, reloading itself from the latch. It is clocked every
+/// CPU cycle in cycle mode, or through a prescaler that approximates one
+/// scanline in scanline mode, so the timer works with rendering turned off.
+pub struct VrcIrq {
+    latch: u8,
+    counter: u8,
+    prescaler: i16,
+    enabled: bool,
+    enable_after_ack: bool,
+    cycle_mode: bool,
+    pending: bool,
+}
+
+impl VrcIrq {
+    pub fn new() -> Self {
+        VrcIrq {
+            latch: 0,
+            counter: 0,
+            prescaler: PRESCALER_PERIOD,
+            enabled: false,
+            enable_after_ack: false,
+            cycle_mode: false,
+            pending: false,
+        }
+    }
+
+    pub fn write_latch(&mut self, value: u8) {
+        self.latch = value;
+    }
+
+    /// IRQ control: bit 0 re-enables on acknowledge, bit 1 enables,
+    /// bit 2 selects cycle mode. Enabling reloads the counter.
+    pub fn write_control(&mut self, value: u8) {
+        self.enable_after_ack = value & 0x01 != 0;
+        self.enabled = value & 0x02 != 0;
+        self.cycle_mode = value & 0x04 != 0;
+        self.pending = false;
+
+        if self.enabled {
+            self.counter = self.latch;
+            self.prescaler = PRESCALER_PERIOD;
+        }
+    }
+
+    pub fn acknowledge(&mut self) {
+        self.pending = false;
+        self.enabled = self.enable_after_ack;
+    }
+
+    /// Called once per CPU cycle.
+    pub fn tick(&mut self) {
+        if !self.enabled {
+            return;
+        }
+
+        if self.cycle_mode {
+            self.clock();
+            return;
+        }
+
+        self.prescaler -= 3;
+        if self.prescaler <= 0 {
+            self.prescaler += PRESCALER_PERIOD;
+            self.clock();
+        }
+    }
+
+    fn clock(&mut self) {
+        if self.counter == 0xFF {
+            self.counter = self.latch;
+            self.pending = true;
+        } else {
+            self.counter += 1;
+        }
+    }
+
+    pub fn pending(&self) -> bool {
+        self.pending
+    }
+}
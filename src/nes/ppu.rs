@@ -1,14 +1,17 @@
-mod addr_reg;
 mod ctrl_reg;
+mod loopy_reg;
 mod mask_reg;
 mod status_reg;
 
-use addr_reg::AddressRegister;
 use ctrl_reg::ControlRegister;
+use loopy_reg::LoopyRegisters;
 use mask_reg::MaskRegister;
 use status_reg::StatusRegister;
 
-use super::cartridge::Mirroring;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use super::mapper::Mapper;
 use super::renderer::frame::Frame;
 
 #[rustfmt::skip]
@@ -28,80 +31,221 @@ pub static SYSTEM_PALETTE: [(u8,u8,u8); 64] = [
    (0x99, 0xFF, 0xFC), (0xDD, 0xDD, 0xDD), (0x11, 0x11, 0x11), (0x11, 0x11, 0x11)
 ];
 
+/// Sprites the hardware can draw on one scanline (the size of secondary OAM).
+const SPRITES_PER_LINE: usize = 8;
+
+/// A sprite selected during evaluation, with its pattern row for the
+/// scanline it will be drawn on.
+#[derive(Clone, Copy)]
+struct ScanlineSprite {
+    x: u8,
+    attributes: u8,
+    pattern_lo: u8,
+    pattern_hi: u8,
+    is_sprite_zero: bool,
+}
+
+impl ScanlineSprite {
+    /// 2-bit color index of this sprite at screen column `x` (0 = transparent).
+    fn pixel(&self, x: usize) -> u8 {
+        let offset = x.wrapping_sub(self.x as usize);
+        if offset >= 8 {
+            return 0;
+        }
+
+        let flip_h = self.attributes & 0x40 != 0;
+        let bit = if flip_h { offset } else { 7 - offset };
+        let bit0 = (self.pattern_lo >> bit) & 1;
+        let bit1 = (self.pattern_hi >> bit) & 1;
+        (bit1 << 1) | bit0
+    }
+
+    fn palette(&self) -> u8 {
+        self.attributes & 0x03
+    }
+
+    fn is_behind_background(&self) -> bool {
+        self.attributes & 0x20 != 0
+    }
+}
+
 pub struct Ppu {
     mem: [u8; 0x800],       // 2 KB VRAM (nametables)
     palette: [u8; 0x20],    // 32 bytes palette RAM
-    chr_rom: Vec<u8>,       // CHR ROM from cartridge
     ctrl: ControlRegister,
     mask: MaskRegister,
-    addr: AddressRegister,
+    loopy: LoopyRegisters,  // Internal v/t/x/w scroll and address registers
     status: StatusRegister,
     oam_addr: u8,
     oam: [u8; 64 * 4],
     data_latch: u8,
+    io_latch: u8,           // Last value on the CPU-PPU data bus, read back from write-only registers
+
+    // Background fetch latches, filled over each 8-dot tile fetch
+    bg_next_tile: u8,
+    bg_next_attribute: u8,
+    bg_next_pattern_lo: u8,
+    bg_next_pattern_hi: u8,
 
-    scroll_x: u8,
-    scroll_y: u8,
-    scroll_latch: bool,     // false = first write (X), true = second write (Y)
+    // Background shift registers: the high byte is the tile being drawn,
+    // the low byte is the next tile
+    bg_pattern_lo: u16,
+    bg_pattern_hi: u16,
+    bg_attribute_lo: u16,
+    bg_attribute_hi: u16,
 
     cycles: usize,
     scanlines: usize,
+    dots: u64,              // Free-running dot counter, used by mappers to time A12 edges
+    odd_frame: bool,
+    warming_up: bool,       // $2000/$2001/$2005/$2006 writes are ignored until the pre-render line
+
+    // Sprites found by evaluation for the current and the next scanline,
+    // in OAM order (lowest index has the highest priority)
+    sprites: Vec<ScanlineSprite>,
+    next_sprites: Vec<ScanlineSprite>,
+    sprite_limit: bool,
 
     nmi_occurred: Option<u8>,
-    mirroring: Mirroring,
+    mapper: Rc<RefCell<dyn Mapper>>,
 
     frame: Frame,
     frame_ready: bool,
 }
 
 impl Ppu {
-    pub fn new(mirroring: Mirroring) -> Ppu {
+    pub fn new(mapper: Rc<RefCell<dyn Mapper>>) -> Ppu {
         Ppu {
             mem: [0; 0x800],
             palette: [0; 0x20],
-            chr_rom: Vec::new(),
             ctrl: ControlRegister::default(),
             mask: MaskRegister::default(),
-            addr: AddressRegister::default(),
+            loopy: LoopyRegisters::default(),
             status: StatusRegister::default(),
             oam_addr: 0,
             oam: [0; 64 * 4],
             data_latch: 0,
-            scroll_x: 0,
-            scroll_y: 0,
-            scroll_latch: false,
+            io_latch: 0,
+            bg_next_tile: 0,
+            bg_next_attribute: 0,
+            bg_next_pattern_lo: 0,
+            bg_next_pattern_hi: 0,
+            bg_pattern_lo: 0,
+            bg_pattern_hi: 0,
+            bg_attribute_lo: 0,
+            bg_attribute_hi: 0,
             cycles: 21,
             scanlines: 0,
+            dots: 0,
+            odd_frame: false,
+            warming_up: true,
+            sprites: Vec::with_capacity(SPRITES_PER_LINE),
+            next_sprites: Vec::with_capacity(SPRITES_PER_LINE),
+            sprite_limit: true,
             nmi_occurred: None,
-            mirroring,
+            mapper,
             frame: Frame::new(),
             frame_ready: false,
         }
     }
     
+    /// Enable or disable the 8-sprites-per-scanline limit. Without it every
+    /// sprite in range is drawn, which removes flicker in games that cycle
+    /// sprites to work around the limit. The overflow flag is unaffected.
+    pub fn set_sprite_limit(&mut self, enabled: bool) {
+        self.sprite_limit = enabled;
+    }
+
+    /// Console reset. PPUCTRL, PPUMASK, the scroll latches and the read
+    /// buffer are cleared, and like at power on the PPU ignores writes to
+    /// $2000, $2001, $2005 and $2006 until the end of the next vblank.
+    /// OAM, palette and VRAM keep their contents.
+    pub fn reset(&mut self) {
+        self.ctrl = ControlRegister::default();
+        self.mask = MaskRegister::default();
+        self.loopy.reset();
+        self.data_latch = 0;
+        self.odd_frame = false;
+        self.nmi_occurred = None;
+        self.warming_up = true;
+    }
+
     pub fn tick(&mut self, tick: u8) -> bool {
-        self.cycles += tick as usize;
-        if self.cycles >= 341 {
-            self.scanlines += 1;
-            self.cycles %= 341;
+        let mut frame_complete = false;
+        for _ in 0..tick {
+            frame_complete |= self.step();
+        }
+        frame_complete
+    }
 
+    /// Advance the PPU by a single dot.
+    /// Returns true when the pre-render line wraps around to a new frame.
+    fn step(&mut self) -> bool {
+        if self.is_rendering_line() && self.mask.is_rendering_enabled() {
+            self.fetch_background();
+
+            // Sprite evaluation for the next line completes by dot 256.
+            // The pre-render line never evaluates, so line 0 has no sprites.
+            if self.cycles == 257 && self.scanlines < 240 {
+                self.mapper.borrow_mut().ppu_sprite_fetches(true);
+                self.evaluate_sprites();
+                self.mapper.borrow_mut().ppu_sprite_fetches(false);
+            }
+
+            // Sprite pattern fetches for the next line start at dot 257.
+            // Mappers watching A12 (MMC3) rely on seeing this address.
+            if self.cycles == 257 {
+                let sprite_table = if self.ctrl.get_sprite_height() == 16 {
+                    0x1000
+                } else {
+                    self.ctrl.get_sprite_pattern_addr()
+                };
+                self.notify_mapper_address(sprite_table);
+            }
+        }
+
+        if self.scanlines < 240 && (1..=256).contains(&self.cycles) {
+            self.render_pixel();
+        }
+
+        if self.cycles == 1 {
             if self.scanlines == 241 {
                 self.status.set_vblank(true);
                 if self.ctrl.get_generate_nmi() {
                     self.nmi_occurred = Some(1);
                 }
 
-                // Render the frame at the start of vblank
-                self.render_frame();
                 self.frame_ready = true;
-            }
-
-            if self.scanlines >= 262 {
-                self.scanlines = 0;
+            } else if self.scanlines == 261 {
+                self.warming_up = false;
                 self.status.set_vblank(false);
                 self.status.set_sprite0_hit(false);
                 self.status.set_sprite_overflow(false);
                 self.nmi_occurred = None;
+            }
+        }
+
+        self.cycles += 1;
+        self.dots += 1;
+
+        // Odd frames skip the last dot of the pre-render line when rendering
+        if self.scanlines == 261
+            && self.cycles == 340
+            && self.odd_frame
+            && self.mask.is_rendering_enabled()
+        {
+            self.cycles = 341;
+        }
+
+        if self.cycles >= 341 {
+            self.cycles = 0;
+            self.scanlines += 1;
+            std::mem::swap(&mut self.sprites, &mut self.next_sprites);
+            self.next_sprites.clear();
+
+            if self.scanlines >= 262 {
+                self.scanlines = 0;
+                self.odd_frame = !self.odd_frame;
                 return true;
             }
         }
@@ -109,6 +253,15 @@ impl Ppu {
         false
     }
 
+    /// Visible scanlines and the pre-render line fetch pattern data.
+    fn is_rendering_line(&self) -> bool {
+        self.scanlines < 240 || self.scanlines == 261
+    }
+
+    fn notify_mapper_address(&self, addr: u16) {
+        self.mapper.borrow_mut().ppu_address(addr, self.dots);
+    }
+
     pub fn get_scanlines(&self) -> usize {
         self.scanlines
     }
@@ -117,43 +270,57 @@ impl Ppu {
         self.cycles
     }
 
-    pub fn load_chr(&mut self, chr: &Vec<u8>) {
-        self.chr_rom = chr.clone();
-    }
-
     pub fn ctrl(&mut self, arg: u8) {
+        if self.warming_up {
+            return;
+        }
         let before_nmi_status = self.ctrl.get_generate_nmi();
         self.ctrl.update(arg);
+        self.loopy.write_ctrl(arg);
         if !before_nmi_status && self.ctrl.get_generate_nmi() && self.status.get_vblank() {
             self.nmi_occurred = Some(1);
         }
     }
 
     pub fn mask(&mut self, arg: u8) {
+        if self.warming_up {
+            return;
+        }
         self.mask.update(arg);
     }
 
     pub fn status(&mut self) -> u8 {
+        // Only the top 3 bits are driven, the rest is open bus
+        self.status.set_open_bus(self.io_latch);
         let result = self.status.get();
         // Reading status clears vblank flag
         self.status.set_vblank(false);
-        // Reading status also resets the address latch
-        self.addr.reset_latch();
-        self.scroll_latch = false;
+        // Reading status also resets the shared $2005/$2006 write toggle
+        self.loopy.reset_latch();
         result
     }
 
+    pub fn get_io_latch(&self) -> u8 {
+        self.io_latch
+    }
+
+    pub fn set_io_latch(&mut self, value: u8) {
+        self.io_latch = value;
+    }
+
     pub fn scroll(&mut self, value: u8) {
-        if !self.scroll_latch {
-            self.scroll_x = value;
-        } else {
-            self.scroll_y = value;
+        if self.warming_up {
+            return;
         }
-        self.scroll_latch = !self.scroll_latch;
+        self.loopy.write_scroll(value);
     }
 
     pub fn addr(&mut self, value: u8) {
-        self.addr.write_byte(value);
+        if self.warming_up {
+            return;
+        }
+        self.loopy.write_addr(value);
+        self.notify_mapper_address(self.loopy.get());
     }
 
     /// Read from VRAM through the internal address bus.
@@ -161,18 +328,22 @@ impl Ppu {
     fn vram_read(&self, addr: u16) -> u8 {
         let addr = addr & 0x3FFF; // Mirror above 0x3FFF
         match addr {
-            // Pattern tables — read from CHR ROM
-            0x0000..=0x1FFF => {
-                if (addr as usize) < self.chr_rom.len() {
-                    self.chr_rom[addr as usize]
-                } else {
-                    0
-                }
-            }
+            // Pattern tables — read through the cartridge mapper
+            0x0000..=0x1FFF => self.mapper.borrow_mut().ppu_read(addr),
             // Nametables
             0x2000..=0x3EFF => {
-                let mirrored = self.mirror_nametable_addr(addr);
-                self.mem[mirrored]
+                if let Some(value) = self.mapper.borrow_mut().nametable_read(addr & 0x2FFF) {
+                    return value;
+                }
+
+                let (page, offset) = self.nametable_page(addr);
+                if page < 2 {
+                    self.mem[page * 0x400 + offset]
+                } else {
+                    let index = (page - 2) * 0x400 + offset;
+                    let mapper = self.mapper.borrow();
+                    mapper.cartridge().vram.get(index).copied().unwrap_or(0)
+                }
             }
             // Palette
             0x3F00..=0x3FFF => {
@@ -191,13 +362,22 @@ impl Ppu {
     fn vram_write(&mut self, addr: u16, value: u8) {
         let addr = addr & 0x3FFF;
         match addr {
-            0x0000..=0x1FFF => {
-                // CHR ROM — typically not writable, but CHR RAM would be
-                // For now, ignore writes to pattern tables
-            }
+            0x0000..=0x1FFF => self.mapper.borrow_mut().ppu_write(addr, value),
             0x2000..=0x3EFF => {
-                let mirrored = self.mirror_nametable_addr(addr);
-                self.mem[mirrored] = value;
+                if self.mapper.borrow_mut().nametable_write(addr & 0x2FFF, value) {
+                    return;
+                }
+
+                let (page, offset) = self.nametable_page(addr);
+                if page < 2 {
+                    self.mem[page * 0x400 + offset] = value;
+                } else {
+                    let index = (page - 2) * 0x400 + offset;
+                    let mut mapper = self.mapper.borrow_mut();
+                    if let Some(byte) = mapper.cartridge_mut().vram.get_mut(index) {
+                        *byte = value;
+                    }
+                }
             }
             0x3F00..=0x3FFF => {
                 let mut palette_addr = (addr - 0x3F00) & 0x1F;
@@ -210,58 +390,54 @@ impl Ppu {
         }
     }
 
-    /// Convert a nametable address (0x2000-0x3EFF) into a VRAM index (0x000-0x7FF)
-    /// based on the cartridge mirroring mode.
-    fn mirror_nametable_addr(&self, addr: u16) -> usize {
+    /// Resolve a nametable address (0x2000-0x3EFF) to a 1 KB page and an
+    /// offset within it, using the mirroring currently selected by the mapper.
+    /// Pages 0-1 are the console's 2 KB VRAM, pages 2-3 are cartridge VRAM.
+    fn nametable_page(&self, addr: u16) -> (usize, usize) {
         let addr = (addr - 0x2000) & 0x0FFF; // Wrap to 0x000-0xFFF range
         let nametable = addr / 0x400;         // Which nametable (0-3)
         let offset = addr % 0x400;            // Offset within nametable
 
-        let mirrored_table = match self.mirroring {
-            Mirroring::Horizontal => {
-                // NT0 and NT1 map to VRAM 0x000, NT2 and NT3 map to VRAM 0x400
-                match nametable {
-                    0 | 1 => 0,
-                    2 | 3 => 1,
-                    _ => 0,
-                }
-            }
-            Mirroring::Vertical => {
-                // NT0 and NT2 map to VRAM 0x000, NT1 and NT3 map to VRAM 0x400
-                match nametable {
-                    0 | 2 => 0,
-                    1 | 3 => 1,
-                    _ => 0,
-                }
-            }
-        };
+        let page = self.mapper.borrow().nametable_page(nametable);
+        (page, offset as usize)
+    }
 
-        (mirrored_table * 0x400 + offset as usize) as usize
+    /// Advance v after a $2007 access. While rendering, the PPU is already
+    /// stepping v itself and the access bumps both coarse X and Y instead.
+    fn increment_vram_addr(&mut self) {
+        if self.is_rendering_line() && self.mask.is_rendering_enabled() {
+            self.loopy.increment_x();
+            self.loopy.increment_y();
+        } else {
+            self.loopy.increment(self.ctrl.get_vram_increment());
+        }
     }
 
     pub fn data_read(&mut self) -> u8 {
-        let addr = self.addr.get();
+        let addr = self.loopy.get() & 0x3FFF;
+        self.notify_mapper_address(addr);
         let is_palette = matches!(addr, 0x3F00..=0x3FFF);
 
         if is_palette {
             // Palette reads are not buffered
             self.data_latch = self.vram_read(addr - 0x1000); // Latch gets the nametable byte "underneath"
             let result = self.vram_read(addr);
-            self.addr.increment(self.ctrl.get_vram_increment());
+            self.increment_vram_addr();
             result
         } else {
             // Non-palette reads are buffered (dummy read)
             let previous_data = self.data_latch;
             self.data_latch = self.vram_read(addr);
-            self.addr.increment(self.ctrl.get_vram_increment());
+            self.increment_vram_addr();
             previous_data
         }
     }
 
     pub fn data_write(&mut self, value: u8) {
-        let addr = self.addr.get();
+        let addr = self.loopy.get() & 0x3FFF;
+        self.notify_mapper_address(addr);
         self.vram_write(addr, value);
-        self.addr.increment(self.ctrl.get_vram_increment());
+        self.increment_vram_addr();
     }
 
     pub fn oam_addr(&mut self, value: u8) {
@@ -277,10 +453,6 @@ impl Ppu {
         self.oam_addr = self.oam_addr.wrapping_add(1);
     }
 
-    pub fn oam_dma(&mut self, data: &[u8; 256]) {
-        self.oam.copy_from_slice(data);
-    }
-
     pub fn get_nmi_occurred(&mut self) -> bool {
         self.nmi_occurred.take().is_some()
     }
@@ -297,116 +469,231 @@ impl Ppu {
 
     // ─── Rendering ───────────────────────────────────────────────────────
 
-    fn render_frame(&mut self) {
-        self.render_background();
-        self.render_sprites();
-    }
-
-    fn render_background(&mut self) {
-        let bg_pattern_base = self.ctrl.get_background_pattern_addr();
-        let nametable_base: u16 = self.ctrl.get_nametable_addr();
-
-        // Render the 32x30 tile grid
-        for tile_row in 0..30u16 {
-            for tile_col in 0..32u16 {
-                let nametable_addr = nametable_base + tile_row * 32 + tile_col;
-                let tile_index = self.vram_read(nametable_addr) as u16;
-
-                // Get the attribute byte for this tile's 2x2 metatile region
-                let attr_table_addr = nametable_base + 0x3C0 
-                    + (tile_row / 4) * 8 
-                    + (tile_col / 4);
-                let attr_byte = self.vram_read(attr_table_addr);
-                
-                // Each attribute byte covers a 4x4 tile area.
-                // Which 2x2 quadrant is this tile in?
-                let quadrant_x = (tile_col % 4) / 2;
-                let quadrant_y = (tile_row % 4) / 2;
-                let shift = (quadrant_y * 2 + quadrant_x) * 2;
-                let palette_idx = ((attr_byte >> shift) & 0x03) as u16;
-
-                // Render 8x8 pixel tile
-                let tile_addr = bg_pattern_base + tile_index * 16;
-                for row in 0..8u16 {
-                    let lo = self.vram_read(tile_addr + row);
-                    let hi = self.vram_read(tile_addr + row + 8);
-
-                    for col in (0..8u16).rev() {
-                        let bit0 = (lo >> col) & 1;
-                        let bit1 = (hi >> col) & 1;
-                        let color_idx = (bit1 << 1) | bit0;
-
-                        let palette_entry = if color_idx == 0 {
-                            // Universal background color
-                            self.vram_read(0x3F00)
-                        } else {
-                            self.vram_read(0x3F00 + palette_idx * 4 + color_idx as u16)
-                        };
-
-                        let color = SYSTEM_PALETTE[(palette_entry & 0x3F) as usize];
-                        let pixel_x = tile_col as usize * 8 + (7 - col as usize);
-                        let pixel_y = tile_row as usize * 8 + row as usize;
-                        self.frame.set_pixel(pixel_x, pixel_y, color);
-                    }
+    /// Background fetch pipeline. Every 8 dots the PPU fetches a nametable
+    /// byte, an attribute byte and two pattern bytes for the tile at v, then
+    /// moves v one tile to the right. Dots 321-336 prefetch the first two
+    /// tiles of the next line.
+    fn fetch_background(&mut self) {
+        let dot = self.cycles;
+
+        if (2..=257).contains(&dot) || (321..=337).contains(&dot) {
+            self.shift_background();
+
+            match (dot - 1) % 8 {
+                0 => {
+                    self.load_background_shifters();
+                    self.bg_next_tile = self.fetch(self.loopy.tile_addr());
                 }
+                2 => {
+                    let attribute = self.fetch(self.loopy.attribute_addr());
+                    self.bg_next_attribute = (attribute >> self.loopy.attribute_shift()) & 0x03;
+                }
+                4 => {
+                    let addr = self.bg_pattern_addr();
+                    self.bg_next_pattern_lo = self.fetch(addr);
+                }
+                6 => {
+                    let addr = self.bg_pattern_addr() + 8;
+                    self.bg_next_pattern_hi = self.fetch(addr);
+                }
+                7 => self.loopy.increment_x(),
+                _ => {}
             }
         }
+
+        match dot {
+            256 => self.loopy.increment_y(),
+            257 => {
+                self.load_background_shifters();
+                self.loopy.copy_x();
+            }
+            // Unused nametable fetches at the end of the line
+            338 | 340 => self.bg_next_tile = self.fetch(self.loopy.tile_addr()),
+            _ => {}
+        }
+
+        if self.scanlines == 261 && (280..=304).contains(&dot) {
+            self.loopy.copy_y();
+        }
     }
 
-    fn render_sprites(&mut self) {
-        let sprite_pattern_base = self.ctrl.get_sprite_pattern_addr();
+    fn bg_pattern_addr(&self) -> u16 {
+        self.ctrl.get_background_pattern_addr()
+            + self.bg_next_tile as u16 * 16
+            + self.loopy.get_fine_y()
+    }
+
+    /// Background fetch during rendering, visible to the mapper.
+    fn fetch(&mut self, addr: u16) -> u8 {
+        self.notify_mapper_address(addr);
+        self.vram_read(addr)
+    }
+
+    fn shift_background(&mut self) {
+        self.bg_pattern_lo <<= 1;
+        self.bg_pattern_hi <<= 1;
+        self.bg_attribute_lo <<= 1;
+        self.bg_attribute_hi <<= 1;
+    }
+
+    fn load_background_shifters(&mut self) {
+        self.bg_pattern_lo = (self.bg_pattern_lo & 0xFF00) | self.bg_next_pattern_lo as u16;
+        self.bg_pattern_hi = (self.bg_pattern_hi & 0xFF00) | self.bg_next_pattern_hi as u16;
+
+        // The attribute bits are the same for all 8 pixels of the tile
+        let attribute_lo = if self.bg_next_attribute & 0b01 != 0 { 0xFF } else { 0x00 };
+        let attribute_hi = if self.bg_next_attribute & 0b10 != 0 { 0xFF } else { 0x00 };
+        self.bg_attribute_lo = (self.bg_attribute_lo & 0xFF00) | attribute_lo;
+        self.bg_attribute_hi = (self.bg_attribute_hi & 0xFF00) | attribute_hi;
+    }
 
-        // Sprites with lower OAM indices have higher priority (drawn on top).
-        // Iterate in reverse so lower-index sprites overwrite higher-index ones.
-        for i in (0..64).rev() {
-            let oam_base = i * 4;
-            let y_pos = self.oam[oam_base] as u16;
-            let tile_index = self.oam[oam_base + 1] as u16;
-            let attributes = self.oam[oam_base + 2];
-            let x_pos = self.oam[oam_base + 3] as u16;
+    /// Output one pixel for the current dot, combining the background with
+    /// the highest priority opaque sprite.
+    fn render_pixel(&mut self) {
+        let x = self.cycles - 1;
+        let y = self.scanlines;
 
-            // Skip sprites at Y=0 or off-screen
-            if y_pos == 0 || y_pos >= 0xEF {
-                continue;
+        let mut pixel = 0u8;
+        let mut palette = 0u8;
+
+        if self.mask.get_show_background() && (x >= 8 || self.mask.get_show_leftmost_background()) {
+            let mux = 0x8000 >> self.loopy.get_fine_x();
+            let bit0 = (self.bg_pattern_lo & mux != 0) as u8;
+            let bit1 = (self.bg_pattern_hi & mux != 0) as u8;
+            pixel = (bit1 << 1) | bit0;
+
+            let pal0 = (self.bg_attribute_lo & mux != 0) as u8;
+            let pal1 = (self.bg_attribute_hi & mux != 0) as u8;
+            palette = (pal1 << 1) | pal0;
+        }
+
+        let sprite = self.sprite_pixel(x);
+
+        if let Some((sprite, sprite_pixel)) = sprite {
+            if pixel != 0 && sprite.is_sprite_zero && self.is_sprite_zero_hit(x) {
+                self.status.set_sprite0_hit(true);
             }
 
-            let palette_idx = (attributes & 0x03) as u16;
-            let flip_h = attributes & 0x40 != 0;
-            let flip_v = attributes & 0x80 != 0;
-            let behind_bg = attributes & 0x20 != 0;
+            // Sprites in front of the background, or over a transparent background pixel
+            if pixel == 0 || !sprite.is_behind_background() {
+                pixel = sprite_pixel;
+                palette = 4 + sprite.palette();
+            }
+        }
 
-            let tile_addr = sprite_pattern_base + tile_index * 16;
+        let palette_entry = if pixel == 0 {
+            // Universal background color
+            self.vram_read(0x3F00)
+        } else {
+            self.vram_read(0x3F00 + palette as u16 * 4 + pixel as u16)
+        };
 
-            for row in 0..8u16 {
-                let actual_row = if flip_v { 7 - row } else { row };
-                let lo = self.vram_read(tile_addr + actual_row);
-                let hi = self.vram_read(tile_addr + actual_row + 8);
+        self.frame.set_pixel(x, y, self.palette_color(palette_entry));
+    }
 
-                for col in (0..8u16).rev() {
-                    let actual_col = if flip_h { 7 - col } else { col };
-                    let bit0 = (lo >> actual_col) & 1;
-                    let bit1 = (hi >> actual_col) & 1;
-                    let color_idx = (bit1 << 1) | bit0;
+    /// First opaque sprite pixel at column `x`, with the sprite it belongs to.
+    fn sprite_pixel(&self, x: usize) -> Option<(ScanlineSprite, u8)> {
+        if !self.mask.get_show_sprites() || (x < 8 && !self.mask.get_show_leftmost_sprites()) {
+            return None;
+        }
 
-                    // Transparent pixel
-                    if color_idx == 0 {
-                        continue;
-                    }
+        self.sprites
+            .iter()
+            .map(|sprite| (*sprite, sprite.pixel(x)))
+            .find(|(_, pixel)| *pixel != 0)
+    }
 
-                    let palette_entry = self.vram_read(0x3F10 + palette_idx * 4 + color_idx as u16);
-                    let color = SYSTEM_PALETTE[(palette_entry & 0x3F) as usize];
+    /// Whether an opaque sprite 0 pixel over an opaque background pixel at
+    /// column `x` counts as a hit. There is no hit at x=255, or in the left
+    /// 8 pixels while either layer is clipped there.
+    fn is_sprite_zero_hit(&self, x: usize) -> bool {
+        if x == 255 {
+            return false;
+        }
+        x >= 8 || (self.mask.get_show_leftmost_background() && self.mask.get_show_leftmost_sprites())
+    }
 
-                    let pixel_x = (x_pos + 7 - col) as usize;
-                    let pixel_y = (y_pos + 1 + row) as usize; // OAM Y is off by 1
+    /// Find the sprites on the next scanline and fetch their pattern rows.
+    /// Only the first eight are kept unless the sprite limit is disabled.
+    ///
+    /// Once eight sprites are found the hardware keeps scanning OAM for
+    /// overflow, but it increments the byte index m along with the sprite
+    /// index n, so it compares tile, attribute and X bytes as if they were
+    /// Y coordinates. This produces both false positives and false negatives.
+    fn evaluate_sprites(&mut self) {
+        let line = self.scanlines;
+        let height = self.ctrl.get_sprite_height() as usize;
+        let in_range = |y: u8| line.wrapping_sub(y as usize) < height;
+
+        self.next_sprites.clear();
+
+        let mut n = 0;
+        while n < 64 && self.next_sprites.len() < SPRITES_PER_LINE {
+            if in_range(self.oam[n * 4]) {
+                let sprite = self.fetch_sprite(n, line);
+                self.next_sprites.push(sprite);
+            }
+            n += 1;
+        }
 
-                    if pixel_x < 256 && pixel_y < 240 {
-                        if !behind_bg {
-                            self.frame.set_pixel(pixel_x, pixel_y, color);
-                        }
-                        // TODO: behind_bg sprites should only show through transparent BG pixels
-                    }
+        if !self.sprite_limit {
+            for index in n..64 {
+                if in_range(self.oam[index * 4]) {
+                    let sprite = self.fetch_sprite(index, line);
+                    self.next_sprites.push(sprite);
                 }
             }
         }
+
+        let mut m = 0;
+        while n < 64 {
+            if in_range(self.oam[n * 4 + m]) {
+                self.status.set_sprite_overflow(true);
+                break;
+            }
+            n += 1;
+            m = (m + 1) & 0x03;
+        }
+    }
+
+    /// Fetch the pattern row of OAM entry `index` for the line after `line`.
+    fn fetch_sprite(&self, index: usize, line: usize) -> ScanlineSprite {
+        let y = self.oam[index * 4] as usize;
+        let tile = self.oam[index * 4 + 1] as u16;
+        let attributes = self.oam[index * 4 + 2];
+        let x = self.oam[index * 4 + 3];
+
+        let height = self.ctrl.get_sprite_height();
+        let mut row = (line - y) as u16;
+        if attributes & 0x80 != 0 {
+            row = height - 1 - row;
+        }
+
+        // 8x16 sprites take the pattern table from bit 0 of the tile index
+        // and use two consecutive tiles for the top and bottom halves.
+        let tile_addr = if height == 16 {
+            let table = if tile & 0x01 != 0 { 0x1000 } else { 0x0000 };
+            let tile = (tile & 0xFE) + row / 8;
+            table + tile * 16 + row % 8
+        } else {
+            self.ctrl.get_sprite_pattern_addr() + tile * 16 + row
+        };
+
+        ScanlineSprite {
+            x,
+            attributes,
+            pattern_lo: self.vram_read(tile_addr),
+            pattern_hi: self.vram_read(tile_addr + 8),
+            is_sprite_zero: index == 0,
+        }
+    }
+
+    fn palette_color(&self, palette_entry: u8) -> (u8, u8, u8) {
+        let index = if self.mask.get_greyscale() {
+            palette_entry & 0x30
+        } else {
+            palette_entry & 0x3F
+        };
+        SYSTEM_PALETTE[index as usize]
     }
 }
@@ -1,7 +1,6 @@
 use crate::nes::cpu::Addr;
 
 pub struct ControlRegister {
-    nametable_addr: Addr,
     sprite_pattern_addr: Addr,
     backgroung_pattern_addr: Addr,
     vram_increment: VramIncrement,
@@ -28,7 +27,6 @@ enum PpuSelect {
 impl Default for ControlRegister {
     fn default() -> Self {
         ControlRegister {
-            nametable_addr: 0,
             sprite_pattern_addr: 0,
             backgroung_pattern_addr: 0,
             vram_increment: VramIncrement::Add1,
@@ -57,13 +55,7 @@ impl ControlRegister {
         // |          (0: read backdrop from EXT pins; 1: output color on EXT pins)
         // +--------- Generate an NMI at the start of the
         //            vertical blanking interval (0: off; 1: on)
-        self.nametable_addr = match arg & 0b11 {
-            0 => 0x2000 as Addr,
-            1 => 0x2400 as Addr,
-            2 => 0x2800 as Addr,
-            3 => 0x2C00 as Addr,
-            _ => panic!("Unexpected error, all bits besides 0 and 1 should've been 0"),
-        };
+        // The nametable select bits go straight into the PPU's t register.
 
         self.vram_increment = if arg & 0b100 != 0 {
             VramIncrement::Add32
@@ -96,9 +88,6 @@ impl ControlRegister {
         };
 
         self.generate_nmi = arg & 0b1000_0000 != 0;
-
-        let scroll_x = if arg & 0b1 != 0 { 256 } else { 0 };
-        let scroll_y = if arg & 0b10 != 0 { 240 } else { 0 };
     }
 
     pub fn get_vram_increment(&self) -> u8 {
@@ -112,10 +101,6 @@ impl ControlRegister {
         self.generate_nmi
     }
 
-    pub fn get_nametable_addr(&self) -> u16 {
-        self.nametable_addr
-    }
-
     pub fn get_sprite_pattern_addr(&self) -> u16 {
         self.sprite_pattern_addr
     }
@@ -123,4 +108,11 @@ impl ControlRegister {
     pub fn get_background_pattern_addr(&self) -> u16 {
         self.backgroung_pattern_addr
     }
+
+    pub fn get_sprite_height(&self) -> u16 {
+        match self.sprite_size {
+            SpriteSize::Size8x8 => 8,
+            SpriteSize::Size8x16 => 16,
+        }
+    }
 }
@@ -0,0 +1,145 @@
+use crate::nes::cpu::Addr;
+
+/// PPU internal scroll/address registers, as documented by loopy.
+///
+/// v and t are 15-bit VRAM addresses laid out as:
+///
+/// yyy NN YYYYY XXXXX
+/// ||| || ||||| +++++-- coarse X scroll
+/// ||| || +++++-------- coarse Y scroll
+/// ||| ++-------------- nametable select
+/// +++----------------- fine Y scroll
+///
+/// During rendering v is the address of the tile being fetched, and t holds
+/// the scroll position written through $2000/$2005/$2006 until it is copied
+/// into v. x is the fine X scroll and w is the shared $2005/$2006 write toggle.
+#[derive(Default)]
+pub struct LoopyRegisters {
+    v: Addr,
+    t: Addr,
+    x: u8,
+    w: bool,
+}
+
+impl LoopyRegisters {
+    /// Current VRAM address.
+    pub fn get(&self) -> Addr {
+        self.v
+    }
+
+    pub fn get_fine_x(&self) -> u8 {
+        self.x
+    }
+
+    pub fn get_fine_y(&self) -> u16 {
+        (self.v >> 12) & 0x07
+    }
+
+    /// $2000 write: t: ...GH.. ........ <- d: ......GH
+    pub fn write_ctrl(&mut self, value: u8) {
+        self.t = (self.t & !0x0C00) | ((value as u16 & 0x03) << 10);
+    }
+
+    /// $2002 read: w <- 0
+    pub fn reset_latch(&mut self) {
+        self.w = false;
+    }
+
+    /// Console reset: t, x and w are cleared, v is left alone.
+    pub fn reset(&mut self) {
+        self.t = 0;
+        self.x = 0;
+        self.w = false;
+    }
+
+    /// $2005 write.
+    /// First write:  t: ....... ...ABCDE <- d: ABCDE...
+    ///               x:              FGH <- d: .....FGH
+    /// Second write: t: FGH..AB CDE..... <- d: ABCDEFGH
+    pub fn write_scroll(&mut self, value: u8) {
+        if !self.w {
+            self.t = (self.t & !0x001F) | (value as u16 >> 3);
+            self.x = value & 0x07;
+        } else {
+            self.t = (self.t & !0x73E0)
+                | ((value as u16 & 0x07) << 12)
+                | ((value as u16 & 0xF8) << 2);
+        }
+        self.w = !self.w;
+    }
+
+    /// $2006 write.
+    /// First write:  t: .CDEFGH ........ <- d: ..CDEFGH (bit 14 cleared)
+    /// Second write: t: ....... ABCDEFGH <- d: ABCDEFGH, then v <- t
+    pub fn write_addr(&mut self, value: u8) {
+        if !self.w {
+            self.t = (self.t & 0x00FF) | ((value as u16 & 0x3F) << 8);
+        } else {
+            self.t = (self.t & 0xFF00) | value as u16;
+            self.v = self.t;
+        }
+        self.w = !self.w;
+    }
+
+    /// $2007 access outside of rendering: add 1 or 32 to v.
+    pub fn increment(&mut self, increment: u8) {
+        self.v = self.v.wrapping_add(increment as u16) & 0x7FFF;
+    }
+
+    /// Move v to the next tile horizontally, wrapping into the
+    /// neighbouring nametable after coarse X 31.
+    pub fn increment_x(&mut self) {
+        if self.v & 0x001F == 31 {
+            self.v &= !0x001F;
+            self.v ^= 0x0400;
+        } else {
+            self.v += 1;
+        }
+    }
+
+    /// Move v to the next pixel row, wrapping into the neighbouring
+    /// nametable after coarse Y 29 (rows 30 and 31 hold attributes).
+    pub fn increment_y(&mut self) {
+        if self.v & 0x7000 != 0x7000 {
+            self.v += 0x1000;
+            return;
+        }
+
+        self.v &= !0x7000;
+        let mut coarse_y = (self.v & 0x03E0) >> 5;
+        if coarse_y == 29 {
+            coarse_y = 0;
+            self.v ^= 0x0800;
+        } else if coarse_y == 31 {
+            coarse_y = 0;
+        } else {
+            coarse_y += 1;
+        }
+        self.v = (self.v & !0x03E0) | (coarse_y << 5);
+    }
+
+    /// Dot 257: v: ....A.. ...BCDEF <- t: ....A.. ...BCDEF
+    pub fn copy_x(&mut self) {
+        self.v = (self.v & !0x041F) | (self.t & 0x041F);
+    }
+
+    /// Pre-render dots 280-304: v: GHIA.BC DEF..... <- t: GHIA.BC DEF.....
+    pub fn copy_y(&mut self) {
+        self.v = (self.v & !0x7BE0) | (self.t & 0x7BE0);
+    }
+
+    /// Nametable byte address for the tile at v.
+    pub fn tile_addr(&self) -> Addr {
+        0x2000 | (self.v & 0x0FFF)
+    }
+
+    /// Attribute byte address for the tile at v.
+    pub fn attribute_addr(&self) -> Addr {
+        0x23C0 | (self.v & 0x0C00) | ((self.v >> 4) & 0x38) | ((self.v >> 2) & 0x07)
+    }
+
+    /// Shift needed to pick this tile's 2-bit palette out of its attribute byte.
+    pub fn attribute_shift(&self) -> u8 {
+        (((self.v >> 4) & 0x04) | (self.v & 0x02)) as u8
+    }
+}
@@ -47,4 +47,28 @@ impl MaskRegister {
         self.emphasize_green = arg & 0b100_0000 != 0;
         self.emphasize_blue = arg & 0b1000_0000 != 0;
     }
+
+    pub fn get_greyscale(&self) -> bool {
+        self.greyscale
+    }
+
+    pub fn get_show_background(&self) -> bool {
+        self.show_background
+    }
+
+    pub fn get_show_leftmost_background(&self) -> bool {
+        self.show_leftmost_background
+    }
+
+    pub fn get_show_sprites(&self) -> bool {
+        self.show_sprites
+    }
+
+    pub fn get_show_leftmost_sprites(&self) -> bool {
+        self.show_leftmost_sprites
+    }
+
+    pub fn is_rendering_enabled(&self) -> bool {
+        self.show_background || self.show_sprites
+    }
 }
@@ -11,6 +11,18 @@ use sdl2::keyboard::Keycode;
 use sdl2::pixels::PixelFormatEnum;
 use sdl2::{self, render::Canvas, video::Window, EventPump};
 
+/// Frontend input collected from SDL2 events.
+pub enum Input {
+    /// Joypad button pressed (true) or released (false)
+    Button(JoypadButton, bool),
+    /// F1: press the console's reset button
+    Reset,
+    /// F2: switch the console off and on again
+    PowerCycle,
+    /// F3: eject the disk and insert the next side
+    SwitchDiskSide,
+}
+
 /// SDL2 audio callback that reads from the shared sample buffer
 struct NesAudioCallback {
     buffer: Arc<Mutex<Vec<f32>>>,
@@ -105,8 +117,8 @@ impl Renderer {
     }
 
     /// Poll SDL2 events. Returns `None` if the user wants to quit,
-    /// or `Some(Vec)` of (button, pressed) pairs for joypad updates.
-    pub fn poll_events(&mut self) -> Option<Vec<(JoypadButton, bool)>> {
+    /// or `Some(Vec)` of joypad updates and frontend commands.
+    pub fn poll_events(&mut self) -> Option<Vec<Input>> {
         let mut key_events = Vec::new();
 
         for event in self.event_pump.poll_iter() {
@@ -117,15 +129,33 @@ impl Renderer {
                     ..
                 } => return None,
 
+                Event::KeyDown {
+                    keycode: Some(Keycode::F1),
+                    repeat: false,
+                    ..
+                } => key_events.push(Input::Reset),
+
+                Event::KeyDown {
+                    keycode: Some(Keycode::F2),
+                    repeat: false,
+                    ..
+                } => key_events.push(Input::PowerCycle),
+
+                Event::KeyDown {
+                    keycode: Some(Keycode::F3),
+                    repeat: false,
+                    ..
+                } => key_events.push(Input::SwitchDiskSide),
+
                 Event::KeyDown { keycode: Some(key), .. } => {
                     if let Some(button) = keycode_to_button(key) {
-                        key_events.push((button, true));
+                        key_events.push(Input::Button(button, true));
                     }
                 }
 
                 Event::KeyUp { keycode: Some(key), .. } => {
                     if let Some(button) = keycode_to_button(key) {
-                        key_events.push((button, false));
+                        key_events.push(Input::Button(button, false));
                     }
                 }
 
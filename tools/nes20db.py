@@ -0,0 +1,101 @@
+#!/usr/bin/env python3
+"""Generate src/nes/cartridge/database/games.rs from the NES 2.0 XML database.
+
+Usage: tools/nes20db.py nes20db.xml [more.xml ...]
+
+The XML is maintained on the nesdev forums ("NES 2.0 XML Database"). Every
+<game> has a <rom> element with the CRC32 and SHA-1 of its PRG ROM followed
+by its CHR ROM, a <pcb> element with the board settings and a <console>
+element with the region. The name is taken from the comment in each game.
+
+tools/known_dumps.xml holds the entries for the ROMs in testroms/. When a
+CRC32 appears in more than one file, the first file given wins.
+"""
+
+import os
+import sys
+import xml.etree.ElementTree as ET
+
+OUTPUT = os.path.join(os.path.dirname(__file__), "..", "src", "nes", "cartridge", "database", "games.rs")
+
+MIRRORING = {
+    "H": "Mirroring::Horizontal",
+    "V": "Mirroring::Vertical",
+    "4": "Mirroring::FourScreen",
+}
+
+TIMING = {
+    "0": "Timing::Ntsc",
+    "1": "Timing::Pal",
+    "2": "Timing::MultiRegion",
+    "3": "Timing::Dendy",
+}
+
+
+def game_name(game):
+    for child in game:
+        if child.tag is ET.Comment:
+            path = child.text.strip().replace("\\", "/")
+            return os.path.splitext(path.rsplit("/", 1)[-1])[0]
+    return "Unknown"
+
+
+def rust_string(text):
+    return '"' + text.replace("\\", "\\\\").replace('"', '\\"') + '"'
+
+
+def main():
+    if len(sys.argv) < 2:
+        sys.exit(__doc__)
+
+    games = []
+    for path in sys.argv[1:]:
+        parser = ET.XMLParser(target=ET.TreeBuilder(insert_comments=True))
+        games.extend(ET.parse(path, parser).getroot().iter("game"))
+
+    entries = {}
+    skipped = 0
+    for game in games:
+        rom, pcb, console = game.find("rom"), game.find("pcb"), game.find("console")
+        if rom is None or pcb is None:
+            skipped += 1
+            continue
+
+        mirroring = MIRRORING.get(pcb.get("mirroring", "H"))
+        timing = TIMING.get(console.get("region", "0") if console is not None else "0")
+        crc = int(rom.get("crc32"), 16)
+        if mirroring is None or timing is None or crc in entries:
+            skipped += 1
+            continue
+
+        sha1 = bytes.fromhex(rom.get("sha1"))
+        entries[crc] = (
+            "GameInfo{name: %s, sha1: [%s], mapper: %d, submapper: %d, mirroring: %s, has_battery: %s, timing: %s}"
+            % (
+                rust_string(game_name(game)),
+                ", ".join("0x%02X" % b for b in sha1),
+                int(pcb.get("mapper", "0")),
+                int(pcb.get("submapper", "0")),
+                mirroring,
+                "true" if pcb.get("battery", "0") == "1" else "false",
+                timing,
+            )
+        )
+
+    with open(OUTPUT, "w", newline="\n") as out:
+        out.write("// Generated by tools/nes20db.py from the NES 2.0 XML database. Do not edit.\n\n")
+        out.write("use phf::phf_map;\n\n")
+        if entries:
+            out.write("use super::super::{Mirroring, Timing};\n")
+        out.write("use super::GameInfo;\n\n")
+        out.write("#[rustfmt::skip]\n")
+        out.write("pub static GAMES: phf::Map<u32, GameInfo> = phf_map! {\n")
+        for crc in sorted(entries):
+            out.write("    0x%08Xu32 => %s,\n" % (crc, entries[crc]))
+        out.write("};\n")
+
+    print("%d games written, %d skipped" % (len(entries), skipped))
+
+
+if __name__ == "__main__":
+    main()